@@ -0,0 +1,1120 @@
+//! Prints a syntax tree back out as consistently formatted SCSS
+//!
+//! Formatting is idempotent: formatting already formatted source returns it
+//! unchanged. Formatting also never changes the compiled CSS, so comments are
+//! never moved across code: silent comments at the end of a statement are
+//! moved after its semicolon or opening brace, and any other code containing
+//! comments is written exactly as it appears in the source.
+
+use super::{
+    Arguments, BinaryOperator, ConfiguredVariable, Expression, ExpressionKind, Import,
+    Interpolation, InterpolationPart, ListSeparator, Parameters, Statement, StatementKind,
+    Stylesheet, UnaryOperator,
+};
+
+const INDENT: &str = "  ";
+
+pub(crate) fn format_stylesheet(source: &str, stylesheet: &Stylesheet) -> String {
+    let mut formatter = Formatter {
+        source,
+        comments: find_comments(source),
+        buf: String::new(),
+        indent: 0,
+    };
+
+    formatter.write_statements(&stylesheet.statements);
+
+    if !formatter.buf.is_empty() {
+        formatter.buf.push('\n');
+    }
+
+    formatter.buf
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+
+    /// The start and end offsets of every comment in the source
+    comments: Vec<(usize, usize)>,
+    buf: String,
+    indent: usize,
+}
+
+/// Finds every comment in `source`, skipping over strings and unquoted URLs
+fn find_comments(source: &str) -> Vec<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut comments = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                let quote = bytes[i];
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'\\' => i += 2,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(source.len(), |idx| i + idx);
+                let end = i + source[i..end].trim_end().len();
+                comments.push((i, end));
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..]
+                    .find("*/")
+                    .map_or(source.len(), |idx| i + idx + 4);
+                comments.push((i, end));
+                i = end;
+            }
+            b'(' if i >= 3
+                && source[i - 3..i].eq_ignore_ascii_case("url")
+                && !source[..i - 3]
+                    .chars()
+                    .next_back()
+                    .map_or(false, |c| c.is_alphanumeric() || c == '_') =>
+            {
+                let rest = source[i + 1..].trim_start();
+                let end = source[i..]
+                    .find(')')
+                    .map_or(source.len(), |idx| i + idx + 1);
+                // interpolation inside of a URL may itself contain comments
+                if rest.starts_with('"') || rest.starts_with('\'') || source[i..end].contains("#{")
+                {
+                    i += 1;
+                } else {
+                    i = end;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    comments
+}
+
+/// Whether `text` contains a `"` that is not escaped
+fn has_unescaped_double_quote(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Chooses the quote to wrap `text` in, preferring double quotes unless the
+/// text contains one
+fn quote_for<'a>(mut texts: impl Iterator<Item = &'a str>) -> char {
+    if texts.any(has_unescaped_double_quote) {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/// Replaces each `\'` in `text` with `'`
+fn unescape_single_quotes(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('\'') => buf.push('\''),
+                Some(next) => {
+                    buf.push(c);
+                    buf.push(next);
+                }
+                None => buf.push(c),
+            }
+        } else {
+            buf.push(c);
+        }
+    }
+
+    buf
+}
+
+fn quote(text: &str) -> String {
+    let quote = quote_for(std::iter::once(text));
+    format!("{}{}{}", quote, text, quote)
+}
+
+/// Collapses each run of whitespace in `text` into a single space, leaving
+/// strings and comments untouched
+///
+/// If `indent` is passed, commas outside of parentheses are not preceded by
+/// whitespace, and are followed by either a single space or, if they were
+/// followed by a newline, a newline and `indent`. Newlines in selector lists
+/// are preserved in the compiled CSS, so they are not removed.
+fn collapse_whitespace(text: &str, indent: Option<&str>) -> String {
+    let mut buf = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut depth = 0_usize;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                buf.push(c);
+                while let Some(next) = chars.next() {
+                    buf.push(next);
+                    if next == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            buf.push(escaped);
+                        }
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            '\\' => {
+                buf.push(c);
+                if let Some(escaped) = chars.next() {
+                    buf.push(escaped);
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                buf.push(c);
+                buf.extend(chars.next());
+                let mut prev = None;
+                for next in chars.by_ref() {
+                    buf.push(next);
+                    if prev == Some('*') && next == '/' {
+                        break;
+                    }
+                    prev = Some(next);
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                buf.push(c);
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                    buf.push(next);
+                }
+                // the end of a silent comment is significant
+                buf.push('\n');
+                while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            '(' | '[' => {
+                depth += 1;
+                buf.push(c);
+            }
+            ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                buf.push(c);
+            }
+            ',' if indent.is_some() && depth == 0 => {
+                let len = buf.trim_end_matches(' ').len();
+                buf.truncate(len);
+                buf.push(',');
+
+                let mut has_newline = false;
+                while let Some(c) = chars.peek().copied().filter(|c| c.is_whitespace()) {
+                    has_newline |= c == '\n';
+                    chars.next();
+                }
+
+                if has_newline {
+                    buf.push('\n');
+                    buf.push_str(indent.unwrap_or_default());
+                } else {
+                    buf.push(' ');
+                }
+            }
+            _ if c.is_whitespace() => {
+                while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if !buf.ends_with(char::is_whitespace) {
+                    buf.push(' ');
+                }
+            }
+            _ => buf.push(c),
+        }
+    }
+
+    buf
+}
+
+/// The block of `statement`, if it has exactly one
+fn body(statement: &Statement) -> Option<&[Statement]> {
+    match &statement.kind {
+        StatementKind::StyleRule { body, .. }
+        | StatementKind::Declaration { body, .. }
+        | StatementKind::Mixin { body, .. }
+        | StatementKind::Function { body, .. }
+        | StatementKind::Each { body, .. }
+        | StatementKind::For { body, .. }
+        | StatementKind::While { body, .. }
+        | StatementKind::AtRoot { body, .. }
+        | StatementKind::Media { body, .. }
+        | StatementKind::Supports { body, .. }
+        | StatementKind::AtRule {
+            body: Some(body), ..
+        }
+        | StatementKind::Include {
+            content: Some(body),
+            ..
+        } => Some(body),
+        _ => None,
+    }
+}
+
+/// Whether `statement` ends with a block rather than a semicolon
+fn has_block(statement: &Statement) -> bool {
+    match &statement.kind {
+        StatementKind::Declaration { value, body, .. } => value.is_none() || !body.is_empty(),
+        StatementKind::AtRule { body, .. } => body.is_some(),
+        StatementKind::Include { content, .. } => content.is_some(),
+        StatementKind::StyleRule { .. }
+        | StatementKind::Mixin { .. }
+        | StatementKind::Function { .. }
+        | StatementKind::If { .. }
+        | StatementKind::Each { .. }
+        | StatementKind::For { .. }
+        | StatementKind::While { .. }
+        | StatementKind::AtRoot { .. }
+        | StatementKind::Media { .. }
+        | StatementKind::Supports { .. } => true,
+        _ => false,
+    }
+}
+
+fn is_comment(statement: &Statement) -> bool {
+    matches!(
+        statement.kind,
+        StatementKind::SilentComment(..) | StatementKind::LoudComment(..)
+    )
+}
+
+impl<'a> Formatter<'a> {
+    fn source_of(&self, expr: &Expression) -> &'a str {
+        &self.source[expr.span.start..expr.span.end]
+    }
+
+    /// The offset of the first character at or after `pos` that is not
+    /// whitespace or a comment
+    fn skip_comments(&self, mut pos: usize) -> usize {
+        loop {
+            let rest = &self.source[pos..];
+            pos += rest.len() - rest.trim_start().len();
+
+            match self.comments.iter().find(|(start, _)| *start == pos) {
+                Some((_, end)) => pos = *end,
+                None => return pos,
+            }
+        }
+    }
+
+    /// The range of source text owned by `statement`, which for statements
+    /// ending in a semicolon includes any comments before the semicolon
+    fn extent(&self, statement: &Statement) -> (usize, usize) {
+        if has_block(statement) || is_comment(statement) {
+            (statement.span.start, statement.span.end)
+        } else {
+            (statement.span.start, self.skip_comments(statement.span.end))
+        }
+    }
+
+    /// The offset of the `{` that opens `body`, whose parent ends at `end`
+    fn open_brace(&self, body: &[Statement], end: usize) -> usize {
+        let mut pos = body.first().map_or(end - 1, |child| child.span.start);
+
+        loop {
+            pos = self.source[..pos].trim_end().len();
+
+            match self.comments.iter().find(|(_, end)| *end == pos) {
+                Some((start, _)) => pos = *start,
+                None => return pos - 1,
+            }
+        }
+    }
+
+    /// The offset of the `}` that closes `body`, which is opened at `open`
+    fn close_brace(&self, body: &[Statement], open: usize) -> usize {
+        let mut pos = body.last().map_or(open + 1, |child| self.extent(child).1);
+
+        loop {
+            pos = self.skip_comments(pos);
+
+            if self.source[pos..].starts_with(';') {
+                pos += 1;
+            } else {
+                return pos;
+            }
+        }
+    }
+
+    /// Splits the source text from `start` to `end` into the code, which ends
+    /// at the returned offset, and the silent comments that follow it
+    ///
+    /// Also returns whether the code itself contains any comments.
+    fn split_trailing_comments(&self, start: usize, end: usize) -> (usize, Vec<&'a str>, bool) {
+        let mut code_end = end;
+        let mut trailing = Vec::new();
+
+        loop {
+            code_end = self.source[..code_end].trim_end().len().max(start);
+
+            match self.comments.iter().find(|(comment_start, comment_end)| {
+                *comment_end == code_end
+                    && *comment_start >= start
+                    && self.source[*comment_start..].starts_with("//")
+            }) {
+                Some((comment_start, comment_end)) => {
+                    trailing.push(&self.source[*comment_start..*comment_end]);
+                    code_end = *comment_start;
+                }
+                None => break,
+            }
+        }
+
+        trailing.reverse();
+
+        let has_comments = self
+            .comments
+            .iter()
+            .any(|(comment_start, _)| *comment_start >= start && *comment_start < code_end);
+
+        (code_end, trailing, has_comments)
+    }
+
+    /// Whether the comment starting at `start` follows other code on the
+    /// same line
+    fn is_trailing_comment(&self, start: usize) -> bool {
+        let line_start = self.source[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        !self.source[line_start..start].trim().is_empty()
+    }
+
+    /// Whether there is at least one blank line between `start` and `end`
+    fn has_blank_line(&self, start: usize, end: usize) -> bool {
+        let mut lines: Vec<&str> = self.source[start..end].split('\n').collect();
+        lines.pop();
+        lines.iter().skip(1).any(|line| line.trim().is_empty())
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.buf.push_str(INDENT);
+        }
+    }
+
+    fn write_statements(&mut self, statements: &[Statement]) {
+        let mut prev_end = None;
+
+        for statement in statements {
+            if is_comment(statement)
+                && !self.buf.is_empty()
+                && self.is_trailing_comment(statement.span.start)
+            {
+                self.buf.push(' ');
+            } else {
+                if !self.buf.is_empty() {
+                    self.buf.push('\n');
+                }
+
+                if let Some(prev_end) = prev_end {
+                    if self.has_blank_line(prev_end, statement.span.start) {
+                        self.buf.push('\n');
+                    }
+                }
+
+                self.write_indent();
+            }
+
+            self.write_statement(statement);
+            prev_end = Some(self.extent(statement).1);
+        }
+    }
+
+    /// Writes silent comments that followed a statement's code, with the first
+    /// on the current line and the rest on their own lines
+    fn write_trailing_comments(&mut self, comments: &[&str]) {
+        for (idx, comment) in comments.iter().enumerate() {
+            if idx == 0 {
+                self.buf.push(' ');
+            } else {
+                self.buf.push('\n');
+                self.write_indent();
+            }
+            self.buf.push_str(comment);
+        }
+    }
+
+    /// Writes the code from `start` to `end`, followed by the rest of the
+    /// statement written by `rest`
+    ///
+    /// The code is formatted using `header`, unless it contains comments, in
+    /// which case it is written exactly as it appears in the source.
+    fn write_code(
+        &mut self,
+        start: usize,
+        end: usize,
+        header: impl FnOnce(&mut Self),
+        rest: impl FnOnce(&mut Self, Vec<&'a str>),
+    ) {
+        let (code_end, trailing, has_comments) = self.split_trailing_comments(start, end);
+
+        if has_comments {
+            self.buf.push_str(self.source[start..code_end].trim());
+        } else {
+            header(self);
+        }
+
+        rest(self, trailing);
+    }
+
+    fn write_statement(&mut self, statement: &Statement) {
+        let start = statement.span.start;
+
+        match &statement.kind {
+            StatementKind::SilentComment(..) | StatementKind::LoudComment(..) => {
+                self.write_header(statement);
+            }
+            StatementKind::AtRoot { query: None, body } if self.is_at_root_shorthand(statement) => {
+                if let StatementKind::StyleRule { selector, body } = &body[0].kind {
+                    let open = self.open_brace(body, statement.span.end);
+                    self.write_code(
+                        start,
+                        open,
+                        |this| {
+                            this.buf.push_str("@at-root ");
+                            this.write_raw(selector, true);
+                        },
+                        |this, trailing| this.write_block(body, &trailing),
+                    );
+                }
+            }
+            StatementKind::If { clauses, else_body } => {
+                let mut open = self.skip_comments(clauses[0].0.span.end);
+                self.write_code(
+                    start,
+                    open,
+                    |this| this.write_header(statement),
+                    |this, trailing| this.write_block(&clauses[0].1, &trailing),
+                );
+
+                let mut prev_body = &clauses[0].1;
+
+                for (condition, body) in &clauses[1..] {
+                    let clause_start = self.close_brace(prev_body, open) + 1;
+                    open = self.skip_comments(condition.span.end);
+                    self.buf.push(' ');
+                    self.write_code(
+                        clause_start,
+                        open,
+                        |this| {
+                            this.buf.push_str("@else if ");
+                            this.write_expression(condition);
+                        },
+                        |this, trailing| this.write_block(body, &trailing),
+                    );
+                    prev_body = body;
+                }
+
+                if let Some(body) = else_body {
+                    let clause_start = self.close_brace(prev_body, open) + 1;
+                    open = self.skip_comments(self.skip_comments(clause_start) + "@else".len());
+                    self.buf.push(' ');
+                    self.write_code(
+                        clause_start,
+                        open,
+                        |this| this.buf.push_str("@else"),
+                        |this, trailing| this.write_block(body, &trailing),
+                    );
+                }
+            }
+            _ => match body(statement) {
+                Some(body) if has_block(statement) => {
+                    let open = self.open_brace(body, statement.span.end);
+                    self.write_code(
+                        start,
+                        open,
+                        |this| this.write_header(statement),
+                        |this, trailing| this.write_block(body, &trailing),
+                    );
+                }
+                _ => {
+                    let (_, end) = self.extent(statement);
+                    self.write_code(
+                        start,
+                        end,
+                        |this| this.write_header(statement),
+                        |this, trailing| {
+                            this.buf.push(';');
+                            this.write_trailing_comments(&trailing);
+                        },
+                    );
+                }
+            },
+        }
+    }
+
+    /// Whether an `@at-root` rule was written as `@at-root a { ... }` rather
+    /// than `@at-root { a { ... } }`
+    fn is_at_root_shorthand(&self, statement: &Statement) -> bool {
+        let body = match &statement.kind {
+            StatementKind::AtRoot { query: None, body } => body,
+            _ => return false,
+        };
+
+        let after_name = self.source[statement.span.start..statement.span.end]
+            .get("@at-root".len()..)
+            .unwrap_or_default();
+
+        body.len() == 1
+            && matches!(body[0].kind, StatementKind::StyleRule { .. })
+            && !after_name.trim_start().starts_with('{')
+    }
+
+    /// Writes a block, preceded by the silent comments that came before its
+    /// opening brace
+    fn write_block(&mut self, body: &[Statement], comments: &[&str]) {
+        self.buf.push_str(" {");
+
+        self.indent += 1;
+        self.write_trailing_comments(comments);
+        self.indent -= 1;
+
+        if body.is_empty() && comments.is_empty() {
+            self.buf.push('}');
+            return;
+        }
+
+        self.indent += 1;
+        self.write_statements(body);
+        self.indent -= 1;
+
+        self.buf.push('\n');
+        self.write_indent();
+        self.buf.push('}');
+    }
+
+    /// Writes everything before the block or semicolon of `statement`
+    fn write_header(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::StyleRule { selector, .. } => self.write_raw(selector, true),
+            StatementKind::Declaration { name, value, .. } => {
+                self.write_interpolation(name);
+                self.buf.push(':');
+                if let Some(value) = value {
+                    self.buf.push(' ');
+                    if name.as_plain().map_or(false, |name| name.starts_with("--")) {
+                        self.buf.push_str(self.source_of(value));
+                    } else {
+                        self.write_expression(value);
+                    }
+                }
+            }
+            StatementKind::VariableDeclaration {
+                namespace,
+                name,
+                value,
+                guarded,
+                global,
+            } => {
+                if let Some(namespace) = namespace {
+                    self.buf.push_str(namespace);
+                    self.buf.push('.');
+                }
+                self.buf.push('$');
+                self.buf.push_str(name);
+                self.buf.push_str(": ");
+                self.write_expression(value);
+                if *guarded {
+                    self.buf.push_str(" !default");
+                }
+                if *global {
+                    self.buf.push_str(" !global");
+                }
+            }
+            StatementKind::SilentComment(text) => self.buf.push_str(text),
+            StatementKind::LoudComment(text) => self.write_interpolation(text),
+            StatementKind::Use {
+                url,
+                namespace,
+                configuration,
+            } => {
+                self.buf.push_str("@use ");
+                self.buf.push_str(&quote(url));
+                if let Some(namespace) = namespace {
+                    self.buf.push_str(" as ");
+                    self.buf.push_str(namespace);
+                }
+                self.write_configuration(configuration);
+            }
+            StatementKind::Forward {
+                url,
+                prefix,
+                show,
+                hide,
+                configuration,
+            } => {
+                self.buf.push_str("@forward ");
+                self.buf.push_str(&quote(url));
+                if let Some(prefix) = prefix {
+                    self.buf.push_str(" as ");
+                    self.buf.push_str(prefix);
+                    self.buf.push('*');
+                }
+                if let Some(show) = show {
+                    self.buf.push_str(" show ");
+                    self.buf.push_str(&show.join(", "));
+                }
+                if let Some(hide) = hide {
+                    self.buf.push_str(" hide ");
+                    self.buf.push_str(&hide.join(", "));
+                }
+                self.write_configuration(configuration);
+            }
+            StatementKind::Import(imports) => {
+                self.buf.push_str("@import ");
+                for (idx, import) in imports.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    match import {
+                        Import::Dynamic { url, .. } => self.buf.push_str(&quote(url)),
+                        Import::Static(url) => self.write_raw(url, false),
+                    }
+                }
+            }
+            StatementKind::Mixin {
+                name, parameters, ..
+            } => {
+                self.buf.push_str("@mixin ");
+                self.buf.push_str(name);
+                if parameters.parameters.is_empty() && parameters.rest.is_none() {
+                    return;
+                }
+                self.write_parameters(parameters);
+            }
+            StatementKind::Function {
+                name, parameters, ..
+            } => {
+                self.buf.push_str("@function ");
+                self.buf.push_str(name);
+                self.write_parameters(parameters);
+            }
+            StatementKind::Return(value) => {
+                self.buf.push_str("@return ");
+                self.write_expression(value);
+            }
+            StatementKind::Include {
+                namespace,
+                name,
+                arguments,
+                content_parameters,
+                ..
+            } => {
+                self.buf.push_str("@include ");
+                if let Some(namespace) = namespace {
+                    self.buf.push_str(namespace);
+                    self.buf.push('.');
+                }
+                self.buf.push_str(name);
+                if *arguments != Arguments::default() {
+                    self.write_arguments(arguments);
+                }
+                if let Some(parameters) = content_parameters {
+                    self.buf.push_str(" using ");
+                    self.write_parameters(parameters);
+                }
+            }
+            StatementKind::Content(arguments) => {
+                self.buf.push_str("@content");
+                if *arguments != Arguments::default() {
+                    self.write_arguments(arguments);
+                }
+            }
+            StatementKind::If { clauses, .. } => {
+                self.buf.push_str("@if ");
+                self.write_expression(&clauses[0].0);
+            }
+            StatementKind::Each {
+                variables, list, ..
+            } => {
+                self.buf.push_str("@each ");
+                for (idx, variable) in variables.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    self.buf.push('$');
+                    self.buf.push_str(variable);
+                }
+                self.buf.push_str(" in ");
+                self.write_expression(list);
+            }
+            StatementKind::For {
+                variable,
+                from,
+                to,
+                inclusive,
+                ..
+            } => {
+                self.buf.push_str("@for $");
+                self.buf.push_str(variable);
+                self.buf.push_str(" from ");
+                self.write_expression(from);
+                self.buf
+                    .push_str(if *inclusive { " through " } else { " to " });
+                self.write_expression(to);
+            }
+            StatementKind::While { condition, .. } => {
+                self.buf.push_str("@while ");
+                self.write_expression(condition);
+            }
+            StatementKind::Extend { selector, optional } => {
+                self.buf.push_str("@extend ");
+                self.write_raw(selector, true);
+                if *optional {
+                    self.buf.push_str(" !optional");
+                }
+            }
+            StatementKind::Debug(value) => {
+                self.buf.push_str("@debug ");
+                self.write_expression(value);
+            }
+            StatementKind::Warn(value) => {
+                self.buf.push_str("@warn ");
+                self.write_expression(value);
+            }
+            StatementKind::Error(value) => {
+                self.buf.push_str("@error ");
+                self.write_expression(value);
+            }
+            StatementKind::AtRoot { query, .. } => {
+                self.buf.push_str("@at-root");
+                if let Some(query) = query {
+                    self.buf.push(' ');
+                    self.write_raw(query, false);
+                }
+            }
+            StatementKind::Media { query, .. } => {
+                self.buf.push_str("@media ");
+                self.write_raw(query, false);
+            }
+            StatementKind::Supports { condition, .. } => {
+                self.buf.push_str("@supports ");
+                self.write_raw(condition, false);
+            }
+            StatementKind::AtRule { name, prelude, .. } => {
+                self.buf.push('@');
+                self.buf.push_str(name);
+                if let Some(prelude) = prelude {
+                    self.buf.push(' ');
+                    self.write_raw(prelude, false);
+                }
+            }
+        }
+    }
+
+    fn write_configuration(&mut self, configuration: &[ConfiguredVariable]) {
+        if configuration.is_empty() {
+            return;
+        }
+
+        self.buf.push_str(" with (");
+        for (idx, variable) in configuration.iter().enumerate() {
+            if idx != 0 {
+                self.buf.push_str(", ");
+            }
+            self.buf.push('$');
+            self.buf.push_str(&variable.name);
+            self.buf.push_str(": ");
+            self.write_expression(&variable.value);
+            if variable.guarded {
+                self.buf.push_str(" !default");
+            }
+        }
+        self.buf.push(')');
+    }
+
+    fn write_parameters(&mut self, parameters: &Parameters) {
+        self.buf.push('(');
+        for (idx, parameter) in parameters.parameters.iter().enumerate() {
+            if idx != 0 {
+                self.buf.push_str(", ");
+            }
+            self.buf.push('$');
+            self.buf.push_str(&parameter.name);
+            if let Some(default) = &parameter.default {
+                self.buf.push_str(": ");
+                self.write_expression(default);
+            }
+        }
+        if let Some(rest) = &parameters.rest {
+            if !parameters.parameters.is_empty() {
+                self.buf.push_str(", ");
+            }
+            self.buf.push('$');
+            self.buf.push_str(rest);
+            self.buf.push_str("...");
+        }
+        self.buf.push(')');
+    }
+
+    fn write_arguments(&mut self, arguments: &Arguments) {
+        self.buf.push('(');
+
+        let mut first = true;
+        let mut separate = |buf: &mut String| {
+            if !first {
+                buf.push_str(", ");
+            }
+            first = false;
+        };
+
+        for argument in &arguments.positional {
+            separate(&mut self.buf);
+            self.write_expression(argument);
+        }
+        for (name, value) in &arguments.named {
+            separate(&mut self.buf);
+            self.buf.push('$');
+            self.buf.push_str(name);
+            self.buf.push_str(": ");
+            self.write_expression(value);
+        }
+        for rest in arguments.rest.iter().chain(&arguments.keyword_rest) {
+            separate(&mut self.buf);
+            self.write_expression(rest);
+            self.buf.push_str("...");
+        }
+
+        self.buf.push(')');
+    }
+
+    /// Writes an interpolation exactly as it was written
+    fn write_interpolation(&mut self, interpolation: &Interpolation) {
+        for part in &interpolation.contents {
+            match part {
+                InterpolationPart::Text(text) => self.buf.push_str(text),
+                InterpolationPart::Expression(expr) => {
+                    self.buf.push_str("#{");
+                    self.write_expression(expr);
+                    self.buf.push('}');
+                }
+            }
+        }
+    }
+
+    /// Writes raw text such as a selector or media query with its whitespace
+    /// collapsed
+    ///
+    /// If `selector` is true, commas are formatted as in a selector list.
+    fn write_raw(&mut self, interpolation: &Interpolation, selector: bool) {
+        let len = interpolation.contents.len();
+        let indent = INDENT.repeat(self.indent);
+        let indent = if selector {
+            Some(indent.as_str())
+        } else {
+            None
+        };
+
+        for (idx, part) in interpolation.contents.iter().enumerate() {
+            match part {
+                InterpolationPart::Text(text) => {
+                    let mut text = collapse_whitespace(text, indent);
+                    if idx == 0 {
+                        text = text.trim_start().to_owned();
+                    }
+                    if idx == len - 1 {
+                        text = text.trim_end_matches(' ').to_owned();
+                    }
+                    self.buf.push_str(&text);
+                }
+                InterpolationPart::Expression(expr) => {
+                    self.buf.push_str("#{");
+                    self.write_expression(expr);
+                    self.buf.push('}');
+                }
+            }
+        }
+    }
+
+    fn write_expression(&mut self, expr: &Expression) {
+        match &expr.kind {
+            ExpressionKind::Number { .. } | ExpressionKind::Color(..) => {
+                self.buf.push_str(self.source_of(expr));
+            }
+            ExpressionKind::String {
+                contents,
+                quoted: true,
+            } => {
+                let quote = quote_for(contents.contents.iter().filter_map(|part| match part {
+                    InterpolationPart::Text(text) => Some(text.as_str()),
+                    InterpolationPart::Expression(..) => None,
+                }));
+                // `\'` is unnecessary once a string is no longer in single quotes
+                let unescape = quote == '"' && self.source_of(expr).starts_with('\'');
+
+                self.buf.push(quote);
+                for part in &contents.contents {
+                    match part {
+                        InterpolationPart::Text(text) if unescape => {
+                            self.buf.push_str(&unescape_single_quotes(text));
+                        }
+                        InterpolationPart::Text(text) => self.buf.push_str(text),
+                        InterpolationPart::Expression(expr) => {
+                            self.buf.push_str("#{");
+                            self.write_expression(expr);
+                            self.buf.push('}');
+                        }
+                    }
+                }
+                self.buf.push(quote);
+            }
+            ExpressionKind::String {
+                contents,
+                quoted: false,
+            } => self.write_interpolation(contents),
+            ExpressionKind::Boolean(true) => self.buf.push_str("true"),
+            ExpressionKind::Boolean(false) => self.buf.push_str("false"),
+            ExpressionKind::Null => self.buf.push_str("null"),
+            ExpressionKind::Variable { namespace, name } => {
+                if let Some(namespace) = namespace {
+                    self.buf.push_str(namespace);
+                    self.buf.push('.');
+                }
+                self.buf.push('$');
+                self.buf.push_str(name);
+            }
+            ExpressionKind::FunctionCall {
+                namespace,
+                name,
+                arguments,
+            } => {
+                if let Some(namespace) = namespace {
+                    self.buf.push_str(namespace);
+                    self.buf.push('.');
+                }
+                self.write_interpolation(name);
+                self.write_arguments(arguments);
+            }
+            ExpressionKind::List {
+                elements,
+                separator,
+                bracketed,
+            } => {
+                if *bracketed {
+                    self.buf.push('[');
+                } else if elements.is_empty() {
+                    self.buf.push('(');
+                }
+
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(if *separator == ListSeparator::Comma {
+                            ", "
+                        } else {
+                            " "
+                        });
+                    }
+                    self.write_expression(element);
+                }
+
+                if elements.len() == 1 && *separator == ListSeparator::Comma {
+                    self.buf.push(',');
+                }
+
+                if *bracketed {
+                    self.buf.push(']');
+                } else if elements.is_empty() {
+                    self.buf.push(')');
+                }
+            }
+            ExpressionKind::Map(pairs) => {
+                self.buf.push('(');
+                for (idx, (key, value)) in pairs.iter().enumerate() {
+                    if idx != 0 {
+                        self.buf.push_str(", ");
+                    }
+                    self.write_expression(key);
+                    self.buf.push_str(": ");
+                    self.write_expression(value);
+                }
+                self.buf.push(')');
+            }
+            ExpressionKind::Parenthesized(inner) => {
+                self.buf.push('(');
+                self.write_expression(inner);
+                self.buf.push(')');
+            }
+            ExpressionKind::UnaryOperation { operator, operand } => {
+                self.buf.push_str(match operator {
+                    UnaryOperator::Plus => "+",
+                    UnaryOperator::Minus => "-",
+                    UnaryOperator::Divide => "/",
+                    UnaryOperator::Not => "not ",
+                });
+
+                let start = self.buf.len();
+                self.write_expression(operand);
+
+                // `- a` must not become the identifier `-a`
+                if *operator == UnaryOperator::Minus
+                    && self.buf[start..].chars().next().map_or(false, |c| {
+                        c.is_alphanumeric() || matches!(c, '_' | '-' | '\\' | '#' | '.')
+                    })
+                {
+                    self.buf.insert(start, ' ');
+                }
+            }
+            ExpressionKind::BinaryOperation { operator, lhs, rhs } => {
+                let operator = match operator {
+                    BinaryOperator::Or => "or",
+                    BinaryOperator::And => "and",
+                    BinaryOperator::Equal => "==",
+                    BinaryOperator::NotEqual => "!=",
+                    BinaryOperator::LessThan => "<",
+                    BinaryOperator::LessThanOrEqual => "<=",
+                    BinaryOperator::GreaterThan => ">",
+                    BinaryOperator::GreaterThanOrEqual => ">=",
+                    BinaryOperator::Plus => "+",
+                    BinaryOperator::Minus => "-",
+                    BinaryOperator::Times => "*",
+                    BinaryOperator::Divide => "/",
+                    BinaryOperator::Modulo => "%",
+                };
+
+                // whitespace around `-` and `/` can change their meaning, so
+                // it is kept as written
+                let spaced = !matches!(operator, "-" | "/")
+                    || self.source[lhs.span.end..rhs.span.start]
+                        .chars()
+                        .any(char::is_whitespace);
+
+                self.write_expression(lhs);
+                if spaced {
+                    self.buf.push(' ');
+                    self.buf.push_str(operator);
+                    self.buf.push(' ');
+                } else {
+                    self.buf.push_str(operator);
+                }
+                self.write_expression(rhs);
+            }
+            ExpressionKind::ParentSelector => self.buf.push('&'),
+            ExpressionKind::Important => self.buf.push_str("!important"),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! # An unevaluated syntax tree of a Sass stylesheet
+//!
+//! Unlike [`from_path`](crate::from_path) and [`from_string`](crate::from_string),
+//! which evaluate a stylesheet as it is parsed, [`parse_path`](crate::parse_path)
+//! and [`parse_string`](crate::parse_string) only parse it, returning the
+//! stylesheet exactly as it was written. Nothing is loaded, so `@use` and
+//! `@import` rules are not followed.
+//!
+//! Every node carries the [`Span`] of the source text it was parsed from.
+//!
+//! ```
+//! use grass::ast::{ExpressionKind, StatementKind};
+//!
+//! fn main() -> Result<(), Box<grass::Error>> {
+//!     let stylesheet = grass::parse_string("$a: 1px + 2px;".to_string())?;
+//!
+//!     match &stylesheet.statements[0].kind {
+//!         StatementKind::VariableDeclaration { name, value, .. } => {
+//!             assert_eq!(name, "a");
+//!             assert!(matches!(value.kind, ExpressionKind::BinaryOperation { .. }));
+//!         }
+//!         _ => unreachable!(),
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(not(feature = "wasm"))]
+pub(crate) use format::format_stylesheet;
+#[cfg(not(feature = "wasm"))]
+pub(crate) use parse::AstParser;
+
+#[cfg(not(feature = "wasm"))]
+mod format;
+#[cfg(not(feature = "wasm"))]
+mod parse;
+
+/// A range of the source text, as byte offsets from the start of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The offset of the first byte in the range
+    pub start: usize,
+
+    /// The offset of the byte after the last byte in the range
+    pub end: usize,
+}
+
+/// A parsed stylesheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stylesheet {
+    pub statements: Vec<Statement>,
+}
+
+/// Text that may contain interpolated expressions, e.g. the selector
+/// `.a-#{$b}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpolation {
+    pub contents: Vec<InterpolationPart>,
+    pub span: Span,
+}
+
+impl Interpolation {
+    /// The text of this interpolation, if it contains no interpolated
+    /// expressions
+    #[must_use]
+    #[inline]
+    pub fn as_plain(&self) -> Option<&str> {
+        match self.contents.as_slice() {
+            [] => Some(""),
+            [InterpolationPart::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Text(String),
+    /// An expression inside of `#{...}`
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// A style rule, e.g. `a { color: red; }`
+    StyleRule {
+        selector: Interpolation,
+        body: Vec<Statement>,
+    },
+    /// A property declaration, e.g. `color: red`
+    ///
+    /// Declarations with nested properties, e.g. `font: { family: serif; }`,
+    /// have a `body`, and may not have a value.
+    Declaration {
+        name: Interpolation,
+        value: Option<Expression>,
+        body: Vec<Statement>,
+    },
+    /// A variable declaration, e.g. `$a: red !default;`
+    VariableDeclaration {
+        /// The namespace of a module member being assigned to, e.g. `a` in
+        /// `a.$b: c`
+        namespace: Option<String>,
+        name: String,
+        value: Expression,
+        /// Whether the `!default` flag was passed
+        guarded: bool,
+        /// Whether the `!global` flag was passed
+        global: bool,
+    },
+    /// `// a comment`, including the leading slashes
+    SilentComment(String),
+    /// `/* a comment */`, including the delimiters
+    LoudComment(Interpolation),
+    Use {
+        url: String,
+        /// The namespace passed with `as`, or `*` for `as *`
+        namespace: Option<String>,
+        configuration: Vec<ConfiguredVariable>,
+    },
+    Forward {
+        url: String,
+        /// The prefix passed with `as`, excluding the trailing `*`
+        prefix: Option<String>,
+        /// The members passed to `show`, with variables including their `$`
+        show: Option<Vec<String>>,
+        /// The members passed to `hide`, with variables including their `$`
+        hide: Option<Vec<String>>,
+        configuration: Vec<ConfiguredVariable>,
+    },
+    Import(Vec<Import>),
+    Mixin {
+        name: String,
+        parameters: Parameters,
+        body: Vec<Statement>,
+    },
+    Function {
+        name: String,
+        parameters: Parameters,
+        body: Vec<Statement>,
+    },
+    Return(Expression),
+    Include {
+        namespace: Option<String>,
+        name: String,
+        arguments: Arguments,
+        /// The parameters passed with `using`, e.g. `using ($a)`
+        content_parameters: Option<Parameters>,
+        /// The block of styles passed to the mixin, if any
+        content: Option<Vec<Statement>>,
+    },
+    Content(Arguments),
+    If {
+        /// The condition and body of the `@if` rule, followed by those of
+        /// each `@else if` rule
+        clauses: Vec<(Expression, Vec<Statement>)>,
+        /// The body of the trailing `@else` rule
+        else_body: Option<Vec<Statement>>,
+    },
+    Each {
+        /// The names of the variables, e.g. `a` and `b` in `@each $a, $b in c`
+        variables: Vec<String>,
+        list: Expression,
+        body: Vec<Statement>,
+    },
+    For {
+        variable: String,
+        from: Expression,
+        to: Expression,
+        /// Whether `through` was used, rather than `to`
+        inclusive: bool,
+        body: Vec<Statement>,
+    },
+    While {
+        condition: Expression,
+        body: Vec<Statement>,
+    },
+    Extend {
+        selector: Interpolation,
+        optional: bool,
+    },
+    Debug(Expression),
+    Warn(Expression),
+    Error(Expression),
+    AtRoot {
+        /// A query such as `(without: media)`, or a selector
+        query: Option<Interpolation>,
+        body: Vec<Statement>,
+    },
+    Media {
+        query: Interpolation,
+        body: Vec<Statement>,
+    },
+    Supports {
+        condition: Interpolation,
+        body: Vec<Statement>,
+    },
+    /// Any other at-rule, e.g. `@keyframes` or `@font-face`
+    AtRule {
+        name: String,
+        prelude: Option<Interpolation>,
+        body: Option<Vec<Statement>>,
+    },
+}
+
+/// A variable passed to the `with` clause of `@use` or `@forward`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfiguredVariable {
+    pub name: String,
+    pub value: Expression,
+    /// Whether the `!default` flag was passed
+    pub guarded: bool,
+    pub span: Span,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Import {
+    /// An import of a Sass or CSS file that is loaded during compilation
+    Dynamic { url: String, span: Span },
+    /// A plain CSS `@import` that is emitted as-is, e.g. `url(a.css)` or
+    /// `"a.css" screen`
+    Static(Interpolation),
+}
+
+/// The parameters declared by a mixin or function, e.g. `($a, $b: 1, $c...)`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Parameters {
+    pub parameters: Vec<Parameter>,
+    /// The name of the rest parameter, e.g. `c` in `$c...`
+    pub rest: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Expression>,
+    pub span: Span,
+}
+
+/// The arguments passed to a function or mixin, e.g. `(1, $b: 2, $c...)`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Arguments {
+    pub positional: Vec<Expression>,
+    pub named: Vec<(String, Expression)>,
+    /// The first argument followed by `...`
+    pub rest: Option<Box<Expression>>,
+    /// The second argument followed by `...`, which must be a map
+    pub keyword_rest: Option<Box<Expression>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
+    Number {
+        value: f64,
+        unit: Option<String>,
+    },
+    /// A hex color, e.g. `#ff0000`, without the leading `#`
+    Color(String),
+    String {
+        contents: Interpolation,
+        quoted: bool,
+    },
+    Boolean(bool),
+    Null,
+    Variable {
+        namespace: Option<String>,
+        name: String,
+    },
+    FunctionCall {
+        namespace: Option<String>,
+        name: Interpolation,
+        arguments: Arguments,
+    },
+    List {
+        elements: Vec<Expression>,
+        separator: ListSeparator,
+        bracketed: bool,
+    },
+    Map(Vec<(Expression, Expression)>),
+    /// An expression wrapped in parentheses
+    Parenthesized(Box<Expression>),
+    UnaryOperation {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    BinaryOperation {
+        operator: BinaryOperator,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    /// `&`
+    ParentSelector,
+    /// `!important`
+    Important,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListSeparator {
+    Space,
+    Comma,
+    /// The separator of a list with fewer than two elements, e.g. `()`
+    Undecided,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    Plus,
+    Minus,
+    Divide,
+    Not,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    Modulo,
+}
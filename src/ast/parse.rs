@@ -0,0 +1,2250 @@
+use codemap::File;
+
+use crate::error::SassResult;
+
+use super::{
+    Arguments, BinaryOperator, ConfiguredVariable, Expression, ExpressionKind, Import,
+    Interpolation, InterpolationPart, ListSeparator, Parameter, Parameters, Span, Statement,
+    StatementKind, Stylesheet, UnaryOperator,
+};
+
+/// Parses a stylesheet into an unevaluated syntax tree
+pub(crate) struct AstParser<'a> {
+    input: &'a str,
+    file: &'a File,
+
+    /// The offset of the next character
+    pos: usize,
+
+    /// The offset after the last character that was not whitespace or a
+    /// comment, which is where spans end
+    prev_end: usize,
+
+    /// Identifiers that end a space-separated list, e.g. `through` in `@for`
+    stop_words: &'static [&'static str],
+}
+
+fn is_name_start(c: char) -> bool {
+    c == '_' || c.is_ascii_alphabetic() || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+/// Removes a vendor prefix such as `-webkit-` from `name`
+fn unvendor(name: &str) -> &str {
+    if name.starts_with('-') && !name.starts_with("--") {
+        if let Some(idx) = name[1..].find('-') {
+            return &name[idx + 2..];
+        }
+    }
+
+    name
+}
+
+/// Whether an `@import` of `url` is emitted as a plain CSS import rather than
+/// loaded
+fn is_plain_css_import(url: &str) -> bool {
+    url.ends_with(".css")
+        || url.starts_with("http://")
+        || url.starts_with("https://")
+        || url.starts_with("//")
+}
+
+#[derive(Default)]
+struct InterpolationBuilder {
+    contents: Vec<InterpolationPart>,
+    text: String,
+}
+
+impl InterpolationBuilder {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.text.push_str(s);
+    }
+
+    fn add_expression(&mut self, expr: Expression) {
+        self.flush();
+        self.contents.push(InterpolationPart::Expression(expr));
+    }
+
+    fn add_interpolation(&mut self, interpolation: Interpolation) {
+        for part in interpolation.contents {
+            match part {
+                InterpolationPart::Text(text) => self.push_str(&text),
+                InterpolationPart::Expression(expr) => self.add_expression(expr),
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.contents
+                .push(InterpolationPart::Text(std::mem::take(&mut self.text)));
+        }
+    }
+
+    /// Removes trailing whitespace following the last interpolated expression
+    fn trim_end(&mut self) {
+        let len = self.text.trim_end().len();
+        self.text.truncate(len);
+    }
+
+    fn finish(mut self, span: Span) -> Interpolation {
+        self.flush();
+        Interpolation {
+            contents: self.contents,
+            span,
+        }
+    }
+}
+
+impl<'a> AstParser<'a> {
+    pub fn new(file: &'a File) -> Self {
+        let input = file.source();
+        let pos = if input.starts_with('\u{feff}') {
+            '\u{feff}'.len_utf8()
+        } else {
+            0
+        };
+
+        Self {
+            input,
+            file,
+            pos,
+            prev_end: pos,
+            stop_words: &[],
+        }
+    }
+
+    pub fn parse_stylesheet(mut self) -> SassResult<Stylesheet> {
+        Ok(Stylesheet {
+            statements: self.parse_statements(true)?,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(n)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        self.prev_end = self.pos;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.next_char();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            self.prev_end = self.pos;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes `keyword`, ignoring case, if it is not immediately followed by
+    /// another identifier character
+    fn scan_keyword(&mut self, keyword: &str) -> bool {
+        if self.looks_like_keyword(keyword) {
+            self.pos += keyword.len();
+            self.prev_end = self.pos;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn looks_like_keyword(&self, keyword: &str) -> bool {
+        let rest = self.rest();
+        rest.get(..keyword.len())
+            .map_or(false, |s| s.eq_ignore_ascii_case(keyword))
+            && !rest[keyword.len()..]
+                .chars()
+                .next()
+                .map_or(false, is_name_char)
+    }
+
+    /// Consumes `keyword` only if it matches exactly, as is required for
+    /// operators such as `and` and for `@else`
+    fn scan_exact_keyword(&mut self, keyword: &str) -> bool {
+        self.rest().starts_with(keyword) && self.scan_keyword(keyword)
+    }
+
+    /// Whether the next tokens are `!` followed by `flag`, e.g. `!important`
+    fn looks_like_flag(&self, flag: &str) -> bool {
+        let rest = match self.rest().strip_prefix('!') {
+            Some(rest) => rest.trim_start(),
+            None => return false,
+        };
+
+        rest.get(..flag.len())
+            .map_or(false, |s| s.eq_ignore_ascii_case(flag))
+            && !rest[flag.len()..]
+                .chars()
+                .next()
+                .map_or(false, is_name_char)
+    }
+
+    fn scan_flag(&mut self, flag: &str) -> bool {
+        if !self.looks_like_flag(flag) {
+            return false;
+        }
+
+        self.pos += 1;
+        self.whitespace();
+        self.pos += flag.len();
+        self.prev_end = self.pos;
+        true
+    }
+
+    fn expect(&mut self, c: char) -> SassResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected \"{}\".", c)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> SassResult<()> {
+        if self.scan_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected \"{}\".", keyword)))
+        }
+    }
+
+    /// An error at the next character
+    fn error(&self, message: String) -> Box<crate::Error> {
+        let end = self.peek().map_or(self.pos, |c| self.pos + c.len_utf8());
+        self.error_at(message, self.pos, end)
+    }
+
+    fn error_at(&self, message: String, start: usize, end: usize) -> Box<crate::Error> {
+        (message, self.file.span.subspan(start as u64, end as u64)).into()
+    }
+
+    /// The span from `start` to the end of the last token that was consumed
+    fn span(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.prev_end.max(start),
+        }
+    }
+
+    fn state(&self) -> (usize, usize) {
+        (self.pos, self.prev_end)
+    }
+
+    fn restore(&mut self, (pos, prev_end): (usize, usize)) {
+        self.pos = pos;
+        self.prev_end = prev_end;
+    }
+
+    /// Skips whitespace, returning whether any was found
+    fn whitespace_without_comments(&mut self) -> bool {
+        let start = self.pos;
+        let len = self.rest().len() - self.rest().trim_start().len();
+        self.pos += len;
+        self.pos != start
+    }
+
+    /// Skips whitespace and comments, returning whether any were found
+    fn whitespace(&mut self) -> bool {
+        let start = self.pos;
+
+        loop {
+            self.whitespace_without_comments();
+
+            if self.rest().starts_with("//") {
+                self.pos += self.rest().find('\n').unwrap_or_else(|| self.rest().len());
+            } else if self.rest().starts_with("/*") {
+                self.pos += self.rest()[2..]
+                    .find("*/")
+                    .map_or_else(|| self.rest().len(), |idx| idx + 4);
+            } else {
+                break;
+            }
+        }
+
+        self.pos != start
+    }
+
+    fn looks_like_identifier(&self) -> bool {
+        match self.peek() {
+            Some(c) if is_name_start(c) || c == '\\' => true,
+            Some('-') => match self.peek_nth(1) {
+                Some(c) if is_name_start(c) || c == '-' || c == '\\' => true,
+                Some('#') => self.peek_nth(2) == Some('{'),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn looks_like_interpolation(&self) -> bool {
+        self.rest().starts_with("#{")
+    }
+
+    fn parse_identifier(&mut self) -> SassResult<String> {
+        if !self.looks_like_identifier() {
+            return Err(self.error("Expected identifier.".to_owned()));
+        }
+
+        let mut ident = String::new();
+
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.parse_escape(&mut ident);
+            } else if is_name_char(c) {
+                ident.push(c);
+                self.next_char();
+            } else {
+                break;
+            }
+        }
+
+        Ok(ident)
+    }
+
+    /// Consumes an escape sequence such as `\61 ` or `\.`, appending it to
+    /// `buffer` as it was written
+    fn parse_escape(&mut self, buffer: &mut String) {
+        let start = self.pos;
+        self.next_char();
+
+        let hex_len = self
+            .rest()
+            .chars()
+            .take(6)
+            .take_while(char::is_ascii_hexdigit)
+            .count();
+
+        if hex_len == 0 {
+            self.next_char();
+        } else {
+            self.pos += hex_len;
+            self.prev_end = self.pos;
+            if self.peek().map_or(false, char::is_whitespace) {
+                self.next_char();
+            }
+        }
+
+        buffer.push_str(&self.input[start..self.pos]);
+    }
+
+    fn parse_interpolated_identifier(&mut self) -> SassResult<Interpolation> {
+        let start = self.pos;
+        let mut builder = InterpolationBuilder::default();
+
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.parse_escape(&mut builder.text);
+            } else if self.looks_like_interpolation() {
+                builder.add_expression(self.parse_single_interpolation()?);
+            } else if is_name_char(c) {
+                builder.push(c);
+                self.next_char();
+            } else {
+                break;
+            }
+        }
+
+        Ok(builder.finish(self.span(start)))
+    }
+
+    /// Parses `#{...}`, returning the interpolated expression
+    fn parse_single_interpolation(&mut self) -> SassResult<Expression> {
+        self.eat_str("#{");
+        self.whitespace();
+        let stop_words = std::mem::replace(&mut self.stop_words, &[]);
+        let expr = self.parse_expression();
+        self.stop_words = stop_words;
+        let expr = expr?;
+        self.whitespace();
+        self.expect('}')?;
+        Ok(expr)
+    }
+
+    /// Parses a quoted string, including the quotes in its span but not in
+    /// its contents
+    fn parse_quoted_string(&mut self) -> SassResult<Interpolation> {
+        let start = self.pos;
+        let quote = match self.next_char() {
+            Some(c @ '"') | Some(c @ '\'') => c,
+            _ => return Err(self.error_at("Expected string.".to_owned(), start, self.pos)),
+        };
+
+        let mut builder = InterpolationBuilder::default();
+
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.next_char();
+                    break;
+                }
+                Some('\\') => {
+                    builder.push('\\');
+                    self.next_char();
+                    match self.next_char() {
+                        Some(c) => builder.push(c),
+                        None => return Err(self.error(format!("Expected {}.", quote))),
+                    }
+                }
+                Some('#') if self.looks_like_interpolation() => {
+                    builder.add_expression(self.parse_single_interpolation()?);
+                }
+                Some('\n') | None => return Err(self.error(format!("Expected {}.", quote))),
+                Some(c) => {
+                    builder.push(c);
+                    self.next_char();
+                }
+            }
+        }
+
+        Ok(builder.finish(self.span(start)))
+    }
+
+    /// Parses a quoted string that may not contain interpolation, such as the
+    /// URL of a `@use` rule
+    fn parse_plain_string(&mut self) -> SassResult<String> {
+        let string = self.parse_quoted_string()?;
+
+        match string.as_plain() {
+            Some(plain) => Ok(plain.to_owned()),
+            None => Err(self.error_at(
+                "Interpolation isn't allowed here.".to_owned(),
+                string.span.start,
+                string.span.end,
+            )),
+        }
+    }
+
+    /// Parses text such as a selector or media query verbatim until `is_end`
+    /// matches a character outside of any parentheses, brackets, or strings
+    ///
+    /// Silent comments are removed, and trailing whitespace is trimmed.
+    fn parse_raw_interpolation(
+        &mut self,
+        is_end: fn(char) -> bool,
+        track_braces: bool,
+    ) -> SassResult<Interpolation> {
+        let start = self.pos;
+        let mut builder = InterpolationBuilder::default();
+        let mut brackets = Vec::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                _ if brackets.is_empty() && is_end(c) => break,
+                '#' if self.looks_like_interpolation() => {
+                    builder.add_expression(self.parse_single_interpolation()?);
+                }
+                '"' | '\'' => {
+                    builder.push(c);
+                    builder.add_interpolation(self.parse_quoted_string()?);
+                    builder.push(c);
+                }
+                '\\' => {
+                    builder.push(c);
+                    self.next_char();
+                    if let Some(c) = self.next_char() {
+                        builder.push(c);
+                    }
+                }
+                '/' if brackets.is_empty() && self.rest().starts_with("//") => {
+                    self.pos += self.rest().find('\n').unwrap_or_else(|| self.rest().len());
+                }
+                '(' | '[' => {
+                    brackets.push(if c == '(' { ')' } else { ']' });
+                    builder.push(c);
+                    self.next_char();
+                }
+                '{' if track_braces => {
+                    brackets.push('}');
+                    builder.push(c);
+                    self.next_char();
+                }
+                ')' | ']' | '}' if brackets.last() == Some(&c) => {
+                    brackets.pop();
+                    builder.push(c);
+                    self.next_char();
+                }
+                _ => {
+                    builder.push(c);
+                    if c.is_whitespace() {
+                        self.pos += c.len_utf8();
+                    } else {
+                        self.next_char();
+                    }
+                }
+            }
+        }
+
+        builder.trim_end();
+
+        Ok(builder.finish(self.span(start)))
+    }
+
+    /// Scans ahead to the first `;`, `{`, or `}` that is not inside of
+    /// parentheses, brackets, strings, or interpolation
+    fn find_statement_terminator(&self) -> Option<char> {
+        let mut chars = self.rest().chars().peekable();
+        let mut depth = 0_usize;
+        let mut interpolation_depth = 0_usize;
+        let mut quote = None;
+
+        while let Some(c) = chars.next() {
+            if let Some(q) = quote {
+                if c == '\\' {
+                    chars.next();
+                } else if c == q {
+                    quote = None;
+                }
+                continue;
+            }
+
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '\\' => {
+                    chars.next();
+                }
+                '#' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    interpolation_depth += 1;
+                }
+                '}' if interpolation_depth > 0 => interpolation_depth -= 1,
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth = depth.saturating_sub(1),
+                ';' | '{' | '}' if depth == 0 && interpolation_depth == 0 => return Some(c),
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Consumes the `;` that ends a statement, which may be omitted before a
+    /// `}` or the end of the file
+    fn expect_statement_end(&mut self) -> SassResult<()> {
+        self.whitespace();
+
+        match self.peek() {
+            Some(';') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some('}') | None => Ok(()),
+            Some(..) => Err(self.error("expected \";\".".to_owned())),
+        }
+    }
+
+    fn parse_statements(&mut self, top_level: bool) -> SassResult<Vec<Statement>> {
+        let mut statements = Vec::new();
+
+        loop {
+            self.whitespace_without_comments();
+
+            let start = self.pos;
+
+            let statement = match self.peek() {
+                None if top_level => break,
+                None => return Err(self.error("expected \"}\".".to_owned())),
+                Some('}') if top_level => return Err(self.error("unmatched \"}\".".to_owned())),
+                Some('}') => break,
+                Some(';') => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some('/') if self.rest().starts_with("//") => {
+                    let len = self.rest().find('\n').unwrap_or_else(|| self.rest().len());
+                    let text = self.rest()[..len].trim_end().to_owned();
+                    self.pos += text.len();
+                    self.prev_end = self.pos;
+                    Statement {
+                        kind: StatementKind::SilentComment(text),
+                        span: self.span(start),
+                    }
+                }
+                Some('/') if self.rest().starts_with("/*") => self.parse_loud_comment()?,
+                Some('$') => self.parse_variable_declaration()?,
+                Some('@') => self.parse_at_rule()?,
+                Some(..) => self.parse_declaration_or_style_rule()?,
+            };
+
+            statements.push(statement);
+        }
+
+        Ok(statements)
+    }
+
+    fn parse_block(&mut self) -> SassResult<Vec<Statement>> {
+        self.expect('{')?;
+        let statements = self.parse_statements(false)?;
+        self.expect('}')?;
+        Ok(statements)
+    }
+
+    fn parse_loud_comment(&mut self) -> SassResult<Statement> {
+        let start = self.pos;
+        let mut builder = InterpolationBuilder::default();
+
+        self.eat_str("/*");
+        builder.push_str("/*");
+
+        loop {
+            if self.eat_str("*/") {
+                builder.push_str("*/");
+                break;
+            }
+
+            match self.peek() {
+                Some('#') if self.looks_like_interpolation() => {
+                    builder.add_expression(self.parse_single_interpolation()?);
+                }
+                Some(c) => {
+                    builder.push(c);
+                    self.next_char();
+                }
+                None => return Err(self.error("expected more input.".to_owned())),
+            }
+        }
+
+        let span = self.span(start);
+
+        Ok(Statement {
+            kind: StatementKind::LoudComment(builder.finish(span)),
+            span,
+        })
+    }
+
+    /// Whether the next tokens are a namespaced variable, e.g. `a.$b`
+    fn looks_like_namespaced_variable(&self) -> bool {
+        let rest = self.rest();
+
+        if !rest.chars().next().map_or(false, is_name_start) {
+            return false;
+        }
+
+        let len = rest
+            .find(|c| !is_name_char(c))
+            .unwrap_or_else(|| rest.len());
+        rest[len..].starts_with(".$")
+    }
+
+    fn parse_variable_declaration(&mut self) -> SassResult<Statement> {
+        let start = self.pos;
+
+        let namespace = if self.peek() == Some('$') {
+            None
+        } else {
+            let namespace = self.parse_identifier()?;
+            self.expect('.')?;
+            Some(namespace)
+        };
+
+        self.expect('$')?;
+        let name = self.parse_identifier()?;
+        self.whitespace();
+        self.expect(':')?;
+        self.whitespace();
+
+        let value = self.parse_expression()?;
+
+        let mut guarded = false;
+        let mut global = false;
+
+        loop {
+            self.whitespace();
+
+            if self.peek() != Some('!') {
+                break;
+            }
+
+            let flag_start = self.pos;
+            self.next_char();
+
+            match self.parse_identifier()?.as_str() {
+                "default" => guarded = true,
+                "global" => global = true,
+                _ => {
+                    return Err(self.error_at(
+                        "Invalid flag name.".to_owned(),
+                        flag_start,
+                        self.pos,
+                    ))
+                }
+            }
+        }
+
+        let span = self.span(start);
+        self.expect_statement_end()?;
+
+        Ok(Statement {
+            kind: StatementKind::VariableDeclaration {
+                namespace,
+                name,
+                value,
+                guarded,
+                global,
+            },
+            span,
+        })
+    }
+
+    fn parse_declaration_or_style_rule(&mut self) -> SassResult<Statement> {
+        if self.looks_like_namespaced_variable() {
+            return self.parse_variable_declaration();
+        }
+
+        let state = self.state();
+
+        if let Some(declaration) = self.try_parse_declaration()? {
+            return Ok(declaration);
+        }
+
+        self.restore(state);
+
+        self.parse_style_rule()
+    }
+
+    /// Parses a declaration, returning `None` if the next tokens are instead
+    /// the selector of a style rule
+    fn try_parse_declaration(&mut self) -> SassResult<Option<Statement>> {
+        let start = self.pos;
+
+        if self.rest().starts_with("--") {
+            return self.parse_custom_property().map(Some);
+        }
+
+        if !self.looks_like_identifier() && !self.looks_like_interpolation() {
+            return Ok(None);
+        }
+
+        let name = self.parse_interpolated_identifier()?;
+        self.whitespace();
+
+        if !self.eat(':') || self.peek() == Some(':') {
+            return Ok(None);
+        }
+
+        let has_whitespace = self.whitespace();
+
+        // `a:hover {` is a selector, while `font: bold {` has nested properties
+        if self.peek() != Some('{')
+            && self.find_statement_terminator() == Some('{')
+            && !has_whitespace
+        {
+            return Ok(None);
+        }
+
+        let value = if self.peek() == Some('{') {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+
+        self.whitespace();
+
+        let body = if self.peek() == Some('{') {
+            self.parse_block()?
+        } else {
+            Vec::new()
+        };
+
+        let span = self.span(start);
+
+        if body.is_empty() {
+            self.expect_statement_end()?;
+        }
+
+        Ok(Some(Statement {
+            kind: StatementKind::Declaration { name, value, body },
+            span,
+        }))
+    }
+
+    /// Parses a custom property such as `--a: b`, whose value is not parsed
+    /// as an expression
+    fn parse_custom_property(&mut self) -> SassResult<Statement> {
+        let start = self.pos;
+        let name = self.parse_interpolated_identifier()?;
+        self.whitespace();
+        self.expect(':')?;
+        self.whitespace_without_comments();
+
+        let contents = self.parse_raw_interpolation(|c| c == ';' || c == '}', true)?;
+        let span = self.span(start);
+        self.expect_statement_end()?;
+
+        let value = Expression {
+            span: contents.span,
+            kind: ExpressionKind::String {
+                contents,
+                quoted: false,
+            },
+        };
+
+        Ok(Statement {
+            kind: StatementKind::Declaration {
+                name,
+                value: Some(value),
+                body: Vec::new(),
+            },
+            span,
+        })
+    }
+
+    fn parse_style_rule(&mut self) -> SassResult<Statement> {
+        let start = self.pos;
+        let selector = self.parse_raw_interpolation(|c| matches!(c, '{' | ';' | '}'), false)?;
+        let body = self.parse_block()?;
+
+        Ok(Statement {
+            kind: StatementKind::StyleRule { selector, body },
+            span: self.span(start),
+        })
+    }
+
+    fn parse_at_rule(&mut self) -> SassResult<Statement> {
+        let start = self.pos;
+        self.expect('@')?;
+        let name_start = self.pos;
+        let name = if self.looks_like_interpolation() {
+            self.parse_interpolated_identifier()?;
+            self.input[name_start..self.pos].to_owned()
+        } else {
+            self.parse_identifier()?
+        };
+        self.whitespace();
+
+        let kind = match name.as_str() {
+            "use" => self.parse_use()?,
+            "forward" => self.parse_forward()?,
+            "import" => self.parse_import()?,
+            "mixin" | "function" => self.parse_callable(&name)?,
+            "return" => {
+                let value = self.parse_expression()?;
+                StatementKind::Return(value)
+            }
+            "include" => self.parse_include()?,
+            "content" => {
+                let arguments = if self.peek() == Some('(') {
+                    self.parse_arguments()?
+                } else {
+                    Arguments::default()
+                };
+                StatementKind::Content(arguments)
+            }
+            "if" => self.parse_if()?,
+            "each" => self.parse_each()?,
+            "for" => self.parse_for()?,
+            "while" => {
+                let condition = self.parse_expression()?;
+                self.whitespace();
+                let body = self.parse_block()?;
+                StatementKind::While { condition, body }
+            }
+            "extend" => {
+                let selector =
+                    self.parse_raw_interpolation(|c| matches!(c, ';' | '}' | '!'), false)?;
+                self.whitespace();
+                let optional = self.scan_flag("optional");
+                StatementKind::Extend { selector, optional }
+            }
+            "debug" => StatementKind::Debug(self.parse_expression()?),
+            "warn" => StatementKind::Warn(self.parse_expression()?),
+            "error" => StatementKind::Error(self.parse_expression()?),
+            "at-root" => self.parse_at_root()?,
+            "media" => {
+                let query =
+                    self.parse_raw_interpolation(|c| matches!(c, '{' | ';' | '}'), false)?;
+                let body = self.parse_block()?;
+                StatementKind::Media { query, body }
+            }
+            "supports" => {
+                let condition =
+                    self.parse_raw_interpolation(|c| matches!(c, '{' | ';' | '}'), false)?;
+                let body = self.parse_block()?;
+                StatementKind::Supports { condition, body }
+            }
+            "else" => {
+                return Err(self.error_at(
+                    "This at-rule is not allowed here.".to_owned(),
+                    start,
+                    name_start + name.len(),
+                ))
+            }
+            _ => self.parse_unknown_at_rule(name)?,
+        };
+
+        let span = self.span(start);
+
+        let has_block = match &kind {
+            StatementKind::AtRule { body, .. } => body.is_some(),
+            StatementKind::Include { content, .. } => content.is_some(),
+            StatementKind::Mixin { .. }
+            | StatementKind::Function { .. }
+            | StatementKind::If { .. }
+            | StatementKind::Each { .. }
+            | StatementKind::For { .. }
+            | StatementKind::While { .. }
+            | StatementKind::AtRoot { .. }
+            | StatementKind::Media { .. }
+            | StatementKind::Supports { .. } => true,
+            _ => false,
+        };
+
+        if !has_block {
+            self.expect_statement_end()?;
+        }
+
+        Ok(Statement { kind, span })
+    }
+
+    fn parse_use(&mut self) -> SassResult<StatementKind> {
+        let url = self.parse_plain_string()?;
+        self.whitespace();
+
+        let namespace = if self.scan_keyword("as") {
+            self.whitespace();
+            if self.eat('*') {
+                Some("*".to_owned())
+            } else {
+                Some(self.parse_identifier()?)
+            }
+        } else {
+            None
+        };
+
+        self.whitespace();
+
+        let configuration = if self.scan_keyword("with") {
+            self.whitespace();
+            self.parse_configuration(false)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(StatementKind::Use {
+            url,
+            namespace,
+            configuration,
+        })
+    }
+
+    fn parse_forward(&mut self) -> SassResult<StatementKind> {
+        let url = self.parse_plain_string()?;
+        self.whitespace();
+
+        let prefix = if self.scan_keyword("as") {
+            self.whitespace();
+            let prefix = self.parse_identifier()?;
+            self.expect('*')?;
+            Some(prefix)
+        } else {
+            None
+        };
+
+        self.whitespace();
+
+        let mut show = None;
+        let mut hide = None;
+
+        if self.scan_keyword("show") {
+            self.whitespace();
+            show = Some(self.parse_member_list()?);
+        } else if self.scan_keyword("hide") {
+            self.whitespace();
+            hide = Some(self.parse_member_list()?);
+        }
+
+        self.whitespace();
+
+        let configuration = if self.scan_keyword("with") {
+            self.whitespace();
+            self.parse_configuration(true)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(StatementKind::Forward {
+            url,
+            prefix,
+            show,
+            hide,
+            configuration,
+        })
+    }
+
+    /// Parses the members passed to `show` or `hide`, e.g. `a, $b`
+    fn parse_member_list(&mut self) -> SassResult<Vec<String>> {
+        let mut members = Vec::new();
+
+        loop {
+            self.whitespace();
+
+            if self.eat('$') {
+                members.push(format!("${}", self.parse_identifier()?));
+            } else {
+                members.push(self.parse_identifier()?);
+            }
+
+            self.whitespace();
+
+            if !self.eat(',') {
+                break;
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Parses the `with` clause of a `@use` or `@forward` rule
+    fn parse_configuration(&mut self, allow_guarded: bool) -> SassResult<Vec<ConfiguredVariable>> {
+        let mut configuration = Vec::new();
+
+        self.expect('(')?;
+
+        loop {
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            let start = self.pos;
+            self.expect('$')?;
+            let name = self.parse_identifier()?;
+            self.whitespace();
+            self.expect(':')?;
+            self.whitespace();
+            let value = self.parse_space_list()?;
+            self.whitespace();
+
+            let guarded = allow_guarded && self.scan_flag("default");
+
+            configuration.push(ConfiguredVariable {
+                name,
+                value,
+                guarded,
+                span: self.span(start),
+            });
+
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            self.expect(',')?;
+        }
+
+        Ok(configuration)
+    }
+
+    fn parse_import(&mut self) -> SassResult<StatementKind> {
+        let mut imports = Vec::new();
+
+        loop {
+            self.whitespace();
+
+            let state = self.state();
+            let start = self.pos;
+
+            match self.peek() {
+                Some('"') | Some('\'') => {
+                    let url = self.parse_quoted_string()?;
+                    self.whitespace();
+
+                    let is_dynamic =
+                        matches!(self.peek(), Some(',') | Some(';') | Some('}') | None)
+                            && url
+                                .as_plain()
+                                .map_or(false, |url| !is_plain_css_import(url));
+
+                    if is_dynamic {
+                        imports.push(Import::Dynamic {
+                            url: url.as_plain().unwrap_or_default().to_owned(),
+                            span: url.span,
+                        });
+                    } else {
+                        self.restore(state);
+                        imports.push(Import::Static(
+                            self.parse_raw_interpolation(|c| matches!(c, ',' | ';' | '}'), false)?,
+                        ));
+                    }
+                }
+                Some(..)
+                    if self
+                        .rest()
+                        .get(..4)
+                        .map_or(false, |s| s.eq_ignore_ascii_case("url(")) =>
+                {
+                    imports.push(Import::Static(
+                        self.parse_raw_interpolation(|c| matches!(c, ',' | ';' | '}'), false)?,
+                    ));
+                }
+                _ => return Err(self.error_at("Expected string.".to_owned(), start, start + 1)),
+            }
+
+            self.whitespace();
+
+            if !self.eat(',') {
+                break;
+            }
+        }
+
+        Ok(StatementKind::Import(imports))
+    }
+
+    /// Parses a `@mixin` or `@function` rule
+    fn parse_callable(&mut self, kind: &str) -> SassResult<StatementKind> {
+        let name = self.parse_identifier()?;
+        self.whitespace();
+
+        let parameters = if kind == "mixin" && self.peek() != Some('(') {
+            Parameters::default()
+        } else {
+            self.parse_parameters()?
+        };
+
+        self.whitespace();
+        let body = self.parse_block()?;
+
+        Ok(if kind == "mixin" {
+            StatementKind::Mixin {
+                name,
+                parameters,
+                body,
+            }
+        } else {
+            StatementKind::Function {
+                name,
+                parameters,
+                body,
+            }
+        })
+    }
+
+    fn parse_include(&mut self) -> SassResult<StatementKind> {
+        let mut namespace = None;
+        let mut name = self.parse_identifier()?;
+
+        if self.peek() == Some('.') && self.peek_nth(1).map_or(false, is_name_start) {
+            self.next_char();
+            namespace = Some(name);
+            name = self.parse_identifier()?;
+        }
+
+        self.whitespace();
+
+        let arguments = if self.peek() == Some('(') {
+            self.parse_arguments()?
+        } else {
+            Arguments::default()
+        };
+
+        self.whitespace();
+
+        let content_parameters = if self.scan_keyword("using") {
+            self.whitespace();
+            Some(self.parse_parameters()?)
+        } else {
+            None
+        };
+
+        self.whitespace();
+
+        let content = if self.peek() == Some('{') {
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+
+        Ok(StatementKind::Include {
+            namespace,
+            name,
+            arguments,
+            content_parameters,
+            content,
+        })
+    }
+
+    fn parse_if(&mut self) -> SassResult<StatementKind> {
+        let condition = self.parse_expression()?;
+        self.whitespace();
+        let body = self.parse_block()?;
+
+        let mut clauses = vec![(condition, body)];
+        let mut else_body = None;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            if !self.scan_exact_keyword("@else") {
+                self.restore(state);
+                break;
+            }
+
+            self.whitespace();
+
+            if self.scan_keyword("if") {
+                self.whitespace();
+                let condition = self.parse_expression()?;
+                self.whitespace();
+                let body = self.parse_block()?;
+                clauses.push((condition, body));
+            } else {
+                else_body = Some(self.parse_block()?);
+                break;
+            }
+        }
+
+        Ok(StatementKind::If { clauses, else_body })
+    }
+
+    fn parse_each(&mut self) -> SassResult<StatementKind> {
+        let mut variables = Vec::new();
+
+        loop {
+            self.expect('$')?;
+            variables.push(self.parse_identifier()?);
+            self.whitespace();
+
+            if !self.eat(',') {
+                break;
+            }
+
+            self.whitespace();
+        }
+
+        self.expect_keyword("in")?;
+        self.whitespace();
+        let list = self.parse_expression()?;
+        self.whitespace();
+        let body = self.parse_block()?;
+
+        Ok(StatementKind::Each {
+            variables,
+            list,
+            body,
+        })
+    }
+
+    fn parse_for(&mut self) -> SassResult<StatementKind> {
+        self.expect('$')?;
+        let variable = self.parse_identifier()?;
+        self.whitespace();
+        self.expect_keyword("from")?;
+        self.whitespace();
+
+        self.stop_words = &["to", "through"];
+        let from = self.parse_expression();
+        self.stop_words = &[];
+        let from = from?;
+
+        self.whitespace();
+
+        let inclusive = if self.scan_keyword("through") {
+            true
+        } else if self.scan_keyword("to") {
+            false
+        } else {
+            return Err(self.error("Expected \"to\" or \"through\".".to_owned()));
+        };
+
+        self.whitespace();
+        let to = self.parse_expression()?;
+        self.whitespace();
+        let body = self.parse_block()?;
+
+        Ok(StatementKind::For {
+            variable,
+            from,
+            to,
+            inclusive,
+            body,
+        })
+    }
+
+    fn parse_at_root(&mut self) -> SassResult<StatementKind> {
+        if self.peek() == Some('{') {
+            return Ok(StatementKind::AtRoot {
+                query: None,
+                body: self.parse_block()?,
+            });
+        }
+
+        if self.peek() == Some('(') {
+            let query = self.parse_raw_interpolation(|c| matches!(c, '{' | ';' | '}'), false)?;
+            return Ok(StatementKind::AtRoot {
+                query: Some(query),
+                body: self.parse_block()?,
+            });
+        }
+
+        // `@at-root a { ... }` is shorthand for `@at-root { a { ... } }`
+        let rule = self.parse_style_rule()?;
+
+        Ok(StatementKind::AtRoot {
+            query: None,
+            body: vec![rule],
+        })
+    }
+
+    fn parse_unknown_at_rule(&mut self, name: String) -> SassResult<StatementKind> {
+        let prelude = match self.peek() {
+            Some('{') | Some(';') | Some('}') | None => None,
+            Some(..) => {
+                Some(self.parse_raw_interpolation(|c| matches!(c, '{' | ';' | '}'), false)?)
+            }
+        };
+
+        let body = if self.peek() == Some('{') {
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+
+        Ok(StatementKind::AtRule {
+            name,
+            prelude,
+            body,
+        })
+    }
+
+    fn parse_parameters(&mut self) -> SassResult<Parameters> {
+        let mut parameters = Parameters::default();
+
+        self.expect('(')?;
+
+        loop {
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            let start = self.pos;
+            self.expect('$')?;
+            let name = self.parse_identifier()?;
+            self.whitespace();
+
+            if self.eat_str("...") {
+                parameters.rest = Some(name);
+                self.whitespace();
+                self.expect(')')?;
+                break;
+            }
+
+            let default = if self.eat(':') {
+                self.whitespace();
+                Some(self.parse_space_list()?)
+            } else {
+                None
+            };
+
+            parameters.parameters.push(Parameter {
+                name,
+                default,
+                span: self.span(start),
+            });
+
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            self.expect(',')?;
+        }
+
+        Ok(parameters)
+    }
+
+    fn parse_arguments(&mut self) -> SassResult<Arguments> {
+        let mut arguments = Arguments::default();
+
+        self.expect('(')?;
+
+        loop {
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            if let Some(name) = self.try_parse_argument_name()? {
+                self.whitespace();
+                let value = self.parse_argument_value()?;
+                arguments.named.push((name, value));
+            } else {
+                let value = self.parse_argument_value()?;
+                self.whitespace();
+
+                if self.eat_str("...") {
+                    if arguments.rest.is_none() {
+                        arguments.rest = Some(Box::new(value));
+                    } else {
+                        arguments.keyword_rest = Some(Box::new(value));
+                    }
+                } else if !arguments.named.is_empty() || arguments.rest.is_some() {
+                    return Err(self.error_at(
+                        "Positional arguments must come before keyword arguments.".to_owned(),
+                        value.span.start,
+                        value.span.end,
+                    ));
+                } else {
+                    arguments.positional.push(value);
+                }
+            }
+
+            self.whitespace();
+
+            if self.eat(')') {
+                break;
+            }
+
+            self.expect(',')?;
+        }
+
+        Ok(arguments)
+    }
+
+    /// Parses the value of an argument
+    ///
+    /// For compatibility with Internet Explorer filters, an argument such as
+    /// `opacity=50` is parsed as an unquoted string.
+    fn parse_argument_value(&mut self) -> SassResult<Expression> {
+        let value = self.parse_space_list()?;
+
+        let state = self.state();
+        self.whitespace();
+
+        if self.peek() != Some('=') || self.peek_nth(1) == Some('=') {
+            self.restore(state);
+            return Ok(value);
+        }
+
+        self.next_char();
+        self.whitespace();
+        let rhs = self.parse_space_list()?;
+
+        let span = Span {
+            start: value.span.start,
+            end: rhs.span.end,
+        };
+
+        Ok(Expression {
+            kind: ExpressionKind::String {
+                contents: Interpolation {
+                    contents: vec![InterpolationPart::Text(
+                        self.input[span.start..span.end].to_owned(),
+                    )],
+                    span,
+                },
+                quoted: false,
+            },
+            span,
+        })
+    }
+
+    /// Parses `$name:` at the start of a keyword argument, or returns `None`
+    /// if the argument is positional
+    fn try_parse_argument_name(&mut self) -> SassResult<Option<String>> {
+        if self.peek() != Some('$') {
+            return Ok(None);
+        }
+
+        let state = self.state();
+        self.next_char();
+        let name = self.parse_identifier()?;
+        self.whitespace();
+
+        if self.eat(':') {
+            Ok(Some(name))
+        } else {
+            self.restore(state);
+            Ok(None)
+        }
+    }
+
+    /// Whether the next character can begin an element of a space-separated
+    /// list
+    fn can_start_expression(&self) -> bool {
+        match self.peek() {
+            None | Some(';') | Some('}') | Some('{') | Some(')') | Some(']') | Some(',')
+            | Some(':') | Some('=') | Some('<') | Some('>') | Some('*') | Some('%') | Some('/') => {
+                false
+            }
+            Some('!') => self.looks_like_flag("important"),
+            Some('.') => self.looks_like_number(0),
+            Some(..) => !self
+                .stop_words
+                .iter()
+                .any(|word| self.looks_like_keyword(word)),
+        }
+    }
+
+    fn binary(operator: BinaryOperator, lhs: Expression, rhs: Expression) -> Expression {
+        Expression {
+            span: Span {
+                start: lhs.span.start,
+                end: rhs.span.end,
+            },
+            kind: ExpressionKind::BinaryOperation {
+                operator,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
+        }
+    }
+
+    fn list(elements: Vec<Expression>, separator: ListSeparator) -> Expression {
+        let span = Span {
+            start: elements[0].span.start,
+            end: elements[elements.len() - 1].span.end,
+        };
+
+        Expression {
+            kind: ExpressionKind::List {
+                elements,
+                separator,
+                bracketed: false,
+            },
+            span,
+        }
+    }
+
+    /// Parses an expression, including comma-separated lists
+    fn parse_expression(&mut self) -> SassResult<Expression> {
+        let first = self.parse_space_list()?;
+
+        let state = self.state();
+        self.whitespace();
+
+        if self.peek() != Some(',') {
+            self.restore(state);
+            return Ok(first);
+        }
+
+        let mut elements = vec![first];
+
+        while self.peek() == Some(',') {
+            self.pos += 1;
+            self.whitespace();
+
+            if !self.can_start_expression() {
+                break;
+            }
+
+            elements.push(self.parse_space_list()?);
+
+            let state = self.state();
+            self.whitespace();
+
+            if self.peek() != Some(',') {
+                self.restore(state);
+                break;
+            }
+        }
+
+        Ok(Self::list(elements, ListSeparator::Comma))
+    }
+
+    /// Parses an expression that is not a comma-separated list
+    fn parse_space_list(&mut self) -> SassResult<Expression> {
+        let mut elements = vec![self.parse_or()?];
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            if !self.can_start_expression() {
+                self.restore(state);
+                break;
+            }
+
+            elements.push(self.parse_or()?);
+        }
+
+        if elements.len() == 1 {
+            Ok(elements.pop().unwrap())
+        } else {
+            Ok(Self::list(elements, ListSeparator::Space))
+        }
+    }
+
+    fn parse_or(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_and()?;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            if !self.scan_exact_keyword("or") {
+                self.restore(state);
+                return Ok(lhs);
+            }
+
+            self.whitespace();
+            let rhs = self.parse_and()?;
+            lhs = Self::binary(BinaryOperator::Or, lhs, rhs);
+        }
+    }
+
+    fn parse_and(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_equality()?;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            if !self.scan_exact_keyword("and") {
+                self.restore(state);
+                return Ok(lhs);
+            }
+
+            self.whitespace();
+            let rhs = self.parse_equality()?;
+            lhs = Self::binary(BinaryOperator::And, lhs, rhs);
+        }
+    }
+
+    fn parse_equality(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_relational()?;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            let operator = if self.eat_str("==") {
+                BinaryOperator::Equal
+            } else if self.eat_str("!=") {
+                BinaryOperator::NotEqual
+            } else {
+                self.restore(state);
+                return Ok(lhs);
+            };
+
+            self.whitespace();
+            let rhs = self.parse_relational()?;
+            lhs = Self::binary(operator, lhs, rhs);
+        }
+    }
+
+    fn parse_relational(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_additive()?;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            let operator = if self.eat_str("<=") {
+                BinaryOperator::LessThanOrEqual
+            } else if self.eat_str(">=") {
+                BinaryOperator::GreaterThanOrEqual
+            } else if self.eat('<') {
+                BinaryOperator::LessThan
+            } else if self.eat('>') {
+                BinaryOperator::GreaterThan
+            } else {
+                self.restore(state);
+                return Ok(lhs);
+            };
+
+            self.whitespace();
+            let rhs = self.parse_additive()?;
+            lhs = Self::binary(operator, lhs, rhs);
+        }
+    }
+
+    fn parse_additive(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_multiplicative()?;
+
+        loop {
+            let state = self.state();
+            let had_whitespace = self.whitespace();
+
+            let operator = match self.peek() {
+                Some('+') => BinaryOperator::Plus,
+                Some('-') => BinaryOperator::Minus,
+                _ => {
+                    self.restore(state);
+                    return Ok(lhs);
+                }
+            };
+
+            // `a -b` is a space-separated list rather than a subtraction
+            if had_whitespace && !self.peek_nth(1).map_or(true, char::is_whitespace) {
+                self.restore(state);
+                return Ok(lhs);
+            }
+
+            self.next_char();
+            self.whitespace();
+            let rhs = self.parse_multiplicative()?;
+            lhs = Self::binary(operator, lhs, rhs);
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> SassResult<Expression> {
+        let mut lhs = self.parse_unary()?;
+
+        loop {
+            let state = self.state();
+            self.whitespace();
+
+            let operator = match self.peek() {
+                Some('*') => BinaryOperator::Times,
+                Some('/') => BinaryOperator::Divide,
+                Some('%') => BinaryOperator::Modulo,
+                _ => {
+                    self.restore(state);
+                    return Ok(lhs);
+                }
+            };
+
+            self.next_char();
+            self.whitespace();
+            let rhs = self.parse_unary()?;
+            lhs = Self::binary(operator, lhs, rhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+
+        let operator = match self.peek() {
+            Some('+') | Some('-') if self.looks_like_number(1) => return self.parse_number(),
+            Some('-') if self.looks_like_identifier() => return self.parse_primary(),
+            Some('+') => UnaryOperator::Plus,
+            Some('-') => UnaryOperator::Minus,
+            Some('/') => UnaryOperator::Divide,
+            _ if self.scan_exact_keyword("not") => UnaryOperator::Not,
+            _ => return self.parse_primary(),
+        };
+
+        if operator != UnaryOperator::Not {
+            self.next_char();
+        }
+
+        self.whitespace();
+        let operand = self.parse_unary()?;
+
+        Ok(Expression {
+            kind: ExpressionKind::UnaryOperation {
+                operator,
+                operand: Box::new(operand),
+            },
+            span: self.span(start),
+        })
+    }
+
+    /// Whether a number begins `offset` characters ahead
+    fn looks_like_number(&self, offset: usize) -> bool {
+        match self.peek_nth(offset) {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('.') => self
+                .peek_nth(offset + 1)
+                .map_or(false, |c| c.is_ascii_digit()),
+            _ => false,
+        }
+    }
+
+    fn parse_primary(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+
+        let kind = match self.peek() {
+            Some('(') => return self.parse_parenthesized(),
+            Some('[') => return self.parse_bracketed_list(),
+            Some('"') | Some('\'') => ExpressionKind::String {
+                contents: self.parse_quoted_string()?,
+                quoted: true,
+            },
+            Some('#') if !self.looks_like_interpolation() => return self.parse_hash(),
+            Some('$') => {
+                self.next_char();
+                ExpressionKind::Variable {
+                    namespace: None,
+                    name: self.parse_identifier()?,
+                }
+            }
+            Some('&') => {
+                self.next_char();
+                ExpressionKind::ParentSelector
+            }
+            Some('!') if self.scan_flag("important") => ExpressionKind::Important,
+            _ if self.looks_like_number(0) => return self.parse_number(),
+            _ if self.looks_like_identifier() || self.looks_like_interpolation() => {
+                return self.parse_identifier_like()
+            }
+            _ => return Err(self.error("Expected expression.".to_owned())),
+        };
+
+        Ok(Expression {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    fn parse_number(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.next_char();
+        }
+
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.next_char();
+        }
+
+        if self.peek() == Some('.') && self.looks_like_number(1) {
+            self.next_char();
+            while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                self.next_char();
+            }
+        }
+
+        if matches!(self.peek(), Some('e') | Some('E'))
+            && (self.looks_like_number(1)
+                || (matches!(self.peek_nth(1), Some('+') | Some('-')) && self.looks_like_number(2)))
+        {
+            self.next_char();
+            self.eat('+');
+            self.eat('-');
+            while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                self.next_char();
+            }
+        }
+
+        let value = self.input[start..self.pos]
+            .parse::<f64>()
+            .map_err(|_| self.error_at("Expected number.".to_owned(), start, self.pos))?;
+
+        let unit = if self.eat('%') {
+            Some("%".to_owned())
+        } else if self.looks_like_identifier()
+            && !self.rest().starts_with("--")
+            && !self.rest().starts_with("-#")
+        {
+            Some(self.parse_unit())
+        } else {
+            None
+        };
+
+        Ok(Expression {
+            kind: ExpressionKind::Number { value, unit },
+            span: self.span(start),
+        })
+    }
+
+    /// Parses the unit of a number, which unlike an identifier stops before
+    /// a `-` that is followed by a number, as in `1px-2px`
+    fn parse_unit(&mut self) -> String {
+        let mut unit = String::new();
+
+        if self.peek() == Some('-') {
+            unit.push('-');
+            self.next_char();
+        }
+
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.parse_escape(&mut unit);
+                continue;
+            }
+
+            if c == '-' && self.looks_like_number(1) || !is_name_char(c) {
+                break;
+            }
+
+            unit.push(c);
+            self.next_char();
+        }
+
+        unit
+    }
+
+    /// Parses a hex color, or an unquoted string beginning with `#`
+    fn parse_hash(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+        self.expect('#')?;
+
+        let text = self.parse_interpolated_identifier()?;
+
+        let kind = match text.as_plain() {
+            Some(text)
+                if matches!(text.len(), 3 | 4 | 6 | 8)
+                    && text.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                ExpressionKind::Color(text.to_owned())
+            }
+            _ => {
+                let mut builder = InterpolationBuilder::default();
+                builder.push('#');
+                builder.add_interpolation(text);
+                ExpressionKind::String {
+                    contents: builder.finish(self.span(start)),
+                    quoted: false,
+                }
+            }
+        };
+
+        Ok(Expression {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    /// Parses an expression beginning with an identifier, such as a keyword,
+    /// an unquoted string, a function call, or a module member
+    fn parse_identifier_like(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+        let ident = self.parse_interpolated_identifier()?;
+
+        let kind = match ident.as_plain() {
+            Some(plain)
+                if self.peek() == Some('.')
+                    && self
+                        .peek_nth(1)
+                        .map_or(false, |c| c == '$' || is_name_start(c)) =>
+            {
+                let namespace = Some(plain.to_owned());
+                self.next_char();
+
+                if self.eat('$') {
+                    ExpressionKind::Variable {
+                        namespace,
+                        name: self.parse_identifier()?,
+                    }
+                } else {
+                    let name_start = self.pos;
+                    let name = self.parse_identifier()?;
+                    let name = Interpolation {
+                        contents: vec![InterpolationPart::Text(name)],
+                        span: self.span(name_start),
+                    };
+
+                    if self.peek() != Some('(') {
+                        return Err(self.error("expected \"(\".".to_owned()));
+                    }
+
+                    ExpressionKind::FunctionCall {
+                        namespace,
+                        name,
+                        arguments: self.parse_arguments()?,
+                    }
+                }
+            }
+            Some(plain) if plain.eq_ignore_ascii_case("u") && self.peek() == Some('+') => {
+                ExpressionKind::String {
+                    contents: self.parse_unicode_range(start),
+                    quoted: false,
+                }
+            }
+            Some(plain) if plain.eq_ignore_ascii_case("progid") && self.peek() == Some(':') => {
+                ExpressionKind::String {
+                    contents: self.parse_progid(start)?,
+                    quoted: false,
+                }
+            }
+            Some(plain)
+                if self.peek() == Some('(')
+                    && (unvendor(plain).eq_ignore_ascii_case("element")
+                        || unvendor(plain).eq_ignore_ascii_case("expression")) =>
+            {
+                ExpressionKind::String {
+                    contents: self.parse_special_function(start)?,
+                    quoted: false,
+                }
+            }
+            Some(plain)
+                if unvendor(plain).eq_ignore_ascii_case("url") && self.peek() == Some('(') =>
+            {
+                match self.try_parse_url(start)? {
+                    Some(contents) => ExpressionKind::String {
+                        contents,
+                        quoted: false,
+                    },
+                    None => ExpressionKind::FunctionCall {
+                        namespace: None,
+                        name: ident,
+                        arguments: self.parse_arguments()?,
+                    },
+                }
+            }
+            _ if self.peek() == Some('(') => ExpressionKind::FunctionCall {
+                namespace: None,
+                name: ident,
+                arguments: self.parse_arguments()?,
+            },
+            Some("true") => ExpressionKind::Boolean(true),
+            Some("false") => ExpressionKind::Boolean(false),
+            Some("null") => ExpressionKind::Null,
+            _ => ExpressionKind::String {
+                contents: ident,
+                quoted: false,
+            },
+        };
+
+        Ok(Expression {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    /// Parses the rest of a unicode range such as `U+0-7F` or `U+4??`
+    fn parse_unicode_range(&mut self, start: usize) -> Interpolation {
+        self.next_char();
+
+        while self
+            .peek()
+            .map_or(false, |c| c.is_ascii_hexdigit() || c == '?')
+        {
+            self.next_char();
+        }
+
+        if self.peek() == Some('-') && self.peek_nth(1).map_or(false, |c| c.is_ascii_hexdigit()) {
+            self.next_char();
+            while self.peek().map_or(false, |c| c.is_ascii_hexdigit()) {
+                self.next_char();
+            }
+        }
+
+        let span = self.span(start);
+
+        Interpolation {
+            contents: vec![InterpolationPart::Text(
+                self.input[span.start..span.end].to_owned(),
+            )],
+            span,
+        }
+    }
+
+    /// Parses the rest of a Microsoft filter such as
+    /// `progid:DXImageTransform.Microsoft.gradient(...)`
+    fn parse_progid(&mut self, start: usize) -> SassResult<Interpolation> {
+        self.expect(':')?;
+
+        while self.peek().map_or(false, |c| is_name_char(c) || c == '.') {
+            self.next_char();
+        }
+
+        self.parse_special_function(start)
+    }
+
+    /// Parses the arguments of a function such as `expression(...)`, whose
+    /// contents are not parsed as Sass
+    fn parse_special_function(&mut self, start: usize) -> SassResult<Interpolation> {
+        let mut builder = InterpolationBuilder::default();
+        builder.push_str(&self.input[start..self.pos]);
+
+        let arguments = self.parse_raw_interpolation(|c| c != '(', false)?;
+        builder.add_interpolation(arguments);
+
+        Ok(builder.finish(self.span(start)))
+    }
+
+    /// Parses the unquoted contents of `url(...)`, returning `None` if it
+    /// contains an expression instead, e.g. `url("a" + $b)`
+    fn try_parse_url(&mut self, start: usize) -> SassResult<Option<Interpolation>> {
+        let state = self.state();
+        let mut builder = InterpolationBuilder::default();
+
+        self.expect('(')?;
+        builder.push_str(&self.input[start..self.pos]);
+        self.whitespace_without_comments();
+
+        loop {
+            match self.peek() {
+                Some('"') | Some('\'') | Some('$') | Some('(') | None => {
+                    self.restore(state);
+                    return Ok(None);
+                }
+                Some(')') => {
+                    builder.push(')');
+                    self.next_char();
+                    break;
+                }
+                Some('#') if self.looks_like_interpolation() => {
+                    builder.add_expression(self.parse_single_interpolation()?);
+                }
+                Some(c) if c.is_whitespace() => {
+                    self.whitespace_without_comments();
+                    if self.peek() != Some(')') {
+                        self.restore(state);
+                        return Ok(None);
+                    }
+                }
+                Some(c) => {
+                    builder.push(c);
+                    self.next_char();
+                }
+            }
+        }
+
+        Ok(Some(builder.finish(self.span(start))))
+    }
+
+    /// Parses an expression beginning with `(`, which may be a map, an empty
+    /// list, or a parenthesized expression
+    fn parse_parenthesized(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+        self.expect('(')?;
+        self.whitespace();
+
+        if self.eat(')') {
+            return Ok(Expression {
+                kind: ExpressionKind::List {
+                    elements: Vec::new(),
+                    separator: ListSeparator::Undecided,
+                    bracketed: false,
+                },
+                span: self.span(start),
+            });
+        }
+
+        let stop_words = std::mem::replace(&mut self.stop_words, &[]);
+        let result = self.parse_parenthesized_contents(start);
+        self.stop_words = stop_words;
+        result
+    }
+
+    fn parse_parenthesized_contents(&mut self, start: usize) -> SassResult<Expression> {
+        let first = self.parse_space_list()?;
+        self.whitespace();
+
+        if self.eat(':') {
+            self.whitespace();
+            let mut pairs = vec![(first, self.parse_space_list()?)];
+
+            loop {
+                self.whitespace();
+
+                if !self.eat(',') {
+                    break;
+                }
+
+                self.whitespace();
+
+                if self.peek() == Some(')') {
+                    break;
+                }
+
+                let key = self.parse_space_list()?;
+                self.whitespace();
+                self.expect(':')?;
+                self.whitespace();
+                let value = self.parse_space_list()?;
+                pairs.push((key, value));
+            }
+
+            self.expect(')')?;
+
+            return Ok(Expression {
+                kind: ExpressionKind::Map(pairs),
+                span: self.span(start),
+            });
+        }
+
+        let inner = if self.peek() == Some(',') {
+            let mut elements = vec![first];
+
+            while self.eat(',') {
+                self.whitespace();
+
+                if self.peek() == Some(')') {
+                    break;
+                }
+
+                elements.push(self.parse_space_list()?);
+                self.whitespace();
+            }
+
+            Self::list(elements, ListSeparator::Comma)
+        } else {
+            first
+        };
+
+        self.expect(')')?;
+
+        Ok(Expression {
+            kind: ExpressionKind::Parenthesized(Box::new(inner)),
+            span: self.span(start),
+        })
+    }
+
+    fn parse_bracketed_list(&mut self) -> SassResult<Expression> {
+        let start = self.pos;
+        self.expect('[')?;
+        self.whitespace();
+
+        if self.eat(']') {
+            return Ok(Expression {
+                kind: ExpressionKind::List {
+                    elements: Vec::new(),
+                    separator: ListSeparator::Undecided,
+                    bracketed: true,
+                },
+                span: self.span(start),
+            });
+        }
+
+        let stop_words = std::mem::replace(&mut self.stop_words, &[]);
+        let inner = self.parse_expression();
+        self.stop_words = stop_words;
+        let inner = inner?;
+
+        self.whitespace();
+        self.expect(']')?;
+
+        let kind = match inner.kind {
+            ExpressionKind::List {
+                elements,
+                separator,
+                bracketed: false,
+            } => ExpressionKind::List {
+                elements,
+                separator,
+                bracketed: true,
+            },
+            kind => ExpressionKind::List {
+                elements: vec![Expression {
+                    kind,
+                    span: inner.span,
+                }],
+                separator: ListSeparator::Undecided,
+                bracketed: true,
+            },
+        };
+
+        Ok(Expression {
+            kind,
+            span: self.span(start),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+/// The query passed to `@at-root`, e.g. `@at-root (without: media)`
+///
+/// This determines which of the enclosing rules the contents of the
+/// `@at-root` rule are moved outside of.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct AtRootQuery {
+    /// Whether the query includes or excludes the named rules,
+    /// i.e. `with` or `without`
+    include: bool,
+
+    /// The lowercased names of the rules this query applies to
+    ///
+    /// These may be the names of at-rules, `rule` for style rules,
+    /// or `all` for every rule
+    names: HashSet<String>,
+}
+
+impl AtRootQuery {
+    pub fn new(include: bool, names: HashSet<String>) -> Self {
+        Self { include, names }
+    }
+
+    /// Whether the contents of the `@at-root` rule should be moved outside
+    /// of the at-rule `name`
+    pub fn excludes_name(&self, name: &str) -> bool {
+        (self.names.contains("all") || self.names.contains(&name.to_ascii_lowercase()))
+            != self.include
+    }
+
+    /// Whether the contents of the `@at-root` rule should be moved outside
+    /// of any enclosing style rules
+    pub fn excludes_style_rules(&self) -> bool {
+        (self.names.contains("all") || self.names.contains("rule")) != self.include
+    }
+
+    /// Whether this query is equivalent to `(without: rule)`, in which case only
+    /// style rules are excluded
+    pub fn is_default(&self) -> bool {
+        !self.include && self.names.len() == 1 && self.names.contains("rule")
+    }
+}
+
+impl Default for AtRootQuery {
+    /// `@at-root` without a query is equivalent to `@at-root (without: rule)`
+    fn default() -> Self {
+        let mut names = HashSet::new();
+        names.insert("rule".to_owned());
+        Self {
+            include: false,
+            names,
+        }
+    }
+}
@@ -9,6 +9,10 @@ pub(crate) struct Function {
     pub args: FuncArgs,
     pub body: Vec<Token>,
     pub declared_at_root: bool,
+
+    /// The id of the module this function was declared in, or `None` if it
+    /// was declared in the entrypoint
+    pub module: Option<usize>,
     pos: Span,
 }
 
@@ -27,12 +31,19 @@ impl PartialEq for Function {
 impl Eq for Function {}
 
 impl Function {
-    pub fn new(args: FuncArgs, body: Vec<Token>, declared_at_root: bool, pos: Span) -> Self {
+    pub fn new(
+        args: FuncArgs,
+        body: Vec<Token>,
+        declared_at_root: bool,
+        module: Option<usize>,
+        pos: Span,
+    ) -> Self {
         Function {
             args,
             body,
             pos,
             declared_at_root,
+            module,
         }
     }
 }
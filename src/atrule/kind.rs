@@ -69,6 +69,22 @@ pub enum AtRuleKind {
     Unknown(String),
 }
 
+impl AtRuleKind {
+    /// Whether or not this at-rule only exists in Sass, and so is not
+    /// allowed in plain CSS files
+    pub const fn is_sass_only(&self) -> bool {
+        !matches!(
+            self,
+            Self::Import
+                | Self::Charset
+                | Self::Supports
+                | Self::Keyframes
+                | Self::Media
+                | Self::Unknown(..)
+        )
+    }
+}
+
 impl TryFrom<&Spanned<String>> for AtRuleKind {
     type Error = Box<SassError>;
     fn try_from(c: &Spanned<String>) -> Result<Self, Box<SassError>> {
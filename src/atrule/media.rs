@@ -5,10 +5,21 @@ use crate::{parse::Stmt, selector::Selector};
 #[derive(Debug, Clone)]
 pub(crate) struct MediaRule {
     pub super_selector: Selector,
-    pub query: String,
+    pub query: Vec<MediaQuery>,
     pub body: Vec<Stmt>,
 }
 
+impl MediaRule {
+    /// The query list of this rule as it should be emitted
+    pub fn query_string(&self) -> String {
+        self.query
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub(crate) struct MediaQuery {
     /// The modifier, probably either "not" or "only".
@@ -25,12 +36,20 @@ pub(crate) struct MediaQuery {
     pub features: Vec<String>,
 }
 
-#[allow(dead_code)]
-impl MediaQuery {
-    pub fn is_condition(&self) -> bool {
-        self.modifier.is_none() && self.media_type.is_none()
-    }
+/// The result of intersecting two media queries
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) enum MediaQueryMergeResult {
+    /// The queries can never both match
+    Empty,
+
+    /// The queries may both match, but CSS has no way of representing their
+    /// intersection
+    Unrepresentable,
 
+    Success(MediaQuery),
+}
+
+impl MediaQuery {
     pub fn matches_all_types(&self) -> bool {
         self.media_type.is_none()
             || self
@@ -47,9 +66,190 @@ impl MediaQuery {
         }
     }
 
-    #[allow(dead_code, unused_variables)]
-    pub fn merge(other: &Self) -> Self {
-        todo!()
+    /// Parses an already-evaluated media query list, e.g.
+    /// `screen and (min-width: 10px), print`
+    ///
+    /// Returns an error message if `query` is not a valid media query list
+    pub fn parse_list(query: &str) -> Result<Vec<Self>, &'static str> {
+        split_top_level_commas(query)
+            .into_iter()
+            .map(|query| Self::parse(query.trim()))
+            .collect()
+    }
+
+    fn parse(query: &str) -> Result<Self, &'static str> {
+        if query.starts_with('(') {
+            return Ok(Self::condition(parse_features(query)?));
+        }
+
+        let (first, rest) = take_identifier(query)?;
+        let rest = rest.trim_start();
+
+        if rest.is_empty() {
+            return Ok(Self {
+                modifier: None,
+                media_type: Some(first.to_owned()),
+                features: Vec::new(),
+            });
+        }
+
+        let (second, rest) = take_identifier(rest)?;
+        let rest = rest.trim_start();
+
+        if second.eq_ignore_ascii_case("and") {
+            return Ok(Self {
+                modifier: None,
+                media_type: Some(first.to_owned()),
+                features: parse_features(rest)?,
+            });
+        }
+
+        let features = if rest.is_empty() {
+            Vec::new()
+        } else {
+            parse_features(expect_and(rest)?)?
+        };
+
+        Ok(Self {
+            modifier: Some(first.to_owned()),
+            media_type: Some(second.to_owned()),
+            features,
+        })
+    }
+
+    /// Returns a query that matches the intersection of `self` and `other`
+    pub fn merge(&self, other: &Self) -> MediaQueryMergeResult {
+        let our_modifier = self.modifier.as_ref().map(|m| m.to_ascii_lowercase());
+        let our_type = self.media_type.as_ref().map(|t| t.to_ascii_lowercase());
+        let their_modifier = other.modifier.as_ref().map(|m| m.to_ascii_lowercase());
+        let their_type = other.media_type.as_ref().map(|t| t.to_ascii_lowercase());
+
+        if our_type.is_none() && their_type.is_none() {
+            return MediaQueryMergeResult::Success(Self::condition(
+                self.features
+                    .iter()
+                    .chain(other.features.iter())
+                    .cloned()
+                    .collect(),
+            ));
+        }
+
+        let our_negated = our_modifier.as_deref() == Some("not");
+        let their_negated = their_modifier.as_deref() == Some("not");
+
+        let modifier;
+        let media_type;
+        let features;
+
+        if our_negated != their_negated {
+            if our_type == their_type {
+                let (negative, positive) = if our_negated {
+                    (&self.features, &other.features)
+                } else {
+                    (&other.features, &self.features)
+                };
+
+                // if the negative features are a subset of the positive features,
+                // the query is empty. for example, `not screen and (color)` has no
+                // intersection with `screen and (color) and (grid)`
+                return if negative.iter().all(|f| positive.contains(f)) {
+                    MediaQueryMergeResult::Empty
+                } else {
+                    MediaQueryMergeResult::Unrepresentable
+                };
+            } else if self.matches_all_types() || other.matches_all_types() {
+                return MediaQueryMergeResult::Unrepresentable;
+            }
+
+            if our_negated {
+                modifier = their_modifier;
+                media_type = their_type;
+                features = other.features.clone();
+            } else {
+                modifier = our_modifier;
+                media_type = our_type.clone();
+                features = self.features.clone();
+            }
+        } else if our_negated {
+            // css has no way of representing "neither screen nor print"
+            if our_type != their_type {
+                return MediaQueryMergeResult::Unrepresentable;
+            }
+
+            let (more, fewer) = if self.features.len() > other.features.len() {
+                (&self.features, &other.features)
+            } else {
+                (&other.features, &self.features)
+            };
+
+            // if one set of features is a superset of the other, use those
+            // features because they're strictly narrower
+            if !fewer.iter().all(|f| more.contains(f)) {
+                return MediaQueryMergeResult::Unrepresentable;
+            }
+
+            modifier = our_modifier;
+            media_type = our_type.clone();
+            features = more.clone();
+        } else if self.matches_all_types() {
+            modifier = their_modifier;
+            // omit the type if either input query did, since that indicates that
+            // they aren't targeting a browser that requires "all and"
+            media_type = if other.matches_all_types() && our_type.is_none() {
+                None
+            } else {
+                their_type
+            };
+            features = self
+                .features
+                .iter()
+                .chain(other.features.iter())
+                .cloned()
+                .collect();
+        } else if other.matches_all_types() {
+            modifier = our_modifier;
+            media_type = our_type.clone();
+            features = self
+                .features
+                .iter()
+                .chain(other.features.iter())
+                .cloned()
+                .collect();
+        } else if our_type != their_type {
+            return MediaQueryMergeResult::Empty;
+        } else {
+            modifier = our_modifier.or(their_modifier);
+            media_type = our_type.clone();
+            features = self
+                .features
+                .iter()
+                .chain(other.features.iter())
+                .cloned()
+                .collect();
+        }
+
+        // preserve the original case of the modifier and type
+        let media_type = if media_type.is_none() {
+            None
+        } else if media_type == our_type {
+            self.media_type.clone()
+        } else {
+            other.media_type.clone()
+        };
+
+        let modifier = if modifier.is_none() {
+            None
+        } else if modifier == self.modifier.as_ref().map(|m| m.to_ascii_lowercase()) {
+            self.modifier.clone()
+        } else {
+            other.modifier.clone()
+        };
+
+        MediaQueryMergeResult::Success(Self {
+            modifier,
+            media_type,
+            features,
+        })
     }
 }
 
@@ -57,6 +257,7 @@ impl fmt::Display for MediaQuery {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(modifier) = &self.modifier {
             f.write_str(modifier)?;
+            f.write_str(" ")?;
         }
         if let Some(media_type) = &self.media_type {
             f.write_str(media_type)?;
@@ -67,3 +268,92 @@ impl fmt::Display for MediaQuery {
         f.write_str(&self.features.join(" and "))
     }
 }
+
+/// Splits `s` on commas that are not inside of parentheses or quotes
+fn split_top_level_commas(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0_usize;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (idx, c) in s.char_indices() {
+        match (c, quote) {
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(..)) => {}
+            ('(', None) => depth += 1,
+            (')', None) => depth = depth.saturating_sub(1),
+            (',', None) if depth == 0 => {
+                parts.push(&s[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Splits a leading identifier off of `s`
+fn take_identifier(s: &str) -> Result<(&str, &str), &'static str> {
+    let end = s
+        .find(|c: char| c.is_whitespace() || c == '(' || c == ',')
+        .unwrap_or_else(|| s.len());
+
+    if end == 0 {
+        return Err("Expected identifier.");
+    }
+
+    Ok(s.split_at(end))
+}
+
+fn expect_and(s: &str) -> Result<&str, &'static str> {
+    match take_identifier(s) {
+        Ok((and, rest)) if and.eq_ignore_ascii_case("and") => Ok(rest.trim_start()),
+        _ => Err("expected \"and\"."),
+    }
+}
+
+/// Parses a list of parenthesized media features joined by `and`
+fn parse_features(mut s: &str) -> Result<Vec<String>, &'static str> {
+    let mut features = Vec::new();
+
+    loop {
+        if !s.starts_with('(') {
+            return Err("expected \"(\".");
+        }
+
+        let mut depth = 0_usize;
+        let mut quote = None;
+        let mut end = None;
+
+        for (idx, c) in s.char_indices() {
+            match (c, quote) {
+                ('"', None) | ('\'', None) => quote = Some(c),
+                (c, Some(q)) if c == q => quote = None,
+                (_, Some(..)) => {}
+                ('(', None) => depth += 1,
+                (')', None) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(idx + 1);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let end = end.ok_or("expected \")\".")?;
+
+        features.push(s[..end].to_owned());
+        s = s[end..].trim_start();
+
+        if s.is_empty() {
+            return Ok(features);
+        }
+
+        s = expect_and(s)?;
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use codemap::Span;
+
 use crate::{
     args::{CallArgs, FuncArgs},
     error::SassResult,
@@ -12,9 +14,23 @@ pub(crate) type BuiltinMixin = fn(CallArgs, &mut Parser<'_>) -> SassResult<Vec<S
 #[derive(Clone)]
 pub(crate) enum Mixin {
     UserDefined(UserDefinedMixin),
-    Builtin(BuiltinMixin),
+    /// A mixin implemented in rust, along with whether or not
+    /// it accepts a content block
+    Builtin(BuiltinMixin, bool),
 }
 
+impl PartialEq for Mixin {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::UserDefined(u1), Self::UserDefined(u2)) => u1.pos == u2.pos,
+            (Self::Builtin(b1, ..), Self::Builtin(b2, ..)) => *b1 as usize == *b2 as usize,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Mixin {}
+
 impl fmt::Debug for Mixin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -25,7 +41,10 @@ impl fmt::Debug for Mixin {
                 .field("accepts_content_block", &u.accepts_content_block)
                 .field("declared_at_root", &u.declared_at_root)
                 .finish(),
-            Self::Builtin(..) => f.debug_struct("BuiltinMixin").finish(),
+            Self::Builtin(_, accepts_content_block) => f
+                .debug_struct("BuiltinMixin")
+                .field("accepts_content_block", accepts_content_block)
+                .finish(),
         }
     }
 }
@@ -36,14 +55,26 @@ impl Mixin {
         body: Vec<Token>,
         accepts_content_block: bool,
         declared_at_root: bool,
+        module: Option<usize>,
+        pos: Span,
     ) -> Self {
         Mixin::UserDefined(UserDefinedMixin::new(
             args,
             body,
             accepts_content_block,
             declared_at_root,
+            module,
+            pos,
         ))
     }
+
+    /// Whether or not this mixin may be passed a content block
+    pub fn accepts_content(&self) -> bool {
+        match self {
+            Self::UserDefined(u) => u.accepts_content_block,
+            Self::Builtin(_, accepts_content_block) => *accepts_content_block,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +83,11 @@ pub(crate) struct UserDefinedMixin {
     pub body: Vec<Token>,
     pub accepts_content_block: bool,
     pub declared_at_root: bool,
+
+    /// The id of the module this mixin was declared in, or `None` if it
+    /// was declared in the entrypoint
+    pub module: Option<usize>,
+    pos: Span,
 }
 
 impl UserDefinedMixin {
@@ -60,12 +96,16 @@ impl UserDefinedMixin {
         body: Vec<Token>,
         accepts_content_block: bool,
         declared_at_root: bool,
+        module: Option<usize>,
+        pos: Span,
     ) -> Self {
         Self {
             args,
             body,
             accepts_content_block,
             declared_at_root,
+            module,
+            pos,
         }
     }
 }
@@ -87,4 +127,8 @@ pub(crate) struct Content {
     /// Whether or not the mixin this `@content` block is inside of was
     /// declared in the global scope
     pub declared_at_root: bool,
+
+    /// The id of the module containing the `@include`, which the content
+    /// block is evaluated in
+    pub module: Option<usize>,
 }
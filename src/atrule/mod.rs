@@ -1,8 +1,10 @@
+pub(crate) use at_root_query::AtRootQuery;
 pub(crate) use function::Function;
 pub(crate) use kind::AtRuleKind;
 pub(crate) use supports::SupportsRule;
 pub(crate) use unknown::UnknownAtRule;
 
+mod at_root_query;
 mod function;
 pub mod keyframes;
 mod kind;
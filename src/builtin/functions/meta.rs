@@ -98,7 +98,9 @@ pub(crate) fn variable_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> Sa
     args.max_args(1)?;
     match args.get_err(0, "name")? {
         Value::String(s, _) => Ok(Value::bool(
-            parser.scopes.var_exists(s.into(), parser.global_scope),
+            parser
+                .scopes
+                .var_exists(s.into(), &parser.global_scope.borrow()),
         )),
         v => Err((
             format!("$name: {} is not a string.", v.inspect(args.span())?),
@@ -143,7 +145,7 @@ pub(crate) fn global_variable_exists(
             .get(module_name.into(), args.span())?
             .var_exists(name)
     } else {
-        parser.global_scope.var_exists(name)
+        parser.global_scope.borrow().var_exists(name)
     }))
 }
 
@@ -178,7 +180,9 @@ pub(crate) fn mixin_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassR
             .get(module_name.into(), args.span())?
             .mixin_exists(name)
     } else {
-        parser.scopes.mixin_exists(name, parser.global_scope)
+        parser
+            .scopes
+            .mixin_exists(name, &parser.global_scope.borrow())
     }))
 }
 
@@ -214,7 +218,8 @@ pub(crate) fn function_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> Sa
             .get(module_name.into(), args.span())?
             .fn_exists(name)
     } else {
-        parser.scopes.fn_exists(name, parser.global_scope)
+        parser.scopes.fn_exists(name, &parser.global_scope.borrow())
+            || parser.options.custom_function(name).is_some()
     }))
 }
 
@@ -260,12 +265,15 @@ pub(crate) fn get_function(mut args: CallArgs, parser: &mut Parser<'_>) -> SassR
                 span: args.span(),
             })?
     } else {
-        parser.scopes.get_fn(name, parser.global_scope)
+        parser.scopes.get_fn(name, &parser.global_scope.borrow())
     } {
         Some(f) => f,
-        None => match GLOBAL_FUNCTIONS.get(name.as_str()) {
-            Some(f) => SassFunction::Builtin(f.clone(), name),
-            None => return Err((format!("Function not found: {}", name), args.span()).into()),
+        None => match parser.options.custom_function(name) {
+            Some(index) => SassFunction::Custom(index, name),
+            None => match GLOBAL_FUNCTIONS.get(name.as_str()) {
+                Some(f) => SassFunction::Builtin(f.clone(), name),
+                None => return Err((format!("Function not found: {}", name), args.span()).into()),
+            },
         },
     };
 
@@ -9,9 +9,10 @@ use crate::{
         },
         modules::{Module, ModuleConfig},
     },
+    common::Identifier,
     error::SassResult,
     parse::{Parser, Stmt},
-    value::Value,
+    value::{SassMixin, Value},
 };
 
 fn load_css(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Vec<Stmt>> {
@@ -89,6 +90,121 @@ fn module_functions(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<V
     ))
 }
 
+fn module_mixins(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+
+    let module = match args.get_err(0, "module")? {
+        Value::String(s, ..) => s,
+        v => {
+            return Err((
+                format!("$module: {} is not a string.", v.inspect(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+
+    Ok(Value::Map(
+        parser.modules.get(module.into(), args.span())?.mixins(),
+    ))
+}
+
+fn get_mixin(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+
+    let span = args.span();
+
+    let name: Identifier = match args.get_err(0, "name")? {
+        Value::String(s, _) => s.into(),
+        v => {
+            return Err((
+                format!("$name: {} is not a string.", v.inspect(span)?),
+                span,
+            )
+                .into())
+        }
+    };
+
+    let module = match args.default_arg(1, "module", Value::Null)? {
+        Value::String(s, ..) => Some(s),
+        Value::Null => None,
+        v => {
+            return Err((
+                format!("$module: {} is not a string.", v.inspect(span)?),
+                span,
+            )
+                .into())
+        }
+    };
+
+    let mixin = if let Some(module_name) = module {
+        let module = parser.modules.get(module_name.into(), span)?;
+
+        if !module.mixin_exists(name) {
+            return Err((format!("Mixin not found: {}", name), span).into());
+        }
+
+        module.get_mixin(Spanned { node: name, span })?
+    } else {
+        if !parser
+            .scopes
+            .mixin_exists(name, &parser.global_scope.borrow())
+        {
+            return Err((format!("Mixin not found: {}", name), span).into());
+        }
+
+        parser
+            .scopes
+            .get_mixin(Spanned { node: name, span }, &parser.global_scope.borrow())?
+    };
+
+    Ok(Value::MixinRef(SassMixin::new(mixin, name)))
+}
+
+fn accepts_content(mut args: CallArgs, _: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+
+    match args.get_err(0, "mixin")? {
+        Value::MixinRef(mixin) => Ok(Value::bool(mixin.accepts_content())),
+        v => Err((
+            format!(
+                "$mixin: {} is not a mixin reference.",
+                v.inspect(args.span())?
+            ),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn apply(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Vec<Stmt>> {
+    let mixin = match args.get_err(0, "mixin")? {
+        Value::MixinRef(mixin) => mixin,
+        v => {
+            return Err((
+                format!(
+                    "$mixin: {} is not a mixin reference.",
+                    v.inspect(args.span())?
+                ),
+                args.span(),
+            )
+                .into())
+        }
+    };
+
+    // the content block passed to `meta.apply()` is forwarded to the applied mixin
+    let (content, content_args) = match parser.content.last() {
+        Some(content) => (content.content.clone(), content.content_args.clone()),
+        None => (None, None),
+    };
+
+    if content.is_some() && !mixin.accepts_content() {
+        return Err(("Mixin doesn't accept a content block.", args.span()).into());
+    }
+
+    parser.run_mixin(mixin.into_mixin(), args.decrement(), content, content_args)
+}
+
 fn module_variables(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
 
@@ -120,8 +236,12 @@ pub(crate) fn declare(f: &mut Module) {
     f.insert_builtin("content-exists", content_exists);
     f.insert_builtin("module-variables", module_variables);
     f.insert_builtin("module-functions", module_functions);
+    f.insert_builtin("module-mixins", module_mixins);
     f.insert_builtin("get-function", get_function);
+    f.insert_builtin("get-mixin", get_mixin);
+    f.insert_builtin("accepts-content", accepts_content);
     f.insert_builtin("call", call);
 
-    f.insert_builtin_mixin("load-css", load_css);
+    f.insert_builtin_mixin("load-css", load_css, false);
+    f.insert_builtin_mixin("apply", apply, true);
 }
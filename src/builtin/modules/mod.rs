@@ -1,16 +1,28 @@
-use std::collections::BTreeMap;
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 
-use codemap::{Span, Spanned};
+use codemap::{CodeMap, File, Span, Spanned};
 
 use crate::{
-    args::CallArgs,
+    args::{CallArgs, FuncArg, FuncArgs},
     atrule::mixin::{BuiltinMixin, Mixin},
     builtin::Builtin,
     common::{Identifier, QuoteKind},
     error::SassResult,
+    file_cache::{move_tokens, FileCache},
+    lexer::Lexer,
+    logger::Logger,
     parse::Parser,
-    scope::Scope,
-    value::{SassFunction, SassMap, Value},
+    scope::SharedScope,
+    selector::Extender,
+    value::{SassFunction, SassMap, SassMixin, Value},
+    Token,
 };
 
 mod color;
@@ -21,17 +33,25 @@ mod meta;
 mod selector;
 mod string;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Module {
-    pub scope: Scope,
+    pub scope: SharedScope,
 
     /// Whether or not this module is builtin
     /// e.g. `"sass:math"`
     is_builtin: bool,
+
+    /// The id of this module in the `ModuleCache`, if it was loaded from a
+    /// stylesheet
+    id: Option<usize>,
 }
 
+/// A module shared by every stylesheet that loads it, so that changes one
+/// makes to its variables are seen by all of them
+pub(crate) type SharedModule = Rc<RefCell<Module>>;
+
 #[derive(Debug, Default)]
-pub(crate) struct Modules(BTreeMap<Identifier, Module>);
+pub(crate) struct Modules(BTreeMap<Identifier, SharedModule>);
 
 #[derive(Debug, Default)]
 pub(crate) struct ModuleConfig(BTreeMap<Identifier, Value>);
@@ -61,8 +81,337 @@ impl ModuleConfig {
     }
 }
 
+/// Every module that has been loaded during a single compilation, keyed by
+/// its canonical path
+///
+/// Each module is only executed once, no matter how many times it is used.
+#[derive(Debug, Default)]
+pub(crate) struct ModuleCache {
+    modules: HashMap<PathBuf, (usize, SharedModule)>,
+
+    /// The global scope and namespaces of every loaded module, indexed by the
+    /// id of that module
+    ///
+    /// The environment of the module currently being evaluated lives inside of
+    /// the `Parser`, so its entry here is empty.
+    environments: Vec<ModuleEnvironment>,
+
+    /// The environment of the entrypoint, while a member of some other module
+    /// is being evaluated
+    root: ModuleEnvironment,
+
+    /// The id of the module currently being evaluated, or `None` for the
+    /// entrypoint
+    current: Option<usize>,
+
+    /// The extender of every loaded module, indexed by the id of that module
+    ///
+    /// This is `None` while the module is still being loaded.
+    extenders: Vec<Option<Extender>>,
+
+    /// The ids of the modules loaded by each module, indexed by the id of
+    /// that module
+    upstream: Vec<Vec<usize>>,
+
+    /// The ids of the modules loaded by the entrypoint
+    root_upstream: Vec<usize>,
+
+    /// The id of the module whose CSS is currently being generated, or `None`
+    /// for the entrypoint
+    ///
+    /// This differs from `current` while a mixin declared in another module
+    /// is being included, as the CSS it emits belongs to the module that
+    /// included it.
+    css_module: Option<usize>,
+
+    /// The canonical path of every file loaded through `@use`, `@import`, or
+    /// `meta.load-css()`, in the order they were first loaded
+    loaded_urls: Vec<PathBuf>,
+
+    /// The canonical paths of the modules that are currently being loaded,
+    /// used to detect `@use` rules that load each other in a loop
+    loading: HashSet<PathBuf>,
+
+    /// The warnings emitted so far, or `None` if warnings are passed to the
+    /// logger as soon as they are emitted
+    warnings: Option<Vec<String>>,
+
+    /// Every file that has been read, which may outlive this compilation
+    files: FileCache,
+
+    /// The contents of the stylesheets loaded by importers, keyed by the
+    /// path their importer gave them
+    imported: HashMap<PathBuf, String>,
+
+    /// The number of mixins and functions currently being evaluated
+    call_depth: usize,
+
+    /// The number of times the body of a `@for`, `@each`, or `@while` rule
+    /// has been evaluated so far
+    loop_iterations: usize,
+
+    /// The parameters of the functions passed to `Options::function` that
+    /// have been called so far, indexed by the index of that function
+    ///
+    /// The parameters are parsed by `Options::function`, so the tokens of
+    /// their default values must be moved into this compilation's `CodeMap`.
+    custom_function_args: HashMap<usize, FuncArgs>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ModuleEnvironment {
+    pub scope: SharedScope,
+    pub modules: Modules,
+}
+
+impl ModuleCache {
+    pub fn get(&self, path: &Path) -> Option<(usize, SharedModule)> {
+        self.modules
+            .get(path)
+            .map(|(id, module)| (*id, Rc::clone(module)))
+    }
+
+    pub fn insert(&mut self, path: PathBuf, id: usize, module: SharedModule) {
+        self.modules.insert(path, (id, module));
+    }
+
+    /// The id of the module currently being evaluated, or `None` for the
+    /// entrypoint
+    pub const fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Reserves an id for a module that is about to be evaluated
+    pub fn new_module_id(&mut self) -> usize {
+        self.environments.push(ModuleEnvironment::default());
+        self.extenders.push(None);
+        self.upstream.push(Vec::new());
+        self.environments.len() - 1
+    }
+
+    pub fn set_current(&mut self, id: Option<usize>) {
+        self.current = id;
+    }
+
+    /// Stores the environment of the module `id` once it is no longer being
+    /// evaluated
+    pub fn store(&mut self, id: Option<usize>, environment: ModuleEnvironment) {
+        match id {
+            Some(id) => self.environments[id] = environment,
+            None => self.root = environment,
+        }
+    }
+
+    /// Removes the environment of the module `id` so that it may be evaluated
+    pub fn take(&mut self, id: Option<usize>) -> ModuleEnvironment {
+        match id {
+            Some(id) => mem::take(&mut self.environments[id]),
+            None => mem::take(&mut self.root),
+        }
+    }
+
+    /// The id of the module whose CSS is currently being generated, or `None`
+    /// for the entrypoint
+    pub const fn css_module(&self) -> Option<usize> {
+        self.css_module
+    }
+
+    pub fn set_css_module(&mut self, id: Option<usize>) {
+        self.css_module = id;
+    }
+
+    /// Records that the module at `path` is being loaded, returning `false` if
+    /// it already was
+    pub fn start_loading(&mut self, path: &Path) -> bool {
+        self.loading.insert(path.to_path_buf())
+    }
+
+    pub fn finish_loading(&mut self, path: &Path) {
+        self.loading.remove(path);
+    }
+
+    /// Records that the file at `path` was read during this compilation
+    pub fn add_loaded_url(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        if !self.loaded_urls.contains(&path) {
+            self.loaded_urls.push(path);
+        }
+    }
+
+    pub fn loaded_urls(&self) -> &[PathBuf] {
+        &self.loaded_urls
+    }
+
+    /// Collect warnings rather than printing them to stderr
+    pub fn collect_warnings(&mut self) {
+        self.warnings = Some(Vec::new());
+    }
+
+    /// Collects `warning` if warnings are being collected, and otherwise
+    /// passes it to `logger`
+    pub fn emit_warning(&mut self, warning: String, logger: &dyn Logger) {
+        match &mut self.warnings {
+            Some(warnings) => warnings.push(warning),
+            None => logger.warn(&warning),
+        }
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        self.warnings.take().unwrap_or_default()
+    }
+
+    /// Records that a mixin or function is being evaluated, returning the new
+    /// call depth
+    pub fn enter_call(&mut self) -> usize {
+        self.call_depth += 1;
+        self.call_depth
+    }
+
+    pub fn exit_call(&mut self) {
+        self.call_depth -= 1;
+    }
+
+    /// Records a single iteration of a loop, returning the total number of
+    /// iterations so far
+    pub fn add_loop_iteration(&mut self) -> usize {
+        self.loop_iterations += 1;
+        self.loop_iterations
+    }
+
+    /// The parameters of the custom function at `index`, whose signature was
+    /// parsed from `signature`
+    pub fn custom_function_args(
+        &mut self,
+        map: &mut CodeMap,
+        index: usize,
+        signature: &File,
+        args: &FuncArgs,
+    ) -> FuncArgs {
+        self.custom_function_args
+            .entry(index)
+            .or_insert_with(|| {
+                let file = map.add_file(signature.name().to_owned(), signature.source().to_owned());
+                FuncArgs(
+                    args.0
+                        .iter()
+                        .map(|arg| FuncArg {
+                            name: arg.name,
+                            default: arg
+                                .default
+                                .as_ref()
+                                .map(|toks| move_tokens(toks, signature, &file)),
+                            is_variadic: arg.is_variadic,
+                        })
+                        .collect(),
+                )
+            })
+            .clone()
+    }
+
+    /// A cache for a new compilation that reuses the files read by a
+    /// previous one
+    #[cfg(not(feature = "wasm"))]
+    pub fn with_files(files: FileCache) -> Self {
+        Self {
+            files,
+            ..Self::default()
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn into_files(self) -> FileCache {
+        self.files
+    }
+
+    /// Records the contents of a stylesheet returned by an importer, so that
+    /// `load_file` returns them for `path` rather than reading it from the
+    /// filesystem
+    pub fn add_imported(&mut self, path: PathBuf, contents: String) {
+        self.imported.insert(path, contents);
+    }
+
+    /// Reads and lexes the file at `path`, or the stylesheet loaded by an
+    /// importer if it was given that path
+    ///
+    /// See [`FileCache::load`] for more information.
+    pub fn load_file(
+        &mut self,
+        map: &mut CodeMap,
+        path: &Path,
+        name: String,
+    ) -> SassResult<(Arc<File>, Vec<Token>)> {
+        if let Some(contents) = self.imported.get(path) {
+            let file = map.add_file(name, contents.clone());
+            let toks = Lexer::new(&file).collect();
+            return Ok((file, toks));
+        }
+
+        self.files.load(map, path, name)
+    }
+
+    pub fn store_extender(&mut self, id: usize, extender: Extender) {
+        self.extenders[id] = Some(extender);
+    }
+
+    /// Records that the module `downstream` loads the module `upstream`
+    pub fn add_upstream(&mut self, downstream: Option<usize>, upstream: usize) {
+        let modules = match downstream {
+            Some(id) => &mut self.upstream[id],
+            None => &mut self.root_upstream,
+        };
+
+        if !modules.contains(&upstream) {
+            modules.push(upstream);
+        }
+    }
+
+    /// The extenders of every module that `id` depends on, directly or
+    /// transitively
+    ///
+    /// Extensions defined in a module apply to the CSS of these modules as
+    /// well as its own.
+    pub fn upstream_extenders_mut(&mut self, id: Option<usize>) -> Vec<&mut Extender> {
+        let upstream = self.upstream_ids(id);
+
+        self.extenders
+            .iter_mut()
+            .enumerate()
+            .filter(|(id, _)| upstream.contains(id))
+            .filter_map(|(_, extender)| extender.as_mut())
+            .collect()
+    }
+
+    pub fn upstream_extenders(&self, id: Option<usize>) -> Vec<&Extender> {
+        let upstream = self.upstream_ids(id);
+
+        self.extenders
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| upstream.contains(id))
+            .filter_map(|(_, extender)| extender.as_ref())
+            .collect()
+    }
+
+    fn upstream_ids(&self, id: Option<usize>) -> HashSet<usize> {
+        let mut upstream = HashSet::new();
+        let mut to_visit = match id {
+            Some(id) => self.upstream[id].clone(),
+            None => self.root_upstream.clone(),
+        };
+
+        while let Some(id) = to_visit.pop() {
+            if upstream.insert(id) {
+                to_visit.extend(self.upstream[id].iter().copied());
+            }
+        }
+
+        upstream
+    }
+}
+
 impl Modules {
-    pub fn insert(&mut self, name: Identifier, module: Module, span: Span) -> SassResult<()> {
+    pub fn insert(&mut self, name: Identifier, module: SharedModule, span: Span) -> SassResult<()> {
         if self.0.contains_key(&name) {
             return Err((
                 format!("There's already a module with namespace \"{}\".", name),
@@ -76,9 +425,9 @@ impl Modules {
         Ok(())
     }
 
-    pub fn get(&self, name: Identifier, span: Span) -> SassResult<&Module> {
+    pub fn get(&self, name: Identifier, span: Span) -> SassResult<Ref<'_, Module>> {
         match self.0.get(&name) {
-            Some(v) => Ok(v),
+            Some(v) => Ok(v.borrow()),
             None => Err((
                 format!(
                     "There is no module with the namespace \"{}\".",
@@ -90,9 +439,9 @@ impl Modules {
         }
     }
 
-    pub fn get_mut(&mut self, name: Identifier, span: Span) -> SassResult<&mut Module> {
-        match self.0.get_mut(&name) {
-            Some(v) => Ok(v),
+    pub fn get_mut(&mut self, name: Identifier, span: Span) -> SassResult<RefMut<'_, Module>> {
+        match self.0.get(&name) {
+            Some(v) => Ok(v.borrow_mut()),
             None => Err((
                 format!(
                     "There is no module with the namespace \"{}\".",
@@ -108,12 +457,13 @@ impl Modules {
 impl Module {
     pub fn new_builtin() -> Self {
         Module {
-            scope: Scope::default(),
+            scope: SharedScope::default(),
             is_builtin: true,
+            id: None,
         }
     }
 
-    pub fn get_var(&self, name: Spanned<Identifier>) -> SassResult<&Value> {
+    pub fn get_var(&self, name: Spanned<Identifier>) -> SassResult<Value> {
         if name.node.as_str().starts_with('-') {
             return Err((
                 "Private members can't be accessed from outside their modules.",
@@ -122,8 +472,8 @@ impl Module {
                 .into());
         }
 
-        match self.scope.vars.get(&name.node) {
-            Some(v) => Ok(v),
+        match self.scope.borrow().vars.get(&name.node) {
+            Some(v) => Ok(v.clone()),
             None => Err(("Undefined variable.", name.span).into()),
         }
     }
@@ -141,7 +491,12 @@ impl Module {
                 .into());
         }
 
-        if self.scope.insert_var(name.node, value).is_some() {
+        if self
+            .scope
+            .borrow_mut()
+            .insert_var(name.node, value)
+            .is_some()
+        {
             Ok(())
         } else {
             Err(("Undefined variable.", name.span).into())
@@ -157,18 +512,26 @@ impl Module {
                 .into());
         }
 
-        match self.scope.mixins.get(&name.node) {
+        match self.scope.borrow().mixins.get(&name.node) {
             Some(v) => Ok(v.clone()),
             None => Err(("Undefined mixin.", name.span).into()),
         }
     }
 
-    pub fn insert_builtin_mixin(&mut self, name: &'static str, mixin: BuiltinMixin) {
-        self.scope.mixins.insert(name.into(), Mixin::Builtin(mixin));
+    pub fn insert_builtin_mixin(
+        &mut self,
+        name: &'static str,
+        mixin: BuiltinMixin,
+        accepts_content_block: bool,
+    ) {
+        self.scope
+            .borrow_mut()
+            .mixins
+            .insert(name.into(), Mixin::Builtin(mixin, accepts_content_block));
     }
 
     pub fn insert_builtin_var(&mut self, name: &'static str, value: Value) {
-        self.scope.vars.insert(name.into(), value);
+        self.scope.borrow_mut().vars.insert(name.into(), value);
     }
 
     pub fn get_fn(&self, name: Spanned<Identifier>) -> SassResult<Option<SassFunction>> {
@@ -180,19 +543,19 @@ impl Module {
                 .into());
         }
 
-        Ok(self.scope.functions.get(&name.node).cloned())
+        Ok(self.scope.borrow().functions.get(&name.node).cloned())
     }
 
     pub fn var_exists(&self, name: Identifier) -> bool {
-        !name.as_str().starts_with('-') && self.scope.var_exists(name)
+        !name.as_str().starts_with('-') && self.scope.borrow().var_exists(name)
     }
 
     pub fn mixin_exists(&self, name: Identifier) -> bool {
-        !name.as_str().starts_with('-') && self.scope.mixin_exists(name)
+        !name.as_str().starts_with('-') && self.scope.borrow().mixin_exists(name)
     }
 
     pub fn fn_exists(&self, name: Identifier) -> bool {
-        !name.as_str().starts_with('-') && self.scope.fn_exists(name)
+        !name.as_str().starts_with('-') && self.scope.borrow().fn_exists(name)
     }
 
     pub fn insert_builtin(
@@ -202,6 +565,7 @@ impl Module {
     ) {
         let ident = name.into();
         self.scope
+            .borrow_mut()
             .functions
             .insert(ident, SassFunction::Builtin(Builtin::new(function), ident));
     }
@@ -209,6 +573,7 @@ impl Module {
     pub fn functions(&self) -> SassMap {
         SassMap::new_with(
             self.scope
+                .borrow()
                 .functions
                 .iter()
                 .filter(|(key, _)| !key.as_str().starts_with('-'))
@@ -222,9 +587,27 @@ impl Module {
         )
     }
 
+    pub fn mixins(&self) -> SassMap {
+        SassMap::new_with(
+            self.scope
+                .borrow()
+                .mixins
+                .iter()
+                .filter(|(key, _)| !key.as_str().starts_with('-'))
+                .map(|(key, value)| {
+                    (
+                        Value::String(key.to_string(), QuoteKind::Quoted),
+                        Value::MixinRef(SassMixin::new(value.clone(), *key)),
+                    )
+                })
+                .collect::<Vec<(Value, Value)>>(),
+        )
+    }
+
     pub fn variables(&self) -> SassMap {
         SassMap::new_with(
             self.scope
+                .borrow()
                 .vars
                 .iter()
                 .filter(|(key, _)| !key.as_str().starts_with('-'))
@@ -238,8 +621,17 @@ impl Module {
         )
     }
 
-    pub const fn new_from_scope(scope: Scope, is_builtin: bool) -> Self {
-        Module { scope, is_builtin }
+    /// The module loaded from a stylesheet whose global scope is `scope`
+    pub const fn new_from_scope(scope: SharedScope, id: usize) -> Self {
+        Module {
+            scope,
+            is_builtin: false,
+            id: Some(id),
+        }
+    }
+
+    pub const fn id(&self) -> Option<usize> {
+        self.id
     }
 }
 
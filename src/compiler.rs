@@ -0,0 +1,79 @@
+use std::{mem, path::Path};
+
+use codemap::CodeMap;
+
+use crate::{
+    builtin::modules::ModuleCache, compile_path_with, compile_string_with, file_cache::FileCache,
+    CompileResult, Options, Result,
+};
+
+/// A long-lived compiler that remembers the files it has read, so that
+/// compiling the same stylesheets again only reads and lexes the files that
+/// have changed
+///
+/// A file is considered changed if its modification time differs from when it
+/// was last read. Files can also be explicitly re-read using
+/// [`Compiler::invalidate`](Compiler::invalidate), which is useful when a file
+/// may be modified more than once within the resolution of its filesystem's
+/// timestamps.
+///
+/// ```
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let mut compiler = grass::Compiler::new(grass::Options::default());
+///     let result = compiler.compile_string("a { b: c; }".to_string())?;
+///     assert_eq!(result.css(), "a {\n  b: c;\n}\n");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Compiler {
+    options: Options,
+    files: FileCache,
+}
+
+impl Compiler {
+    #[must_use]
+    #[inline]
+    pub fn new(options: Options) -> Self {
+        Self {
+            options,
+            files: FileCache::default(),
+        }
+    }
+
+    /// Compile CSS from a path, reusing any unchanged files read by previous
+    /// compilations
+    ///
+    /// See [`compile_path`](crate::compile_path) for more information.
+    #[inline]
+    pub fn compile_path(&mut self, p: &str) -> Result<CompileResult> {
+        let mut module_cache = ModuleCache::with_files(mem::take(&mut self.files));
+        let result = compile_path_with(&mut CodeMap::new(), p, &self.options, &mut module_cache);
+        self.files = module_cache.into_files();
+        result
+    }
+
+    /// Compile CSS from a string, reusing any unchanged files read by
+    /// previous compilations
+    ///
+    /// See [`compile_string`](crate::compile_string) for more information.
+    #[inline]
+    pub fn compile_string(&mut self, p: String) -> Result<CompileResult> {
+        let mut module_cache = ModuleCache::with_files(mem::take(&mut self.files));
+        let result = compile_string_with(&mut CodeMap::new(), p, &self.options, &mut module_cache);
+        self.files = module_cache.into_files();
+        result
+    }
+
+    /// Forces the file at `path` to be read again the next time it is loaded
+    #[inline]
+    pub fn invalidate(&mut self, path: &Path) {
+        self.files.invalidate(path);
+    }
+
+    /// Forces every file to be read again the next time it is loaded
+    #[inline]
+    pub fn invalidate_all(&mut self) {
+        self.files.clear();
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::Debug;
+
+use crate::importer::MaybeSendSync;
+
+/// A function implemented outside of Sass that may be called by stylesheets
+///
+/// Custom functions are passed to [`Options::function`](crate::Options::function)
+/// along with their signature. They may be called from any stylesheet, but
+/// functions declared by a stylesheet with the same name take precedence.
+///
+/// Each argument is passed as Sass source, in the same form that
+/// `meta.inspect()` would return it, and the returned string is parsed as a
+/// Sass expression. Quoted strings therefore include their quotes.
+///
+/// ```
+/// #[derive(Debug)]
+/// struct Double;
+///
+/// impl grass::CustomFunction for Double {
+///     fn call(&self, args: &[String]) -> Result<String, String> {
+///         Ok(format!("{} * 2", args[0]))
+///     }
+/// }
+///
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let options = grass::Options::default().function("double($number)", Box::new(Double))?;
+///     let css = grass::from_string("a { b: double(2px); }".to_string(), &options)?;
+///     assert_eq!(css, "a {\n  b: 4px;\n}\n");
+///     Ok(())
+/// }
+/// ```
+pub trait CustomFunction: Debug + MaybeSendSync {
+    /// Calls the function with the values of its parameters, in the order
+    /// they're declared in its signature
+    ///
+    /// A rest parameter is passed as a single list. An error is reported at
+    /// the call site.
+    fn call(&self, args: &[String]) -> Result<String, String>;
+}
@@ -2,11 +2,11 @@ use std::{
     error::Error,
     fmt::{self, Display},
     io,
-    rc::Rc,
     string::FromUtf8Error,
+    sync::Arc,
 };
 
-use codemap::{Span, SpanLoc};
+use codemap::{CodeMap, Span, SpanLoc};
 
 pub type SassResult<T> = Result<T, Box<SassError>>;
 
@@ -37,13 +37,114 @@ pub struct SassError {
 }
 
 impl SassError {
-    pub(crate) fn raw(self) -> (String, Span) {
+    /// The message describing this error, without its location
+    #[inline]
+    #[must_use]
+    pub fn message(&self) -> String {
+        match &self.kind {
+            SassErrorKind::Raw(message, ..)
+            | SassErrorKind::FromOrigin { message, .. }
+            | SassErrorKind::ParseError { message, .. }
+            | SassErrorKind::FromUtf8Error(message) => message.clone(),
+            SassErrorKind::IoError(error) => error.to_string(),
+        }
+    }
+
+    /// The name of the file in which this error occurred, if it has a
+    /// location
+    #[inline]
+    #[must_use]
+    pub fn file_name(&self) -> Option<&str> {
+        match &self.kind {
+            SassErrorKind::ParseError { loc, .. } => Some(loc.file.name()),
+            _ => None,
+        }
+    }
+
+    /// The line on which this error occurred, starting at 1, if it has a
+    /// location
+    #[inline]
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        match &self.kind {
+            SassErrorKind::ParseError { loc, .. } => Some(loc.begin.line + 1),
+            _ => None,
+        }
+    }
+
+    /// The column at which this error occurred, starting at 1, if it has a
+    /// location
+    #[inline]
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        match &self.kind {
+            SassErrorKind::ParseError { loc, .. } => Some(loc.begin.column + 1),
+            _ => None,
+        }
+    }
+
+    /// Whether the location of this error is a span that has not yet been
+    /// looked up, as opposed to an error that has no location at all
+    pub(crate) fn is_raw(&self) -> bool {
+        matches!(
+            self.kind,
+            SassErrorKind::Raw(..) | SassErrorKind::FromOrigin { .. }
+        )
+    }
+
+    pub(crate) fn raw(self, map: &CodeMap) -> (String, Span) {
         match self.kind {
             SassErrorKind::Raw(string, span) => (string, span),
+            SassErrorKind::FromOrigin {
+                message,
+                span,
+                origin,
+            } => {
+                let loc = map.look_up_span(origin);
+                (
+                    format!(
+                        "From line {}, column {} of {}:\n{}",
+                        loc.begin.line + 1,
+                        loc.begin.column + 1,
+                        loc.file.name(),
+                        message
+                    ),
+                    span,
+                )
+            }
             e => todo!("unable to get raw of {:?}", e),
         }
     }
 
+    /// Marks this error as having been caused by the code at `origin`
+    ///
+    /// Errors that do not have a span are returned unchanged.
+    pub(crate) fn with_origin(mut self: Box<Self>, origin: Span) -> Box<Self> {
+        if let SassErrorKind::Raw(message, span) = self.kind {
+            self.kind = SassErrorKind::FromOrigin {
+                message,
+                span,
+                origin,
+            };
+        }
+        self
+    }
+
+    /// Reports this error at `span` instead, noting where it originally
+    /// occurred
+    ///
+    /// Errors that do not have a span are returned unchanged.
+    pub(crate) fn reported_at(mut self: Box<Self>, span: Span) -> Box<Self> {
+        if let SassErrorKind::Raw(message, origin) = self.kind {
+            self.kind = SassErrorKind::FromOrigin {
+                message,
+                span,
+                origin,
+            };
+        }
+        self
+    }
+
     pub(crate) const fn from_loc(message: String, loc: SpanLoc, unicode: bool) -> Self {
         SassError {
             kind: SassErrorKind::ParseError {
@@ -61,14 +162,20 @@ enum SassErrorKind {
     /// It contains only a `String` message and
     /// a span
     Raw(String, Span),
+    /// A raw error reported at `span` that was caused by the code at `origin`
+    FromOrigin {
+        message: String,
+        span: Span,
+        origin: Span,
+    },
     ParseError {
         message: String,
         loc: SpanLoc,
         unicode: bool,
     },
-    // we put IoErrors in an `Rc` to allow it to be
-    // cloneable
-    IoError(Rc<io::Error>),
+    // we put IoErrors in an `Arc` to allow it to be
+    // cloneable and sent between threads
+    IoError(Arc<io::Error>),
     FromUtf8Error(String),
 }
 
@@ -86,7 +193,11 @@ impl Display for SassError {
             } => (message, loc, *unicode),
             SassErrorKind::FromUtf8Error(s) => return writeln!(f, "Error: {}", s),
             SassErrorKind::IoError(s) => return writeln!(f, "Error: {}", s),
-            SassErrorKind::Raw(..) => todo!(),
+            // the span of a raw error can't be looked up without the
+            // `CodeMap` it points into, so only its message is shown
+            SassErrorKind::Raw(message, ..) | SassErrorKind::FromOrigin { message, .. } => {
+                return writeln!(f, "Error: {}", message)
+            }
         };
 
         let first_bar = if unicode { '╷' } else { '|' };
@@ -128,7 +239,7 @@ impl From<io::Error> for Box<SassError> {
     #[inline]
     fn from(error: io::Error) -> Box<SassError> {
         Box::new(SassError {
-            kind: SassErrorKind::IoError(Rc::new(error)),
+            kind: SassErrorKind::IoError(Arc::new(error)),
         })
     }
 }
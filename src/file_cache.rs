@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use codemap::{CodeMap, File};
+
+use crate::{error::SassResult, lexer::Lexer, Token};
+
+/// Source files that have already been read and lexed, keyed by their
+/// canonical path
+///
+/// A cached file is added to the `CodeMap` it is loaded into again, and its
+/// tokens are moved to point into the new copy, so a cache may be used with a
+/// fresh `CodeMap` for each compilation.
+#[derive(Debug, Default)]
+pub(crate) struct FileCache {
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    /// The modification time of the file when it was read
+    modified: SystemTime,
+    file: Arc<File>,
+    toks: Vec<Token>,
+}
+
+impl FileCache {
+    /// Reads and lexes the file at `path`, reusing the cached tokens if the
+    /// file has not been modified since it was last read
+    ///
+    /// `name` is the name used to refer to the file in error messages.
+    pub fn load(
+        &mut self,
+        map: &mut CodeMap,
+        path: &Path,
+        name: String,
+    ) -> SassResult<(Arc<File>, Vec<Token>)> {
+        let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        if let Some(cached) = self.files.get(&canonical_path) {
+            if Some(cached.modified) == modified {
+                let file = map.add_file(name, cached.file.source().to_owned());
+                let toks = move_tokens(&cached.toks, &cached.file, &file);
+                return Ok((file, toks));
+            }
+        }
+
+        let file = map.add_file(name, String::from_utf8(fs::read(path)?)?);
+        let toks: Vec<Token> = Lexer::new(&file).collect();
+
+        match modified {
+            Some(modified) => {
+                self.files.insert(
+                    canonical_path,
+                    CachedFile {
+                        modified,
+                        file: Arc::clone(&file),
+                        toks: toks.clone(),
+                    },
+                );
+            }
+            // without a modification time there is no way to tell whether the
+            // cached file is stale
+            None => {
+                self.files.remove(&canonical_path);
+            }
+        }
+
+        Ok((file, toks))
+    }
+
+    /// Forgets the file at `path`, so that it is read again the next time it
+    /// is loaded
+    #[cfg(not(feature = "wasm"))]
+    pub fn invalidate(&mut self, path: &Path) {
+        let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.files.remove(&canonical_path);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+/// Moves tokens lexed from `from` to point into `to`, a copy of the same
+/// source that was added to a different `CodeMap`
+pub(crate) fn move_tokens(toks: &[Token], from: &File, to: &File) -> Vec<Token> {
+    let offset = from.span.low();
+    toks.iter()
+        .map(|tok| {
+            Token::new(
+                to.span
+                    .subspan(tok.pos.low() - offset, tok.pos.high() - offset),
+                tok.kind,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,85 @@
+use std::{
+    fmt::Debug,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// `Send + Sync`, except on wasm, where there is only one thread and the
+/// JavaScript values that implement importers and custom functions can't be
+/// shared between threads
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Loads stylesheets that aren't found on the filesystem
+///
+/// Importers are passed to [`Options::importer`](crate::Options::importer).
+/// When resolving a URL loaded by `@use`, `@import`, or `meta.load-css()`,
+/// grass first looks for a file relative to the file containing the rule,
+/// then asks each importer in the order they were added, and finally searches
+/// the load paths.
+///
+/// ```
+/// use std::{io, path::Path};
+///
+/// #[derive(Debug)]
+/// struct Theme;
+///
+/// impl grass::Importer for Theme {
+///     fn import(&self, url: &str, _from: &Path) -> io::Result<Option<grass::ImporterResult>> {
+///         Ok(match url {
+///             "theme" => Some(grass::ImporterResult::new("theme.scss", "$primary: red;")),
+///             _ => None,
+///         })
+///     }
+/// }
+///
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let options = grass::Options::default().importer(Box::new(Theme));
+///     let css = grass::from_string(
+///         "@use \"theme\";\na { color: theme.$primary; }".to_string(),
+///         &options,
+///     )?;
+///     assert_eq!(css, "a {\n  color: red;\n}\n");
+///     Ok(())
+/// }
+/// ```
+pub trait Importer: Debug + MaybeSendSync {
+    /// Loads the stylesheet referred to by `url`, which was loaded from the
+    /// file at `from`
+    ///
+    /// `url` is exactly as it was written in the stylesheet. Returns
+    /// `Ok(None)` if this importer doesn't recognize `url`. An error is
+    /// reported at the rule that loaded the stylesheet.
+    fn import(&self, url: &str, from: &Path) -> io::Result<Option<ImporterResult>>;
+}
+
+/// A stylesheet loaded by an [`Importer`](Importer)
+#[derive(Debug, Clone)]
+pub struct ImporterResult {
+    pub(crate) path: PathBuf,
+    pub(crate) contents: String,
+}
+
+impl ImporterResult {
+    /// `path` identifies the stylesheet: it is used in error messages, to
+    /// make sure modules are only loaded once, and is passed to importers as
+    /// the file that stylesheets loaded by this one are loaded from. Its
+    /// extension determines the syntax of `contents`, which is parsed as
+    /// SCSS unless the extension is `.css`.
+    #[inline]
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(path: P, contents: S) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+}
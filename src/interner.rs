@@ -1,21 +1,23 @@
-use lasso::{Rodeo, Spur};
+use lasso::{Spur, ThreadedRodeo};
+use once_cell::sync::Lazy;
 
-use std::cell::RefCell;
 use std::fmt::{self, Display};
 
-thread_local!(static STRINGS: RefCell<Rodeo<Spur>> = RefCell::new(Rodeo::default()));
+/// Interned strings are shared by every thread, so that values containing them
+/// may be sent between threads
+static STRINGS: Lazy<ThreadedRodeo<Spur>> = Lazy::new(ThreadedRodeo::default);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub(crate) struct InternedString(Spur);
 
 impl InternedString {
     pub fn get_or_intern<T: AsRef<str>>(s: T) -> Self {
-        Self(STRINGS.with(|interner| interner.borrow_mut().get_or_intern(s)))
+        Self(STRINGS.get_or_intern(s))
     }
 
     #[allow(dead_code)]
     pub fn resolve(self) -> String {
-        STRINGS.with(|interner| interner.borrow().resolve(&self.0).to_string())
+        STRINGS.resolve(&self.0).to_string()
     }
 
     #[allow(dead_code)]
@@ -23,15 +25,13 @@ impl InternedString {
         self.resolve_ref() == ""
     }
 
-    pub fn resolve_ref<'a>(self) -> &'a str {
-        unsafe {
-            STRINGS.with(|interner| &(*(interner.as_ptr()).as_ref().unwrap().resolve(&self.0)))
-        }
+    pub fn resolve_ref(self) -> &'static str {
+        STRINGS.resolve(&self.0)
     }
 }
 
 impl Display for InternedString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        STRINGS.with(|interner| write!(f, "{}", interner.borrow().resolve(&self.0)))
+        write!(f, "{}", STRINGS.resolve(&self.0))
     }
 }
@@ -90,39 +90,66 @@ grass input.scss
 )]
 #![cfg_attr(feature = "nightly", feature(track_caller))]
 #![cfg_attr(feature = "profiling", inline(never))]
-use std::{fs, path::Path};
-
-#[cfg(feature = "wasm")]
-use wasm_bindgen::prelude::*;
+#[cfg(not(feature = "wasm"))]
+use std::{
+    fs,
+    io::{Read, Write},
+    num::NonZeroUsize,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 pub(crate) use beef::lean::Cow;
 
-use codemap::CodeMap;
+use codemap::{CodeMap, File};
 
 use peekmore::PeekMore;
 
+#[cfg(not(feature = "wasm"))]
+pub use crate::compiler::Compiler;
+pub use crate::custom_function::CustomFunction;
 pub use crate::error::{SassError as Error, SassResult as Result};
+pub use crate::importer::{Importer, ImporterResult};
+pub use crate::logger::Logger;
 pub(crate) use crate::token::Token;
 use crate::{
-    builtin::modules::{ModuleConfig, Modules},
+    args::FuncArgs,
+    builtin::modules::{ModuleCache, ModuleConfig, Modules},
+    common::{Identifier, QuoteKind},
     lexer::Lexer,
-    output::Css,
+    logger::StdErrLogger,
+    output::{Css, LimitedWriter},
     parse::{
         common::{ContextFlags, NeverEmptyVec},
-        Parser,
+        is_css_file, Parser,
     },
-    scope::{Scope, Scopes},
+    scope::{Scopes, SharedScope},
     selector::{Extender, Selector},
+    value::{PrecisionGuard, Value, DEFAULT_PRECISION},
 };
 
+pub mod ast;
+
 mod args;
 mod atrule;
 mod builtin;
 mod color;
 mod common;
+#[cfg(not(feature = "wasm"))]
+mod compiler;
+mod custom_function;
 mod error;
+mod file_cache;
+mod importer;
 mod interner;
 mod lexer;
+mod logger;
 mod output;
 mod parse;
 mod scope;
@@ -132,6 +159,8 @@ mod token;
 mod unit;
 mod utils;
 mod value;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 #[non_exhaustive]
 #[derive(Debug)]
@@ -150,16 +179,53 @@ pub enum OutputStyle {
 /// The simplest usage is `grass::Options::default()`;
 /// however, a builder pattern is also exposed to offer
 /// more control.
+///
+/// `Options` owns all of its configuration, so it may be
+/// stored alongside the rest of an application's settings
+/// and shared between threads.
 #[derive(Debug)]
-pub struct Options<'a> {
+pub struct Options {
     style: OutputStyle,
-    load_paths: Vec<&'a Path>,
+    load_paths: Vec<PathBuf>,
     allows_charset: bool,
     unicode_error_messages: bool,
     quiet: bool,
+    precision: usize,
+    node_modules: bool,
+    global_variables: Vec<(String, GlobalVariable)>,
+    prepended_source: Option<(String, String)>,
+    max_call_depth: Option<usize>,
+    max_loop_iterations: Option<usize>,
+    max_output_size: Option<usize>,
+    logger: Box<dyn Logger>,
+    importers: Vec<Box<dyn Importer>>,
+    functions: Vec<CustomFunctionDeclaration>,
 }
 
-impl Default for Options<'_> {
+/// The value of a variable passed to `Options::global_variable_source` or
+/// `Options::global_variable_string`
+#[derive(Debug)]
+enum GlobalVariable {
+    /// Sass source, parsed as a single expression
+    Source(String),
+    /// A quoted string, used as-is
+    QuotedString(String),
+}
+
+/// A function passed to `Options::function`, along with its signature
+#[derive(Debug)]
+struct CustomFunctionDeclaration {
+    name: Identifier,
+    /// The source of the parameters of the function, excluding the opening
+    /// parenthesis, in a file named after the whole signature
+    signature: Arc<File>,
+    /// The parameters parsed from `signature`, whose tokens point into a
+    /// `CodeMap` of their own
+    args: FuncArgs,
+    function: Box<dyn CustomFunction>,
+}
+
+impl Default for Options {
     #[inline]
     fn default() -> Self {
         Self {
@@ -168,12 +234,22 @@ impl Default for Options<'_> {
             allows_charset: true,
             unicode_error_messages: true,
             quiet: false,
+            precision: DEFAULT_PRECISION,
+            node_modules: false,
+            global_variables: Vec::new(),
+            prepended_source: None,
+            max_call_depth: None,
+            max_loop_iterations: None,
+            max_output_size: None,
+            logger: Box::new(StdErrLogger),
+            importers: Vec::new(),
+            functions: Vec::new(),
         }
     }
 }
 
 #[allow(clippy::missing_const_for_fn)]
-impl<'a> Options<'a> {
+impl Options {
     /// `grass` currently offers 2 different output styles
     ///
     ///  - `OutputStyle::Expanded` writes each selector and declaration on its own line.
@@ -217,8 +293,8 @@ impl<'a> Options<'a> {
     /// This method will append a single path to the list.
     #[must_use]
     #[inline]
-    pub fn load_path(mut self, path: &'a Path) -> Self {
-        self.load_paths.push(path);
+    pub fn load_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.load_paths.push(path.as_ref().to_path_buf());
         self
     }
 
@@ -229,8 +305,9 @@ impl<'a> Options<'a> {
     /// See [`Options::load_path`](Options::load_path) for more information about load paths
     #[must_use]
     #[inline]
-    pub fn load_paths(mut self, paths: &'a [&'a Path]) -> Self {
-        self.load_paths.extend_from_slice(paths);
+    pub fn load_paths<P: AsRef<Path>>(mut self, paths: &[P]) -> Self {
+        self.load_paths
+            .extend(paths.iter().map(|path| path.as_ref().to_path_buf()));
         self
     }
 
@@ -261,43 +338,369 @@ impl<'a> Options<'a> {
         self.unicode_error_messages = unicode_error_messages;
         self
     }
+
+    /// The number of digits after the decimal point that Sass
+    /// will emit when serializing numbers, whether in the CSS
+    /// output, in error messages, or through `inspect()`.
+    ///
+    /// By default, numbers are emitted with a precision of 10.
+    #[must_use]
+    #[inline]
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// This flag tells Sass to resolve bare imports such as
+    /// `@use "bootstrap"` from `node_modules` directories, walking
+    /// up from the directory of the importing file.
+    ///
+    /// When only the name of a package is imported, the file named
+    /// by the `sass` or `style` field of its `package.json` is loaded.
+    /// Otherwise, the rest of the URL is resolved inside of the package.
+    ///
+    /// `node_modules` is only searched after the current directory
+    /// and all load paths. By default, this value is `false`.
+    #[must_use]
+    #[inline]
+    pub fn resolve_node_modules(mut self, node_modules: bool) -> Self {
+        self.node_modules = node_modules;
+        self
+    }
+
+    /// Predefine a global variable in the entry file, as if it were
+    /// declared before the first line of the stylesheet.
+    ///
+    /// The value is Sass source rather than a typed value: it is converted
+    /// to a string and parsed as a single Sass expression, so `1.5`, `true`,
+    /// and `"10px"` define a number, a boolean, and a number with units
+    /// respectively, while `"Helvetica Neue"` defines a list of two unquoted
+    /// strings. Use [`Options::global_variable_string`] to define a quoted
+    /// string instead. It is an error for the source to contain anything
+    /// after the expression, such as a `;`.
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let options = grass::Options::default()
+    ///         .global_variable_source("theme", "dark")
+    ///         .global_variable_source("gutter", 10);
+    ///     let css = grass::from_string("a { b: $theme; c: $gutter * 2px; }".to_string(), &options)?;
+    ///     assert_eq!(css, "a {\n  b: dark;\n  c: 20px;\n}\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Variables are only defined in the entry file and the files it
+    /// imports with `@import`; modules loaded with `@use` do not see them.
+    /// Declaring the same variable twice replaces its earlier value.
+    #[must_use]
+    #[inline]
+    pub fn global_variable_source(self, name: &str, value: impl ToString) -> Self {
+        self.global_variable(name, GlobalVariable::Source(value.to_string()))
+    }
+
+    /// Predefine a global variable in the entry file whose value is the
+    /// quoted string `value`, exactly as given.
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let options = grass::Options::default().global_variable_string("font", "Helvetica Neue");
+    ///     let css = grass::from_string("a { font-family: $font; }".to_string(), &options)?;
+    ///     assert_eq!(css, "a {\n  font-family: \"Helvetica Neue\";\n}\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// See [`Options::global_variable_source`] for more information.
+    #[must_use]
+    #[inline]
+    pub fn global_variable_string(self, name: &str, value: impl Into<String>) -> Self {
+        self.global_variable(name, GlobalVariable::QuotedString(value.into()))
+    }
+
+    fn global_variable(mut self, name: &str, value: GlobalVariable) -> Self {
+        let name = name.trim_start_matches('$').to_owned();
+
+        match self.global_variables.iter_mut().find(|(n, _)| *n == name) {
+            Some(var) => var.1 = value,
+            None => self.global_variables.push((name, value)),
+        }
+
+        self
+    }
+
+    /// Prepend Sass source to the entry file, similar to the
+    /// `additionalData` option of webpack's `sass-loader`.
+    ///
+    /// The source is evaluated in the context of the entry file, so it
+    /// may contain `@use` rules and may define variables, mixins, and
+    /// functions used by the rest of the stylesheet. Relative URLs are
+    /// resolved from the directory of the entry file. Errors inside of the
+    /// prepended source are reported using `name` as the file name.
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let options = grass::Options::default()
+    ///         .prepend("$primary: red;", "config");
+    ///     let css = grass::from_string("a { color: $primary; }".to_string(), &options)?;
+    ///     assert_eq!(css, "a {\n  color: red;\n}\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Calling this method again replaces the previously prepended source.
+    /// Plain CSS entry files are not affected.
+    #[must_use]
+    #[inline]
+    pub fn prepend(mut self, source: &str, name: &str) -> Self {
+        self.prepended_source = Some((name.to_owned(), source.to_owned()));
+        self
+    }
+
+    /// The maximum number of mixins and functions that may be evaluated
+    /// inside of one another, such as by a mixin that includes itself.
+    ///
+    /// Exceeding this limit emits an error rather than overflowing the
+    /// stack. Each level of nesting uses a fair amount of stack space, so
+    /// the appropriate limit depends on the stack size of the thread
+    /// compiling the stylesheet: a few hundred is safe for the 8 MiB main
+    /// thread of a release build.
+    ///
+    /// By default, there is no limit.
+    #[must_use]
+    #[inline]
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = Some(max_call_depth);
+        self
+    }
+
+    /// The maximum total number of times the bodies of `@for`, `@each`, and
+    /// `@while` rules may be evaluated over the course of a compilation.
+    ///
+    /// Exceeding this limit emits an error, which guards against `@while`
+    /// rules that never terminate.
+    ///
+    /// By default, there is no limit.
+    #[must_use]
+    #[inline]
+    pub fn max_loop_iterations(mut self, max_loop_iterations: usize) -> Self {
+        self.max_loop_iterations = Some(max_loop_iterations);
+        self
+    }
+
+    /// The maximum size of the emitted CSS in bytes, not counting a
+    /// `@charset` declaration.
+    ///
+    /// Exceeding this limit emits an error rather than returning the CSS.
+    ///
+    /// By default, there is no limit.
+    #[must_use]
+    #[inline]
+    pub fn max_output_size(mut self, max_output_size: usize) -> Self {
+        self.max_output_size = Some(max_output_size);
+        self
+    }
+
+    /// Handle warnings and the output of `@debug` rules using `logger`
+    /// rather than printing them to stderr.
+    ///
+    /// Warnings returned by [`compile_path`](crate::compile_path) and
+    /// [`compile_string`](crate::compile_string) as part of the
+    /// [`CompileResult`](CompileResult) are not passed to the logger.
+    /// Nothing is logged if [`Options::quiet`](Options::quiet) is set.
+    #[must_use]
+    #[inline]
+    pub fn logger(mut self, logger: Box<dyn Logger>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Load stylesheets that aren't found on the filesystem using
+    /// `importer`.
+    ///
+    /// Importers are asked to load a URL after looking for it relative to
+    /// the file that loads it, but before searching the load paths. This
+    /// method may be called more than once, in which case the importers are
+    /// asked in the order they were added.
+    ///
+    /// See [`Importer`](Importer) for more information.
+    #[must_use]
+    #[inline]
+    pub fn importer(mut self, importer: Box<dyn Importer>) -> Self {
+        self.importers.push(importer);
+        self
+    }
+
+    /// Define a function that is implemented in Rust.
+    ///
+    /// `signature` is the name of the function followed by its parameters
+    /// as they would be written in a `@function` rule, such as
+    /// `"theme-color($name, $shade: 500)"`. Parameters may have default
+    /// values and the last may be a rest parameter.
+    ///
+    /// Defining a function with the same name as an earlier one replaces
+    /// it. See [`CustomFunction`](CustomFunction) for more information.
+    ///
+    /// An error is returned if the parameters can't be parsed.
+    #[inline]
+    pub fn function(mut self, signature: &str, function: Box<dyn CustomFunction>) -> Result<Self> {
+        let (name, parameters) = match signature.find('(') {
+            Some(idx) => (&signature[..idx], &signature[idx + 1..]),
+            None => (signature, ")"),
+        };
+
+        let mut map = CodeMap::new();
+        let file = map.add_file(signature.to_owned(), parameters.to_owned());
+        let args = parse_custom_function_args(&mut map, &file, self.unicode_error_messages)?;
+
+        let declaration = CustomFunctionDeclaration {
+            name: Identifier::from(name.trim()),
+            signature: file,
+            args,
+            function,
+        };
+
+        match self
+            .functions
+            .iter_mut()
+            .find(|f| f.name == declaration.name)
+        {
+            Some(f) => *f = declaration,
+            None => self.functions.push(declaration),
+        }
+
+        Ok(self)
+    }
+}
+
+impl Options {
+    /// The index of the custom function named `name`, if there is one
+    pub(crate) fn custom_function(&self, name: Identifier) -> Option<usize> {
+        self.functions.iter().position(|f| f.name == name)
+    }
 }
 
 fn raw_to_parse_error(map: &CodeMap, err: Error, unicode: bool) -> Box<Error> {
-    let (message, span) = err.raw();
+    if !err.is_raw() {
+        return Box::new(err);
+    }
+
+    let (message, span) = err.raw(map);
     Box::new(Error::from_loc(message, map.look_up_span(span), unicode))
 }
 
-/// Compile CSS from a path
+/// The output of a successful compilation, along with information about how
+/// it was produced
 ///
 /// ```
 /// fn main() -> Result<(), Box<grass::Error>> {
-///     let sass = grass::from_path("input.scss", &grass::Options::default())?;
+///     let result = grass::compile_string("a { b: c; }".to_string(), &grass::Options::default())?;
+///     assert_eq!(result.css(), "a {\n  b: c;\n}\n");
+///     assert!(result.loaded_urls().is_empty());
 ///     Ok(())
 /// }
 /// ```
-/// (grass does not currently allow files or paths that are not valid UTF-8)
-#[cfg_attr(feature = "profiling", inline(never))]
-#[cfg_attr(not(feature = "profiling"), inline)]
-#[cfg(not(feature = "wasm"))]
-pub fn from_path(p: &str, options: &Options) -> Result<String> {
-    let mut map = CodeMap::new();
-    let file = map.add_file(p.into(), String::from_utf8(fs::read(p)?)?);
+#[derive(Debug, Clone)]
+pub struct CompileResult {
+    css: String,
+    source_map: Option<String>,
+    loaded_urls: Vec<PathBuf>,
+    warnings: Vec<String>,
+}
+
+impl CompileResult {
+    /// The compiled CSS
+    #[must_use]
+    #[inline]
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+
+    /// The source map of the compiled CSS
+    ///
+    /// `grass` does not yet generate source maps, so this is always `None`.
+    #[must_use]
+    #[inline]
+    pub fn source_map(&self) -> Option<&str> {
+        self.source_map.as_deref()
+    }
+
+    /// The canonical path of every file read during compilation, including
+    /// the entrypoint and every file loaded through `@use`, `@import`, or
+    /// `meta.load-css()`
+    ///
+    /// Each path is listed once, in the order it was first loaded.
+    #[must_use]
+    #[inline]
+    pub fn loaded_urls(&self) -> &[PathBuf] {
+        &self.loaded_urls
+    }
+
+    /// The warnings emitted during compilation, e.g. by `@warn`
+    ///
+    /// These are not printed to stderr, and are empty if
+    /// [`Options::quiet`](Options::quiet) is set.
+    #[must_use]
+    #[inline]
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Consumes this result, returning the compiled CSS
+    #[must_use]
+    #[inline]
+    pub fn into_css(self) -> String {
+        self.css
+    }
+}
+
+/// Evaluates the entry file `file`, returning the CSS it emits
+///
+/// The precision of `options` must already be in effect, and must remain in
+/// effect while the returned CSS is printed.
+fn evaluate(
+    map: &mut CodeMap,
+    file: &Arc<File>,
+    toks: Vec<Token>,
+    path: &Path,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+) -> Result<Css> {
     let empty_span = file.span.subspan(0, 0);
+    let is_plain_css = is_css_file(path);
+
+    let toks = match &options.prepended_source {
+        Some((name, source)) if !is_plain_css => {
+            let prepended = map.add_file(name.clone(), source.clone());
+            let mut prepended_toks: Vec<Token> = Lexer::new(&prepended).collect();
+            prepended_toks.push(Token::new(prepended.span.subspan(0, 0), '\n'));
+            prepended_toks.extend(toks);
+            prepended_toks
+        }
+        Some(..) | None => toks,
+    };
+
+    let mut global_scope = SharedScope::default();
+
+    if !is_plain_css {
+        declare_global_variables(map, path, options, module_cache, &mut global_scope)
+            .map_err(|e| raw_to_parse_error(map, *e, options.unicode_error_messages))?;
+    }
 
     let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: p.as_ref(),
+        toks: &mut toks.into_iter().peekmore(),
+        map,
+        path,
         scopes: &mut Scopes::new(),
-        global_scope: &mut Scope::new(),
+        global_scope: &mut global_scope,
         super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
         span_before: empty_span,
         content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
+        flags: if is_plain_css {
+            ContextFlags::empty() | ContextFlags::IN_PLAIN_CSS
+        } else {
+            ContextFlags::empty()
+        },
         at_root: true,
         at_root_has_selector: false,
         extender: &mut Extender::new(empty_span),
@@ -305,14 +708,257 @@ pub fn from_path(p: &str, options: &Options) -> Result<String> {
         options,
         modules: &mut Modules::default(),
         module_config: &mut ModuleConfig::default(),
+        module_cache,
+        media_queries: &mut None,
     }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))?;
+    .parse_entrypoint()
+    .map_err(|e| raw_to_parse_error(map, *e, options.unicode_error_messages))?;
+
+    Css::from_stmts(stmts, false)
+        .map_err(|e| raw_to_parse_error(map, *e, options.unicode_error_messages))
+}
+
+fn compile(
+    map: &mut CodeMap,
+    file: &Arc<File>,
+    toks: Vec<Token>,
+    path: &Path,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+) -> Result<String> {
+    let _precision = PrecisionGuard::new(options.precision);
+
+    let css = evaluate(map, file, toks, path, options, module_cache)?;
+
+    let mut output = Vec::new();
+    write_css(map, file, css, options, &mut output)?;
+
+    Ok(String::from_utf8(output)?)
+}
+
+/// Like `compile`, but writes the CSS to `output` as it is printed
+#[cfg(not(feature = "wasm"))]
+fn compile_to_writer(
+    map: &mut CodeMap,
+    file: &Arc<File>,
+    toks: Vec<Token>,
+    path: &Path,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+    output: &mut dyn io::Write,
+) -> Result<()> {
+    let _precision = PrecisionGuard::new(options.precision);
+
+    let css = evaluate(map, file, toks, path, options, module_cache)?;
+
+    write_css(map, file, css, options, output)?;
 
-    Css::from_stmts(stmts, false, options.allows_charset)
-        .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))
+    Ok(output.flush()?)
+}
+
+/// Prints `css` to `output`, failing as soon as more than
+/// `Options::max_output_size` bytes have been printed
+///
+/// Whether a `@charset` declaration is needed is decided before anything is
+/// printed, so that the output can be streamed straight to `output`.
+fn write_css(
+    map: &CodeMap,
+    file: &Arc<File>,
+    css: Css,
+    options: &Options,
+    output: &mut dyn io::Write,
+) -> Result<()> {
+    if options.allows_charset {
+        let is_ascii = css
+            .is_ascii()
+            .map_err(|e| raw_to_parse_error(map, *e, options.unicode_error_messages))?;
+
+        if !is_ascii {
+            output.write_all(b"@charset \"UTF-8\";\n")?;
+        }
+    }
+
+    let mut output = LimitedWriter::new(output, options.max_output_size);
+
+    match css.write(&mut output, map) {
+        Ok(()) => Ok(()),
+        Err(..) if output.exceeded() => Err(Box::new(Error::from_loc(
+            format!(
+                "Maximum output size ({} bytes) exceeded.",
+                options.max_output_size.unwrap_or_default()
+            ),
+            map.look_up_span(file.span.subspan(0, 0)),
+            options.unicode_error_messages,
+        ))),
+        Err(e) => Err(raw_to_parse_error(map, *e, options.unicode_error_messages)),
+    }
+}
+
+/// Parses the parameters of a function passed to `Options::function`, from
+/// `file` containing everything after the opening parenthesis
+fn parse_custom_function_args(
+    map: &mut CodeMap,
+    file: &Arc<File>,
+    unicode_error_messages: bool,
+) -> Result<FuncArgs> {
+    let empty_span = file.span.subspan(0, 0);
+    let mut toks: Vec<Token> = Lexer::new(file).collect();
+    // the parameters are parsed as though they were followed by the body of
+    // a `@function` rule
+    let end = file.span.len();
+    toks.push(Token::new(file.span.subspan(end, end), '{'));
+
+    let options = Options::default();
+
+    let args = Parser {
+        toks: &mut toks.into_iter().peekmore(),
+        map,
+        path: Path::new(""),
+        scopes: &mut Scopes::new(),
+        global_scope: &mut SharedScope::default(),
+        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
+        span_before: empty_span,
+        content: &mut Vec::new(),
+        flags: ContextFlags::empty(),
+        at_root: true,
+        at_root_has_selector: false,
+        extender: &mut Extender::new(empty_span),
+        content_scopes: &mut Scopes::new(),
+        options: &options,
+        modules: &mut Modules::default(),
+        module_config: &mut ModuleConfig::default(),
+        module_cache: &mut ModuleCache::default(),
+        media_queries: &mut None,
+    }
+    .parse_custom_function_args();
+
+    args.map_err(|e| raw_to_parse_error(map, *e, unicode_error_messages))
+}
+
+/// Evaluates the values of the variables passed to
+/// `Options::global_variable_source` and `Options::global_variable_string`,
+/// declaring them in the global scope of the entry file
+fn declare_global_variables(
+    map: &mut CodeMap,
+    path: &Path,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+    global_scope: &mut SharedScope,
+) -> Result<()> {
+    for (name, value) in &options.global_variables {
+        let source = match value {
+            GlobalVariable::Source(source) => source,
+            GlobalVariable::QuotedString(s) => {
+                global_scope
+                    .borrow_mut()
+                    .insert_var(name.into(), Value::String(s.clone(), QuoteKind::Quoted));
+                continue;
+            }
+        };
+
+        let file = map.add_file(format!("${}", name), source.clone());
+        let empty_span = file.span.subspan(0, 0);
+        let toks: Vec<Token> = Lexer::new(&file).collect();
+
+        let value = Parser {
+            toks: &mut toks.into_iter().peekmore(),
+            map,
+            path,
+            scopes: &mut Scopes::new(),
+            global_scope,
+            super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
+            span_before: empty_span,
+            content: &mut Vec::new(),
+            flags: ContextFlags::empty(),
+            at_root: true,
+            at_root_has_selector: false,
+            extender: &mut Extender::new(empty_span),
+            content_scopes: &mut Scopes::new(),
+            options,
+            modules: &mut Modules::default(),
+            module_config: &mut ModuleConfig::default(),
+            module_cache,
+            media_queries: &mut None,
+        }
+        .parse_entire_value()?;
+
+        global_scope
+            .borrow_mut()
+            .insert_var(name.into(), value.node);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "wasm"))]
+fn compile_path_with(
+    map: &mut CodeMap,
+    p: &str,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+) -> Result<CompileResult> {
+    module_cache.collect_warnings();
+    module_cache.add_loaded_url(p.as_ref());
+
+    let (file, toks) = module_cache.load_file(map, p.as_ref(), p.into())?;
+    let css = compile(map, &file, toks, p.as_ref(), options, module_cache)?;
+
+    Ok(CompileResult {
+        css,
+        source_map: None,
+        loaded_urls: module_cache.loaded_urls().to_vec(),
+        warnings: module_cache.take_warnings(),
+    })
+}
+
+fn compile_string_with(
+    map: &mut CodeMap,
+    p: String,
+    options: &Options,
+    module_cache: &mut ModuleCache,
+) -> Result<CompileResult> {
+    module_cache.collect_warnings();
+
+    let file = map.add_file("stdin".into(), p);
+    let toks = Lexer::new(&file).collect();
+    let css = compile(map, &file, toks, Path::new(""), options, module_cache)?;
+
+    Ok(CompileResult {
+        css,
+        source_map: None,
+        loaded_urls: module_cache.loaded_urls().to_vec(),
+        warnings: module_cache.take_warnings(),
+    })
+}
+
+/// Compile CSS from a path
+///
+/// ```
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let sass = grass::from_path("input.scss", &grass::Options::default())?;
+///     Ok(())
+/// }
+/// ```
+/// (grass does not currently allow files or paths that are not valid UTF-8)
+///
+/// Files ending in `.css` are compiled as plain CSS, in which Sass-only
+/// syntax such as variables and nesting is not allowed.
+#[cfg_attr(feature = "profiling", inline(never))]
+#[cfg_attr(not(feature = "profiling"), inline)]
+#[cfg(not(feature = "wasm"))]
+pub fn from_path(p: &str, options: &Options) -> Result<String> {
+    let mut map = CodeMap::new();
+    let mut module_cache = ModuleCache::default();
+    let (file, toks) = module_cache.load_file(&mut map, p.as_ref(), p.into())?;
+
+    compile(
+        &mut map,
+        &file,
+        toks,
+        p.as_ref(),
+        options,
+        &mut module_cache,
+    )
 }
 
 /// Compile CSS from a string
@@ -330,70 +976,268 @@ pub fn from_path(p: &str, options: &Options) -> Result<String> {
 pub fn from_string(p: String, options: &Options) -> Result<String> {
     let mut map = CodeMap::new();
     let file = map.add_file("stdin".into(), p);
-    let empty_span = file.span.subspan(0, 0);
-    let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: Path::new(""),
-        scopes: &mut Scopes::new(),
-        global_scope: &mut Scope::new(),
-        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
-        span_before: empty_span,
-        content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
-        at_root: true,
-        at_root_has_selector: false,
-        extender: &mut Extender::new(empty_span),
-        content_scopes: &mut Scopes::new(),
+    let toks = Lexer::new(&file).collect();
+
+    compile(
+        &mut map,
+        &file,
+        toks,
+        Path::new(""),
         options,
-        modules: &mut Modules::default(),
-        module_config: &mut ModuleConfig::default(),
-    }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))?;
+        &mut ModuleCache::default(),
+    )
+}
 
-    Css::from_stmts(stmts, false, options.allows_charset)
-        .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e, options.unicode_error_messages))
+/// Compile CSS from a path, writing it to `output` as it is printed
+///
+/// Unlike [`from_path`](from_path), the CSS is never held in memory all at
+/// once, which reduces peak memory usage for very large stylesheets.
+///
+/// ```no_run
+/// use std::{fs::File, io::BufWriter};
+///
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let output = BufWriter::new(File::create("output.css")?);
+///     grass::from_path_to_writer("input.scss", output, &grass::Options::default())?;
+///     Ok(())
+/// }
+/// ```
+///
+/// If an error occurs while the CSS is being written, `output` may already
+/// contain part of the stylesheet.
+#[cfg_attr(feature = "profiling", inline(never))]
+#[cfg_attr(not(feature = "profiling"), inline)]
+#[cfg(not(feature = "wasm"))]
+pub fn from_path_to_writer<W: Write>(p: &str, mut output: W, options: &Options) -> Result<()> {
+    let mut map = CodeMap::new();
+    let mut module_cache = ModuleCache::default();
+    let (file, toks) = module_cache.load_file(&mut map, p.as_ref(), p.into())?;
+
+    compile_to_writer(
+        &mut map,
+        &file,
+        toks,
+        p.as_ref(),
+        options,
+        &mut module_cache,
+        &mut output,
+    )
 }
 
-#[cfg(feature = "wasm")]
-#[wasm_bindgen]
-pub fn from_string(p: String) -> std::result::Result<String, JsValue> {
+/// Compile CSS read from `input`, writing it to `output` as it is printed
+///
+/// The input is read to its end before compilation begins, and must be valid
+/// UTF-8. Errors inside of it are reported using the file name "stdin".
+///
+/// ```
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let mut output = Vec::new();
+///     grass::from_reader_to_writer(
+///         "a { b { color: &; } }".as_bytes(),
+///         &mut output,
+///         &grass::Options::default(),
+///     )?;
+///     assert_eq!(output, b"a b {\n  color: a b;\n}\n");
+///     Ok(())
+/// }
+/// ```
+///
+/// See [`from_path_to_writer`](from_path_to_writer) for more information.
+#[cfg_attr(feature = "profiling", inline(never))]
+#[cfg_attr(not(feature = "profiling"), inline)]
+#[cfg(not(feature = "wasm"))]
+pub fn from_reader_to_writer<R: Read, W: Write>(
+    mut input: R,
+    mut output: W,
+    options: &Options,
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    input.read_to_end(&mut buffer)?;
+
+    let mut map = CodeMap::new();
+    let file = map.add_file("stdin".into(), String::from_utf8(buffer)?);
+    let toks = Lexer::new(&file).collect();
+
+    compile_to_writer(
+        &mut map,
+        &file,
+        toks,
+        Path::new(""),
+        options,
+        &mut ModuleCache::default(),
+        &mut output,
+    )
+}
+
+/// Compile CSS from a path, returning the CSS along with the files it loaded
+/// and the warnings it emitted
+///
+/// See [`from_path`](from_path) for more information.
+#[cfg_attr(feature = "profiling", inline(never))]
+#[cfg_attr(not(feature = "profiling"), inline)]
+#[cfg(not(feature = "wasm"))]
+pub fn compile_path(p: &str, options: &Options) -> Result<CompileResult> {
+    compile_path_with(&mut CodeMap::new(), p, options, &mut ModuleCache::default())
+}
+
+/// Compile CSS from a string, returning the CSS along with the files it
+/// loaded and the warnings it emitted
+///
+/// See [`from_string`](from_string) for more information.
+#[cfg_attr(feature = "profiling", inline(never))]
+#[cfg_attr(not(feature = "profiling"), inline)]
+#[cfg(not(feature = "wasm"))]
+pub fn compile_string(p: String, options: &Options) -> Result<CompileResult> {
+    compile_string_with(&mut CodeMap::new(), p, options, &mut ModuleCache::default())
+}
+
+/// Parse a stylesheet from a path without compiling it
+///
+/// Imported and used files are not loaded. See the [`ast`](crate::ast) module
+/// for more information.
+///
+/// ```no_run
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let stylesheet = grass::parse_path("input.scss")?;
+///     Ok(())
+/// }
+/// ```
+#[cfg(not(feature = "wasm"))]
+pub fn parse_path(p: &str) -> Result<ast::Stylesheet> {
+    let mut map = CodeMap::new();
+    let file = map.add_file(p.into(), String::from_utf8(fs::read(p)?)?);
+
+    ast::AstParser::new(&file)
+        .parse_stylesheet()
+        .map_err(|e| raw_to_parse_error(&map, *e, true))
+}
+
+/// Parse a stylesheet from a string without compiling it
+///
+/// See [`parse_path`](parse_path) for more information.
+#[cfg(not(feature = "wasm"))]
+pub fn parse_string(p: String) -> Result<ast::Stylesheet> {
     let mut map = CodeMap::new();
     let file = map.add_file("stdin".into(), p);
-    let empty_span = file.span.subspan(0, 0);
 
-    let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: Path::new(""),
-        scopes: &mut Scopes::new(),
-        global_scope: &mut Scope::new(),
-        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
-        span_before: empty_span,
-        content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
-        at_root: true,
-        at_root_has_selector: false,
-        extender: &mut Extender::new(empty_span),
-        content_scopes: &mut Scopes::new(),
-        options: &Options::default(),
-        modules: &mut Modules::default(),
-        module_config: &mut ModuleConfig::default(),
-    }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e, true).to_string())?;
+    ast::AstParser::new(&file)
+        .parse_stylesheet()
+        .map_err(|e| raw_to_parse_error(&map, *e, true))
+}
+
+/// Format the stylesheet at a path, returning the formatted source
+///
+/// Formatting only changes whitespace, quotes, and the placement of comments,
+/// and formatting a stylesheet that is already formatted leaves it unchanged.
+///
+/// ```no_run
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let formatted = grass::format_path("input.scss")?;
+///     Ok(())
+/// }
+/// ```
+#[cfg(not(feature = "wasm"))]
+pub fn format_path(p: &str) -> Result<String> {
+    format_string_named(String::from_utf8(fs::read(p)?)?, p)
+}
+
+/// Format a stylesheet, returning the formatted source
+///
+/// ```
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let formatted = grass::format_string("a{color:red;b{c:'d'}}".to_string())?;
+///     assert_eq!(formatted, "a {\n  color: red;\n  b {\n    c: \"d\";\n  }\n}\n");
+///     Ok(())
+/// }
+/// ```
+#[cfg(not(feature = "wasm"))]
+pub fn format_string(p: String) -> Result<String> {
+    format_string_named(p, "stdin")
+}
+
+#[cfg(not(feature = "wasm"))]
+fn format_string_named(p: String, name: &str) -> Result<String> {
+    let mut map = CodeMap::new();
+    let file = map.add_file(name.into(), p);
+
+    let stylesheet = ast::AstParser::new(&file)
+        .parse_stylesheet()
+        .map_err(|e| raw_to_parse_error(&map, *e, true))?;
+
+    Ok(ast::format_stylesheet(file.source(), &stylesheet))
+}
+
+/// Compile several independent entry points in parallel, using up to one
+/// thread per available CPU
+///
+/// The results are returned in the same order as `paths`. Warnings are
+/// collected separately for each entry point rather than printed.
+///
+/// ```no_run
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let results = grass::compile_many(&["a.scss", "b.scss"], &grass::Options::default());
+///     for result in results {
+///         println!("{}", result?.css());
+///     }
+///     Ok(())
+/// }
+/// ```
+#[cfg(not(feature = "wasm"))]
+pub fn compile_many(paths: &[&str], options: &Options) -> Vec<Result<CompileResult>> {
+    // deeply nested stylesheets recurse deeply, so give each thread as much
+    // stack as the main thread typically has
+    const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<Option<Result<CompileResult>>> = paths.iter().map(|_| None).collect();
+
+    // each thread compiles entry points until none are left
+    let work = || {
+        let mut compiled = Vec::new();
+
+        loop {
+            let idx = next.fetch_add(1, Ordering::Relaxed);
+
+            match paths.get(idx) {
+                Some(path) => compiled.push((idx, compile_path(path, options))),
+                None => break compiled,
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .filter_map(|_| {
+                thread::Builder::new()
+                    .stack_size(STACK_SIZE)
+                    .spawn_scoped(scope, work)
+                    .ok()
+            })
+            .collect();
+
+        // if a thread couldn't be spawned, the entry points it would have
+        // compiled are compiled on this thread instead
+        let mut compiled = if handles.len() < threads {
+            work()
+        } else {
+            Vec::new()
+        };
+
+        for handle in handles {
+            match handle.join() {
+                Ok(mut thread_compiled) => compiled.append(&mut thread_compiled),
+                Err(e) => panic::resume_unwind(e),
+            }
+        }
+
+        for (idx, result) in compiled {
+            results[idx] = Some(result);
+        }
+    });
 
-    Ok(Css::from_stmts(stmts, false, true)
-        .map_err(|e| raw_to_parse_error(&map, *e, true).to_string())?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e, true).to_string())?)
+    results.into_iter().map(Option::unwrap).collect()
 }
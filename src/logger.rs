@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+
+/// A destination for the messages emitted during compilation
+///
+/// By default, warnings and the output of `@debug` rules are printed to
+/// stderr. A logger may be passed to
+/// [`Options::logger`](crate::Options::logger) to handle them differently,
+/// such as by forwarding them to a build tool.
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// #[derive(Debug, Default)]
+/// struct Collect(Mutex<Vec<String>>);
+///
+/// impl grass::Logger for Collect {
+///     fn warn(&self, message: &str) {
+///         self.0.lock().unwrap().push(message.to_owned());
+///     }
+///
+///     fn debug(&self, message: &str) {
+///         self.0.lock().unwrap().push(message.to_owned());
+///     }
+/// }
+///
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let options = grass::Options::default().logger(Box::new(Collect::default()));
+///     grass::from_string("@debug 1 + 1;".to_string(), &options)?;
+///     Ok(())
+/// }
+/// ```
+pub trait Logger: Debug + Send + Sync {
+    /// Emits a warning, such as one produced by a `@warn` rule
+    ///
+    /// The message includes the location of the code that emitted it.
+    fn warn(&self, message: &str);
+
+    /// Emits the output of a `@debug` rule
+    ///
+    /// The message includes the location of the `@debug` rule.
+    fn debug(&self, message: &str);
+}
+
+/// The logger used when none is passed to `Options::logger`, which prints
+/// every message to stderr
+#[derive(Debug)]
+pub(crate) struct StdErrLogger;
+
+impl Logger for StdErrLogger {
+    fn warn(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+
+    fn debug(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
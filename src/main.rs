@@ -1,13 +1,14 @@
 use std::{
-    fs::OpenOptions,
+    env,
+    fs::{self, OpenOptions},
     io::{stdin, stdout, BufWriter, Read, Write},
-    path::Path,
+    path::PathBuf,
 };
 
-use clap::{arg_enum, App, AppSettings, Arg};
+use clap::{arg_enum, App, AppSettings, Arg, ArgMatches, SubCommand};
 
 #[cfg(not(feature = "wasm"))]
-use grass::{from_path, from_string, Options};
+use grass::{format_path, format_string, from_path_to_writer, from_reader_to_writer, Options};
 
 arg_enum! {
     #[derive(PartialEq, Debug)]
@@ -33,6 +34,7 @@ fn main() {}
 fn main() -> std::io::Result<()> {
     let matches = App::new("grass")
         .setting(AppSettings::ColoredHelp)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .version(env!("CARGO_PKG_VERSION"))
         .about("A near-feature-complete Sass compiler written purely in Rust")
         .version_short("v")
@@ -56,6 +58,11 @@ fn main() -> std::io::Result<()> {
                 .takes_value(true)
                 .number_of_values(1)
         )
+        .arg(
+            Arg::with_name("NODE_MODULES")
+                .long("node-modules")
+                .help("Resolve imports of packages from node_modules directories.")
+        )
         .arg(
             Arg::with_name("STYLE")
                 .short("s")
@@ -169,26 +176,63 @@ fn main() -> std::io::Result<()> {
             Arg::with_name("OUTPUT")
                 .help("Output SCSS file")
         )
-
-        // Hidden, legacy arguments
         .arg(
             Arg::with_name("PRECISION")
                 .long("precision")
-                .hidden(true)
+                .help("How many digits of precision to use when outputting decimal numbers.")
                 .takes_value(true)
+                .validator(|p| p.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Format SCSS files in place")
+                .arg(
+                    Arg::with_name("CHECK")
+                        .long("check")
+                        .help("Don't write any files, and exit with status 1 if any file isn't formatted"),
+                )
+                .arg(
+                    Arg::with_name("STDIN")
+                        .long("stdin")
+                        .help("Format the stylesheet from stdin, writing it to stdout"),
+                )
+                .arg(
+                    Arg::with_name("FILES")
+                        .required_unless("STDIN")
+                        .multiple(true)
+                        .help("SCSS files to format"),
+                ),
         )
         .get_matches();
 
-    let load_paths = matches
+    if let Some(matches) = matches.subcommand_matches("fmt") {
+        return fmt(matches);
+    }
+
+    // paths from `SASS_PATH` are searched after those passed with `--load-path`
+    let sass_path: Vec<PathBuf> =
+        env::var_os("SASS_PATH").map_or_else(Vec::new, |paths| env::split_paths(&paths).collect());
+
+    let load_paths: Vec<PathBuf> = matches
         .values_of("LOAD_PATH")
-        .map_or_else(Vec::new, |vals| vals.map(Path::new).collect());
+        .map_or_else(Vec::new, |vals| vals.map(PathBuf::from).collect())
+        .into_iter()
+        .chain(sass_path)
+        .collect();
 
-    let options = &Options::default()
+    let mut options = Options::default()
         .load_paths(&load_paths)
+        .resolve_node_modules(matches.is_present("NODE_MODULES"))
         .quiet(matches.is_present("QUIET"))
         .unicode_error_messages(!matches.is_present("NO_UNICODE"))
         .allows_charset(!matches.is_present("NO_CHARSET"));
 
+    if let Some(precision) = matches.value_of("PRECISION") {
+        options = options.precision(precision.parse().unwrap());
+    }
+
+    let options = &options;
+
     let (mut stdout_write, mut file_write);
     let buf_out: &mut dyn Write = if let Some(path) = matches.value_of("OUTPUT") {
         file_write = BufWriter::new(
@@ -204,26 +248,72 @@ fn main() -> std::io::Result<()> {
         &mut stdout_write
     };
 
-    buf_out.write_all(
-        if let Some(name) = matches.value_of("INPUT") {
-            from_path(name, &options)
-        } else if matches.is_present("STDIN") {
-            from_string(
-                {
-                    let mut buffer = String::new();
-                    stdin().read_to_string(&mut buffer)?;
-                    buffer
-                },
-                options,
-            )
+    if let Some(name) = matches.value_of("INPUT") {
+        from_path_to_writer(name, buf_out, options)
+    } else if matches.is_present("STDIN") {
+        from_reader_to_writer(stdin(), buf_out, options)
+    } else {
+        unreachable!()
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    Ok(())
+}
+
+/// Formats the files passed to `grass fmt`, or with `--check` reports those
+/// that aren't formatted
+#[cfg(not(feature = "wasm"))]
+fn fmt(matches: &ArgMatches) -> std::io::Result<()> {
+    let check = matches.is_present("CHECK");
+
+    if matches.is_present("STDIN") {
+        let mut input = String::new();
+        stdin().read_to_string(&mut input)?;
+
+        let formatted = format_string(input.clone()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+
+        if check {
+            if formatted != input {
+                eprintln!("stdin is not formatted");
+                std::process::exit(1);
+            }
         } else {
-            unreachable!()
+            stdout().write_all(formatted.as_bytes())?;
         }
-        .unwrap_or_else(|e| {
+
+        return Ok(());
+    }
+
+    let mut unformatted = false;
+
+    for path in matches.values_of("FILES").into_iter().flatten() {
+        let input = fs::read_to_string(path)?;
+
+        let formatted = format_path(path).unwrap_or_else(|e| {
             eprintln!("{}", e);
             std::process::exit(1)
-        })
-        .as_bytes(),
-    )?;
+        });
+
+        if formatted == input {
+            continue;
+        }
+
+        if check {
+            eprintln!("{} is not formatted", path);
+            unformatted = true;
+        } else {
+            fs::write(path, formatted)?;
+        }
+    }
+
+    if unformatted {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
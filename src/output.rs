@@ -1,5 +1,8 @@
 //! # Convert from SCSS AST to CSS
-use std::{io::Write, mem};
+use std::{
+    io::{self, Write},
+    mem,
+};
 
 use codemap::CodeMap;
 
@@ -19,7 +22,15 @@ use crate::{
 struct ToplevelUnknownAtRule {
     name: String,
     params: String,
-    body: Vec<Stmt>,
+    /// `None` if the rule has no body at all
+    body: Option<Css>,
+}
+
+#[derive(Debug, Clone)]
+struct ToplevelKeyframes {
+    rule: String,
+    name: String,
+    body: Option<Css>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,10 +38,10 @@ enum Toplevel {
     RuleSet(Selector, Vec<BlockEntry>),
     MultilineComment(String),
     UnknownAtRule(Box<ToplevelUnknownAtRule>),
-    Keyframes(Box<Keyframes>),
+    Keyframes(Box<ToplevelKeyframes>),
     KeyframesRuleSet(Vec<KeyframesSelector>, Vec<BlockEntry>),
-    Media { query: String, body: Vec<Stmt> },
-    Supports { params: String, body: Vec<Stmt> },
+    Media { query: String, body: Css },
+    Supports { params: String, body: Option<Css> },
     Newline,
     // todo: do we actually need a toplevel style variant?
     Style(Style),
@@ -85,26 +96,39 @@ impl Toplevel {
 pub(crate) struct Css {
     blocks: Vec<Toplevel>,
     in_at_rule: bool,
-    allows_charset: bool,
     plain_imports: Vec<Toplevel>,
 }
 
 impl Css {
-    pub const fn new(in_at_rule: bool, allows_charset: bool) -> Self {
+    pub const fn new(in_at_rule: bool) -> Self {
         Css {
             blocks: Vec::new(),
             in_at_rule,
-            allows_charset,
             plain_imports: Vec::new(),
         }
     }
 
-    pub(crate) fn from_stmts(
-        s: Vec<Stmt>,
-        in_at_rule: bool,
-        allows_charset: bool,
-    ) -> SassResult<Self> {
-        Css::new(in_at_rule, allows_charset).parse_stylesheet(s)
+    pub(crate) fn from_stmts(s: Vec<Stmt>, in_at_rule: bool) -> SassResult<Self> {
+        Css::new(in_at_rule).parse_stylesheet(s)
+    }
+
+    /// Converts the body of an at-rule, which is `None` if the rule has no
+    /// body at all
+    fn from_body(body: Vec<Stmt>) -> SassResult<Option<Self>> {
+        if body.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Css::from_stmts(body, true)?))
+    }
+
+    fn keyframes(keyframes: Keyframes) -> SassResult<Toplevel> {
+        let Keyframes { rule, name, body } = keyframes;
+        Ok(Toplevel::Keyframes(Box::new(ToplevelKeyframes {
+            rule,
+            name,
+            body: Css::from_body(body)?,
+        })))
     }
 
     fn parse_stmt(&mut self, stmt: Stmt) -> SassResult<Vec<Toplevel>> {
@@ -124,12 +148,19 @@ impl Css {
                         Stmt::Style(s) => vals.first_mut().unwrap().push_style(s),
                         Stmt::Comment(s) => vals.first_mut().unwrap().push_comment(s),
                         Stmt::Media(m) => {
-                            let MediaRule { query, body, .. } = *m;
-                            vals.push(Toplevel::Media { query, body })
+                            let query = m.query_string();
+                            let MediaRule { body, .. } = *m;
+                            vals.push(Toplevel::Media {
+                                query,
+                                body: Css::from_stmts(body, true)?,
+                            })
                         }
                         Stmt::Supports(s) => {
                             let SupportsRule { params, body } = *s;
-                            vals.push(Toplevel::Supports { params, body })
+                            vals.push(Toplevel::Supports {
+                                params,
+                                body: Css::from_body(body)?,
+                            })
                         }
                         Stmt::UnknownAtRule(u) => {
                             let UnknownAtRule {
@@ -137,25 +168,18 @@ impl Css {
                             } = *u;
                             vals.push(Toplevel::UnknownAtRule(Box::new(ToplevelUnknownAtRule {
                                 params,
-                                body,
+                                body: Css::from_body(body)?,
                                 name,
                             })))
                         }
                         Stmt::Return(..) => unreachable!(),
-                        Stmt::AtRoot { body } => {
+                        Stmt::AtRoot { body, .. } => {
                             body.into_iter().try_for_each(|r| -> SassResult<()> {
                                 vals.append(&mut self.parse_stmt(r)?);
                                 Ok(())
                             })?
                         }
-                        Stmt::Keyframes(k) => {
-                            let Keyframes { rule, name, body } = *k;
-                            vals.push(Toplevel::Keyframes(Box::new(Keyframes {
-                                rule,
-                                name,
-                                body,
-                            })))
-                        }
+                        Stmt::Keyframes(k) => vals.push(Css::keyframes(*k)?),
                         k @ Stmt::KeyframesRuleSet(..) => {
                             unreachable!("@keyframes ruleset {:?}", k)
                         }
@@ -171,12 +195,19 @@ impl Css {
             }
             Stmt::Style(s) => vec![Toplevel::Style(s)],
             Stmt::Media(m) => {
-                let MediaRule { query, body, .. } = *m;
-                vec![Toplevel::Media { query, body }]
+                let query = m.query_string();
+                let MediaRule { body, .. } = *m;
+                vec![Toplevel::Media {
+                    query,
+                    body: Css::from_stmts(body, true)?,
+                }]
             }
             Stmt::Supports(s) => {
                 let SupportsRule { params, body } = *s;
-                vec![Toplevel::Supports { params, body }]
+                vec![Toplevel::Supports {
+                    params,
+                    body: Css::from_body(body)?,
+                }]
             }
             Stmt::UnknownAtRule(u) => {
                 let UnknownAtRule {
@@ -185,12 +216,18 @@ impl Css {
                 vec![Toplevel::UnknownAtRule(Box::new(ToplevelUnknownAtRule {
                     params,
                     name,
-                    body,
+                    body: Css::from_body(body)?,
                 }))]
             }
             Stmt::Return(..) => unreachable!("@return: {:?}", stmt),
-            Stmt::AtRoot { .. } => unreachable!("@at-root: {:?}", stmt),
-            Stmt::Keyframes(k) => vec![Toplevel::Keyframes(k)],
+            Stmt::AtRoot { body, .. } => {
+                let mut vals = Vec::new();
+                for stmt in body {
+                    vals.append(&mut self.parse_stmt(stmt)?);
+                }
+                vals
+            }
+            Stmt::Keyframes(k) => vec![Css::keyframes(*k)?],
             Stmt::KeyframesRuleSet(k) => {
                 let KeyframesRuleSet { body, selector } = *k;
                 if body.is_empty() {
@@ -233,35 +270,89 @@ impl Css {
         Ok(self)
     }
 
-    pub fn pretty_print(mut self, map: &CodeMap) -> SassResult<String> {
-        let mut string = Vec::new();
-        let allows_charset = self.allows_charset;
-        self._inner_pretty_print(&mut string, map, 0)?;
-        if allows_charset && string.iter().any(|s| !s.is_ascii()) {
-            return Ok(format!("@charset \"UTF-8\";\n{}", unsafe {
-                String::from_utf8_unchecked(string)
-            }));
+    /// Writes the stylesheet to `buf` as it is printed, rather than building
+    /// the whole output in memory
+    ///
+    /// No `@charset` declaration is written; see [`Css::is_ascii`].
+    pub fn write(self, buf: &mut dyn Write, map: &CodeMap) -> SassResult<()> {
+        self._inner_pretty_print(buf, map, 0)
+    }
+
+    /// Whether the printed stylesheet contains only ASCII, in which case it
+    /// doesn't need a `@charset` declaration
+    ///
+    /// This is checked before anything is written, so that the declaration
+    /// can come first without holding back the rest of the output.
+    pub fn is_ascii(&self) -> SassResult<bool> {
+        for block in &self.blocks {
+            let is_ascii = match block {
+                Toplevel::RuleSet(selector, styles) => {
+                    styles.is_empty()
+                        || (selector.to_string().is_ascii() && entries_are_ascii(styles)?)
+                }
+                Toplevel::KeyframesRuleSet(selector, body) => {
+                    body.is_empty()
+                        || (selector.iter().all(|s| s.to_string().is_ascii())
+                            && entries_are_ascii(body)?)
+                }
+                Toplevel::MultilineComment(s) | Toplevel::Import(s) => s.is_ascii(),
+                Toplevel::UnknownAtRule(u) => {
+                    u.name.is_ascii() && u.params.is_ascii() && body_is_ascii(&u.body)?
+                }
+                Toplevel::Keyframes(k) => {
+                    k.rule.is_ascii() && k.name.is_ascii() && body_is_ascii(&k.body)?
+                }
+                Toplevel::Supports { params, body } => params.is_ascii() && body_is_ascii(body)?,
+                Toplevel::Media { query, body } => {
+                    body.is_empty() || (query.is_ascii() && body.is_ascii()?)
+                }
+                Toplevel::Style(s) => s.to_string()?.is_ascii(),
+                Toplevel::Newline => true,
+            };
+
+            if !is_ascii {
+                return Ok(false);
+            }
         }
-        Ok(unsafe { String::from_utf8_unchecked(string) })
+
+        Ok(true)
+    }
+
+    /// Whether printing the stylesheet would write nothing
+    fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|block| match block {
+            Toplevel::RuleSet(_, entries) | Toplevel::KeyframesRuleSet(_, entries) => {
+                entries.is_empty()
+            }
+            Toplevel::Media { body, .. } => body.is_empty(),
+            Toplevel::Newline => true,
+            Toplevel::MultilineComment(..)
+            | Toplevel::UnknownAtRule(..)
+            | Toplevel::Keyframes(..)
+            | Toplevel::Supports { .. }
+            | Toplevel::Style(..)
+            | Toplevel::Import(..) => false,
+        })
     }
 
     fn _inner_pretty_print(
-        &mut self,
-        buf: &mut Vec<u8>,
+        self,
+        buf: &mut dyn Write,
         map: &CodeMap,
         nesting: usize,
     ) -> SassResult<()> {
         let mut has_written = false;
         let padding = vec![' '; nesting * 2].iter().collect::<String>();
         let mut should_emit_newline = false;
-        for block in mem::take(&mut self.blocks) {
+        let in_at_rule = self.in_at_rule;
+        for block in self.blocks {
             match block {
                 Toplevel::RuleSet(selector, styles) => {
                     if styles.is_empty() {
                         continue;
                     }
                     has_written = true;
-                    if should_emit_newline && !self.in_at_rule {
+                    if should_emit_newline && !in_at_rule {
                         should_emit_newline = false;
                         writeln!(buf)?;
                     }
@@ -282,7 +373,7 @@ impl Css {
                         "{}{} {{",
                         padding,
                         selector
-                            .into_iter()
+                            .iter()
                             .map(|s| s.to_string())
                             .collect::<Vec<String>>()
                             .join(", ")
@@ -313,22 +404,20 @@ impl Css {
                         write!(buf, "{}@{} {}", padding, name, params)?;
                     }
 
-                    if body.is_empty() {
-                        writeln!(buf, ";")?;
-                        continue;
-                    } else {
-                        writeln!(buf, " {{")?;
-                    }
+                    let body = match body {
+                        Some(body) => body,
+                        None => {
+                            writeln!(buf, ";")?;
+                            continue;
+                        }
+                    };
 
-                    Css::from_stmts(body, true, self.allows_charset)?._inner_pretty_print(
-                        buf,
-                        map,
-                        nesting + 1,
-                    )?;
+                    writeln!(buf, " {{")?;
+                    body._inner_pretty_print(buf, map, nesting + 1)?;
                     writeln!(buf, "{}}}", padding)?;
                 }
                 Toplevel::Keyframes(k) => {
-                    let Keyframes { rule, name, body } = *k;
+                    let ToplevelKeyframes { rule, name, body } = *k;
                     if should_emit_newline {
                         should_emit_newline = false;
                         writeln!(buf)?;
@@ -340,18 +429,16 @@ impl Css {
                         write!(buf, " {}", name)?;
                     }
 
-                    if body.is_empty() {
-                        writeln!(buf, " {{}}")?;
-                        continue;
-                    } else {
-                        writeln!(buf, " {{")?;
-                    }
+                    let body = match body {
+                        Some(body) => body,
+                        None => {
+                            writeln!(buf, " {{}}")?;
+                            continue;
+                        }
+                    };
 
-                    Css::from_stmts(body, true, self.allows_charset)?._inner_pretty_print(
-                        buf,
-                        map,
-                        nesting + 1,
-                    )?;
+                    writeln!(buf, " {{")?;
+                    body._inner_pretty_print(buf, map, nesting + 1)?;
                     writeln!(buf, "{}}}", padding)?;
                 }
                 Toplevel::Supports { params, body } => {
@@ -366,31 +453,26 @@ impl Css {
                         write!(buf, "{}@supports {}", padding, params)?;
                     }
 
-                    if body.is_empty() {
-                        writeln!(buf, ";")?;
-                        continue;
-                    } else {
-                        writeln!(buf, " {{")?;
-                    }
+                    let body = match body {
+                        Some(body) => body,
+                        None => {
+                            writeln!(buf, ";")?;
+                            continue;
+                        }
+                    };
 
-                    Css::from_stmts(body, true, self.allows_charset)?._inner_pretty_print(
-                        buf,
-                        map,
-                        nesting + 1,
-                    )?;
+                    writeln!(buf, " {{")?;
+                    body._inner_pretty_print(buf, map, nesting + 1)?;
                     writeln!(buf, "{}}}", padding)?;
                 }
                 Toplevel::Media { query, body } => {
+                    // media rules containing only empty style rules are omitted
                     if body.is_empty() {
                         continue;
                     }
 
                     writeln!(buf, "{}@media {} {{", padding, query)?;
-                    Css::from_stmts(body, true, self.allows_charset)?._inner_pretty_print(
-                        buf,
-                        map,
-                        nesting + 1,
-                    )?;
+                    body._inner_pretty_print(buf, map, nesting + 1)?;
                     writeln!(buf, "{}}}", padding)?;
                 }
                 Toplevel::Style(s) => {
@@ -407,3 +489,65 @@ impl Css {
         Ok(())
     }
 }
+
+fn entries_are_ascii(entries: &[BlockEntry]) -> SassResult<bool> {
+    for entry in entries {
+        if !entry.to_string()?.is_ascii() {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn body_is_ascii(body: &Option<Css>) -> SassResult<bool> {
+    match body {
+        Some(body) => body.is_ascii(),
+        None => Ok(true),
+    }
+}
+
+/// A writer that fails once more than `limit` bytes have been written to it
+pub(crate) struct LimitedWriter<'a> {
+    inner: &'a mut dyn Write,
+    remaining: Option<usize>,
+    exceeded: bool,
+}
+
+impl<'a> LimitedWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write, limit: Option<usize>) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            exceeded: false,
+        }
+    }
+
+    /// Whether writing failed because the limit was exceeded
+    pub const fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+impl Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(remaining) = &mut self.remaining {
+            if buf.len() > *remaining {
+                self.exceeded = true;
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "maximum output size exceeded",
+                ));
+            }
+
+            *remaining -= buf.len();
+        }
+
+        self.inner.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,245 @@
+use std::{collections::HashSet, mem};
+
+use codemap::Spanned;
+
+use crate::{
+    atrule::{AtRootQuery, SupportsRule, UnknownAtRule},
+    error::SassResult,
+};
+
+use super::{Parser, Stmt};
+
+impl<'a> Parser<'a> {
+    /// Parse the query of an `@at-root` rule, e.g. `(without: media supports)`
+    ///
+    /// Assumes the next token is the opening parenthesis
+    pub(super) fn parse_at_root_query(&mut self) -> SassResult<AtRootQuery> {
+        self.expect_char('(')?;
+        self.whitespace_or_comment();
+
+        let Spanned { node: kind, span } = self.parse_identifier()?;
+
+        let include = match kind.to_ascii_lowercase().as_str() {
+            "with" => true,
+            "without" => false,
+            _ => return Err(("expected \"with\" or \"without\".", span).into()),
+        };
+
+        self.whitespace_or_comment();
+        self.expect_char(':')?;
+        self.whitespace_or_comment();
+
+        let mut names = HashSet::new();
+
+        loop {
+            names.insert(self.parse_identifier()?.node.to_ascii_lowercase());
+            self.whitespace_or_comment();
+
+            if self.consume_char_if_exists(')') {
+                break;
+            }
+
+            if self.toks.peek().is_none() {
+                return Err(("expected \")\".", self.span_before).into());
+            }
+        }
+
+        self.whitespace_or_comment();
+
+        Ok(AtRootQuery::new(include, names))
+    }
+}
+
+impl Stmt {
+    /// The name used to match this rule against an `@at-root` query, if this
+    /// is an at-rule that `@at-root` rules may be moved outside of
+    fn at_rule_name(&self) -> Option<&str> {
+        match self {
+            Stmt::Media(..) => Some("media"),
+            Stmt::Supports(..) => Some("supports"),
+            Stmt::UnknownAtRule(u) => Some(&u.name),
+            _ => None,
+        }
+    }
+
+    fn at_rule_body_mut(&mut self) -> Option<&mut Vec<Stmt>> {
+        match self {
+            Stmt::Media(m) => Some(&mut m.body),
+            Stmt::Supports(s) => Some(&mut s.body),
+            Stmt::UnknownAtRule(u) => Some(&mut u.body),
+            _ => None,
+        }
+    }
+
+    /// Creates a copy of this at-rule with a new body
+    fn with_body(&self, body: Vec<Stmt>) -> Stmt {
+        match self {
+            Stmt::Media(m) => {
+                let mut media = m.clone();
+                media.body = body;
+                Stmt::Media(media)
+            }
+            Stmt::Supports(s) => Stmt::Supports(Box::new(SupportsRule {
+                params: s.params.clone(),
+                body,
+            })),
+            Stmt::UnknownAtRule(u) => Stmt::UnknownAtRule(Box::new(UnknownAtRule {
+                name: u.name.clone(),
+                super_selector: u.super_selector.clone(),
+                params: u.params.clone(),
+                body,
+            })),
+            _ => unreachable!("only at-rules may be copied with a new body"),
+        }
+    }
+
+    /// Moves any `@at-root` rules inside of this at-rule whose query excludes
+    /// it to directly after it
+    ///
+    /// `@at-root` rules that are not excluded by this at-rule are moved to the
+    /// top level of its body, outside of any style rules, so that the
+    /// at-rules enclosing this one may in turn decide whether or not to
+    /// exclude them.
+    pub(super) fn hoist_at_root_rules(mut self) -> Vec<Stmt> {
+        let name = match self.at_rule_name() {
+            Some(name) => name.to_owned(),
+            None => return vec![self],
+        };
+
+        let mut escaped = Vec::new();
+        let mut kept = Vec::new();
+        let mut is_empty = false;
+
+        if let Some(body) = self.at_rule_body_mut() {
+            take_at_root_rules(body, &name, &mut escaped, &mut kept);
+            body.append(&mut kept);
+            is_empty = has_no_output(body);
+        }
+
+        // rules that only contained `@at-root` rules are removed entirely
+        if is_empty && !escaped.is_empty() {
+            return escaped;
+        }
+
+        let mut stmts = vec![self];
+        stmts.append(&mut escaped);
+        stmts
+    }
+}
+
+/// Removes all `@at-root` rules excluded by the at-rule `name` from `body`,
+/// as well as all `@at-root` rules nested inside of style rules
+///
+/// `@at-root` rules that escape a nested at-rule are wrapped in a copy of
+/// that at-rule, as they have already been determined to belong inside it.
+/// Those that are not excluded by `name` are left inside nested at-rules.
+fn take_at_root_rules(
+    body: &mut Vec<Stmt>,
+    name: &str,
+    escaped: &mut Vec<Stmt>,
+    kept: &mut Vec<Stmt>,
+) {
+    for stmt in mem::take(body) {
+        match stmt {
+            Stmt::AtRoot { query, body: inner } => {
+                if query.excludes_name(name) {
+                    escaped.push(Stmt::AtRoot { query, body: inner });
+                } else {
+                    kept.push(Stmt::AtRoot { query, body: inner });
+                }
+            }
+            Stmt::RuleSet {
+                selector,
+                body: mut inner,
+            } => {
+                let was_empty = inner.is_empty();
+                take_at_root_rules(&mut inner, name, escaped, kept);
+                if was_empty || !inner.is_empty() {
+                    body.push(Stmt::RuleSet {
+                        selector,
+                        body: inner,
+                    });
+                }
+            }
+            mut rule @ Stmt::Media(..)
+            | mut rule @ Stmt::Supports(..)
+            | mut rule @ Stmt::UnknownAtRule(..) => {
+                let found = take_nested_at_root_rules(&mut rule, name);
+                let is_empty = !found.is_empty()
+                    && rule.at_rule_body_mut().map_or(false, |b| has_no_output(b));
+
+                for (query, inner) in found {
+                    escaped.push(Stmt::AtRoot { query, body: inner });
+                }
+
+                if !is_empty {
+                    body.push(rule);
+                }
+            }
+            stmt => body.push(stmt),
+        }
+    }
+}
+
+/// Removes all `@at-root` rules excluded by the at-rule `name` from the body
+/// of the nested at-rule `rule`, wrapping them in copies of any at-rules
+/// nested between the two
+fn take_nested_at_root_rules(rule: &mut Stmt, name: &str) -> Vec<(AtRootQuery, Vec<Stmt>)> {
+    let found = match rule.at_rule_body_mut() {
+        Some(body) => take_at_root_rules_from_body(body, name),
+        None => return Vec::new(),
+    };
+
+    found
+        .into_iter()
+        .map(|(query, inner)| (query, vec![rule.with_body(inner)]))
+        .collect()
+}
+
+fn take_at_root_rules_from_body(body: &mut Vec<Stmt>, name: &str) -> Vec<(AtRootQuery, Vec<Stmt>)> {
+    let mut found = Vec::new();
+
+    for stmt in mem::take(body) {
+        match stmt {
+            Stmt::AtRoot { query, body: inner } if query.excludes_name(name) => {
+                found.push((query, inner));
+            }
+            Stmt::RuleSet {
+                selector,
+                body: mut inner,
+            } => {
+                let was_empty = inner.is_empty();
+                found.append(&mut take_at_root_rules_from_body(&mut inner, name));
+                if was_empty || !inner.is_empty() {
+                    body.push(Stmt::RuleSet {
+                        selector,
+                        body: inner,
+                    });
+                }
+            }
+            mut nested @ Stmt::Media(..)
+            | mut nested @ Stmt::Supports(..)
+            | mut nested @ Stmt::UnknownAtRule(..) => {
+                let mut nested_found = take_nested_at_root_rules(&mut nested, name);
+                if nested_found.is_empty()
+                    || !nested
+                        .at_rule_body_mut()
+                        .map_or(false, |b| has_no_output(b))
+                {
+                    body.push(nested);
+                }
+                found.append(&mut nested_found);
+            }
+            stmt => body.push(stmt),
+        }
+    }
+
+    found
+}
+
+/// Whether or not `body` consists only of empty style rules, which are not
+/// emitted
+pub(super) fn has_no_output(body: &[Stmt]) -> bool {
+    body.iter()
+        .all(|stmt| matches!(stmt, Stmt::RuleSet { body, .. } if body.is_empty()))
+}
@@ -51,6 +51,8 @@ impl<'a> Parser<'a> {
                 options: self.options,
                 modules: self.modules,
                 module_config: self.module_config,
+                module_cache: self.module_cache,
+                media_queries: self.media_queries,
             }
             .parse_stmt()?;
             self.scopes.exit_scope();
@@ -109,6 +111,8 @@ impl<'a> Parser<'a> {
                                 options: self.options,
                                 modules: self.modules,
                                 module_config: self.module_config,
+                                module_cache: self.module_cache,
+                                media_queries: self.media_queries,
                             }
                             .parse_stmt()?;
                             self.scopes.exit_scope();
@@ -141,6 +145,8 @@ impl<'a> Parser<'a> {
                                 options: self.options,
                                 modules: self.modules,
                                 module_config: self.module_config,
+                                module_cache: self.module_cache,
+                                media_queries: self.media_queries,
                             }
                             .parse_stmt();
                             self.scopes.exit_scope();
@@ -238,6 +244,8 @@ impl<'a> Parser<'a> {
             }
         };
 
+        let span = from_val.span.merge(to_val.span);
+
         self.expect_char('{')?;
 
         let body = read_until_closing_curly_brace(self.toks)?;
@@ -260,6 +268,7 @@ impl<'a> Parser<'a> {
         self.scopes.enter_new_scope();
 
         for i in iter {
+            self.add_loop_iteration(span)?;
             self.scopes.insert_var_last(
                 var.node,
                 Value::Dimension(Some(Number::from(i)), Unit::None, true),
@@ -282,6 +291,8 @@ impl<'a> Parser<'a> {
                     options: self.options,
                     modules: self.modules,
                     module_config: self.module_config,
+                    module_cache: self.module_cache,
+                    media_queries: self.media_queries,
                 }
                 .parse_stmt()?;
                 if !these_stmts.is_empty() {
@@ -306,6 +317,8 @@ impl<'a> Parser<'a> {
                         options: self.options,
                         modules: self.modules,
                         module_config: self.module_config,
+                        module_cache: self.module_cache,
+                        media_queries: self.media_queries,
                     }
                     .parse_stmt()?,
                 );
@@ -341,6 +354,7 @@ impl<'a> Parser<'a> {
         let mut val = self.parse_value_from_vec(cond.clone(), true)?;
         self.scopes.enter_new_scope();
         while val.node.is_true() {
+            self.add_loop_iteration(val.span)?;
             if self.flags.in_function() {
                 let these_stmts = Parser {
                     toks: &mut body.clone().into_iter().peekmore(),
@@ -359,6 +373,8 @@ impl<'a> Parser<'a> {
                     options: self.options,
                     modules: self.modules,
                     module_config: self.module_config,
+                    module_cache: self.module_cache,
+                    media_queries: self.media_queries,
                 }
                 .parse_stmt()?;
                 if !these_stmts.is_empty() {
@@ -383,6 +399,8 @@ impl<'a> Parser<'a> {
                         options: self.options,
                         modules: self.modules,
                         module_config: self.module_config,
+                        module_cache: self.module_cache,
+                        media_queries: self.media_queries,
                     }
                     .parse_stmt()?,
                 );
@@ -423,10 +441,8 @@ impl<'a> Parser<'a> {
         }
         self.whitespace_or_comment();
         let iter_val_toks = read_until_open_curly_brace(self.toks)?;
-        let iter = self
-            .parse_value_from_vec(iter_val_toks, true)?
-            .node
-            .as_list();
+        let Spanned { node: iter, span } = self.parse_value_from_vec(iter_val_toks, true)?;
+        let iter = iter.as_list();
         self.toks.next();
         self.whitespace();
         let mut body = read_until_closing_curly_brace(self.toks)?;
@@ -441,6 +457,7 @@ impl<'a> Parser<'a> {
         self.scopes.enter_new_scope();
 
         for row in iter {
+            self.add_loop_iteration(span)?;
             if vars.len() == 1 {
                 self.scopes.insert_var_last(vars[0].node, row);
             } else {
@@ -471,6 +488,8 @@ impl<'a> Parser<'a> {
                     options: self.options,
                     modules: self.modules,
                     module_config: self.module_config,
+                    module_cache: self.module_cache,
+                    media_queries: self.media_queries,
                 }
                 .parse_stmt()?;
                 if !these_stmts.is_empty() {
@@ -495,6 +514,8 @@ impl<'a> Parser<'a> {
                         options: self.options,
                         modules: self.modules,
                         module_config: self.module_config,
+                        module_cache: self.module_cache,
+                        media_queries: self.media_queries,
                     }
                     .parse_stmt()?,
                 );
@@ -2,7 +2,7 @@ use codemap::Spanned;
 use peekmore::PeekMore;
 
 use crate::{
-    args::CallArgs,
+    args::{CallArgs, FuncArgs},
     atrule::Function,
     common::{unvendor, Identifier},
     error::SassResult,
@@ -53,12 +53,12 @@ impl<'a> Parser<'a> {
         });
         self.whitespace();
 
-        let function = Function::new(args, body, self.at_root, span);
+        let function = Function::new(args, body, self.at_root, self.module_cache.current(), span);
 
         let name_as_ident = Identifier::from(name);
 
         if self.at_root {
-            self.global_scope.insert_fn(
+            self.global_scope.borrow_mut().insert_fn(
                 name_as_ident,
                 SassFunction::UserDefined(Box::new(function), name_as_ident),
             );
@@ -81,6 +81,70 @@ impl<'a> Parser<'a> {
     }
 
     pub fn eval_function(&mut self, function: Function, args: CallArgs) -> SassResult<Value> {
+        self.enter_call(self.span_before)?;
+
+        let calling_module = self.enter_module(function.module);
+
+        let value = self.eval_function_in_module(function, args);
+
+        self.enter_module(calling_module);
+
+        self.exit_call();
+
+        value
+    }
+
+    /// Parses the parameters of a function passed to `Options::function`,
+    /// which are followed by a `{` as though they were part of a `@function`
+    /// rule
+    pub(crate) fn parse_custom_function_args(&mut self) -> SassResult<FuncArgs> {
+        let args = self.parse_func_args()?;
+
+        match self.toks.peek() {
+            Some(tok) => Err(("expected no more input.", tok.pos()).into()),
+            None => Ok(args),
+        }
+    }
+
+    /// Calls the function passed to `Options::function` at `index`
+    pub fn eval_custom_function(&mut self, index: usize, args: CallArgs) -> SassResult<Value> {
+        let declaration = &self.options.functions[index];
+        let span = args.span();
+
+        let fn_args = self.module_cache.custom_function_args(
+            self.map,
+            index,
+            &declaration.signature,
+            &declaration.args,
+        );
+
+        let names: Vec<Identifier> = fn_args.0.iter().map(|arg| arg.name).collect();
+
+        self.enter_call(self.span_before)?;
+        let scope = self.eval_args(fn_args, args);
+        self.exit_call();
+        let mut scope = scope?;
+
+        let mut values = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some(value) = scope.vars.remove(&name) {
+                values.push(value.inspect(span)?.into_owned());
+            }
+        }
+
+        let result = declaration
+            .function
+            .call(&values)
+            .map_err(|message| (message, span))?;
+
+        // the result has no file of its own, so errors inside of it are
+        // reported at the call site
+        let toks: Vec<Token> = result.chars().map(|c| Token::new(span, c)).collect();
+
+        Ok(self.parse_value_from_vec(toks, true)?.node)
+    }
+
+    fn eval_function_in_module(&mut self, function: Function, args: CallArgs) -> SassResult<Value> {
         let Function {
             body,
             args: fn_args,
@@ -120,6 +184,8 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_stmt()?;
 
@@ -1,4 +1,4 @@
-use std::{ffi::OsStr, fs, path::Path, path::PathBuf};
+use std::{ffi::OsStr, path::Path, path::PathBuf};
 
 use codemap::{Span, Spanned};
 use peekmore::PeekMore;
@@ -6,12 +6,23 @@ use peekmore::PeekMore;
 use crate::{
     common::{ListSeparator::Comma, QuoteKind},
     error::SassResult,
-    lexer::Lexer,
+    importer::ImporterResult,
     value::Value,
     Token,
 };
 
-use super::{Parser, Stmt};
+use super::{common::ContextFlags, node_modules::resolve_node_module, Parser, Stmt};
+
+/// Whether or not `path` is a plain CSS file, which is parsed without
+/// support for Sass-only syntax
+pub(crate) fn is_css_file(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("css"))
+}
+
+/// Whether or not `path` is written in the indented syntax
+pub(crate) fn is_indented_syntax_file(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("sass"))
+}
 
 fn is_plain_css_import(url: &str) -> bool {
     if url.len() < 5 {
@@ -24,13 +35,136 @@ fn is_plain_css_import(url: &str) -> bool {
         || url.starts_with("//")
 }
 
+/// Appends `extension` to the file name of `path`, e.g. `foo.bar` => `foo.bar.scss`
+fn with_appended_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// The existing files among `path` and its partial, `_`-prefixed, equivalent
+fn try_path(path: &Path) -> Vec<PathBuf> {
+    let partial = match path.file_name() {
+        Some(name) => {
+            let mut partial = OsStr::new("_").to_owned();
+            partial.push(name);
+            Some(path.with_file_name(partial))
+        }
+        None => None,
+    };
+
+    partial
+        .into_iter()
+        .chain(std::iter::once(path.to_path_buf()))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// The existing files among `path` with a Sass extension appended, or with a
+/// `.css` extension appended if there are no such Sass files
+fn try_path_with_extensions(path: &Path) -> Vec<PathBuf> {
+    let mut paths = try_path(&with_appended_extension(path, "sass"));
+    paths.append(&mut try_path(&with_appended_extension(path, "scss")));
+
+    if paths.is_empty() {
+        try_path(&with_appended_extension(path, "css"))
+    } else {
+        paths
+    }
+}
+
+fn exactly_one(mut paths: Vec<PathBuf>, span: Span) -> SassResult<Option<PathBuf>> {
+    if paths.len() > 1 {
+        return Err((
+            format!(
+                "It's not clear which file to import. Found:\n{}",
+                paths
+                    .iter()
+                    .map(|path| format!("  {}", path.to_string_lossy()))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            ),
+            span,
+        )
+            .into());
+    }
+
+    Ok(paths.pop())
+}
+
+/// Resolves `path` to the file it refers to, if one exists
+///
+/// If `for_import` is true, import-only files such as `foo.import.scss` are
+/// preferred over files without the `.import` infix.
+///
+/// <https://github.com/sass/sass/blob/main/spec/modules.md#resolving-a-file-url>
+pub(super) fn resolve_import_path(
+    path: &Path,
+    for_import: bool,
+    span: Span,
+) -> SassResult<Option<PathBuf>> {
+    if let Some(extension @ "sass") | Some(extension @ "scss") | Some(extension @ "css") =
+        path.extension().and_then(OsStr::to_str)
+    {
+        if for_import {
+            let import_only = with_appended_extension(&path.with_extension("import"), extension);
+
+            if let Some(path) = exactly_one(try_path(&import_only), span)? {
+                return Ok(Some(path));
+            }
+        }
+
+        return exactly_one(try_path(path), span);
+    }
+
+    if for_import {
+        let import_only = with_appended_extension(path, "import");
+
+        if let Some(path) = exactly_one(try_path_with_extensions(&import_only), span)? {
+            return Ok(Some(path));
+        }
+    }
+
+    if let Some(path) = exactly_one(try_path_with_extensions(path), span)? {
+        return Ok(Some(path));
+    }
+
+    if path.is_dir() {
+        if for_import {
+            let index = path.join("index.import");
+
+            if let Some(path) = exactly_one(try_path_with_extensions(&index), span)? {
+                return Ok(Some(path));
+            }
+        }
+
+        return exactly_one(try_path_with_extensions(&path.join("index")), span);
+    }
+
+    Ok(None)
+}
+
 impl<'a> Parser<'a> {
-    /// Searches the current directory of the file then searches in `load_paths` directories
-    /// if the import has not yet been found.
+    /// Searches relative to the directory of the current file, then asks each
+    /// of the importers, then searches relative to each of the `load_paths`
+    /// directories if the import has not yet been found
+    ///
+    /// For compatibility with earlier versions of grass, a file whose path
+    /// exactly matches the import is used if no other file is found.
+    ///
+    /// `for_import` should be true when resolving an `@import`, and false
+    /// when resolving a module.
     ///
     /// <https://sass-lang.com/documentation/at-rules/import#finding-the-file>
     /// <https://sass-lang.com/documentation/at-rules/import#load-paths>
-    pub(super) fn find_import(&self, path: &Path) -> Option<PathBuf> {
+    /// <https://sass-lang.com/documentation/at-rules/import#import-only-files>
+    pub(super) fn find_import(
+        &mut self,
+        path: &Path,
+        for_import: bool,
+        span: Span,
+    ) -> SassResult<Option<PathBuf>> {
         let path_buf = if path.is_absolute() {
             // todo: test for absolute path imports
             path.into()
@@ -41,51 +175,42 @@ impl<'a> Parser<'a> {
                 .join(path)
         };
 
-        let name = path_buf.file_name().unwrap_or_else(|| OsStr::new(".."));
-
-        let paths = [
-            path_buf.with_file_name(name).with_extension("scss"),
-            path_buf
-                .with_file_name(format!("_{}", name.to_str().unwrap()))
-                .with_extension("scss"),
-            path_buf.clone(),
-            path_buf.join("index.scss"),
-            path_buf.join("_index.scss"),
-        ];
-
-        for name in &paths {
-            if name.is_file() {
-                return Some(name.to_path_buf());
-            }
+        if let Some(path) = resolve_import_path(&path_buf, for_import, span)? {
+            return Ok(Some(path));
         }
 
-        for path in &self.options.load_paths {
-            let paths: Vec<PathBuf> = if path.is_dir() {
-                vec![
-                    path.join(format!("{}.scss", name.to_str().unwrap())),
-                    path.join(format!("_{}.scss", name.to_str().unwrap())),
-                    path.join("index.scss"),
-                    path.join("_index.scss"),
-                ]
-            } else {
-                vec![
-                    path.to_path_buf(),
-                    path.with_file_name(name).with_extension("scss"),
-                    path.with_file_name(format!("_{}", name.to_str().unwrap()))
-                        .with_extension("scss"),
-                    path.join("index.scss"),
-                    path.join("_index.scss"),
-                ]
-            };
+        let options = self.options;
 
-            for name in paths {
-                if name.is_file() {
-                    return Some(name);
+        for importer in &options.importers {
+            match importer.import(&path.to_string_lossy(), self.path) {
+                Ok(Some(ImporterResult { path, contents })) => {
+                    self.module_cache.add_imported(path.clone(), contents);
+                    return Ok(Some(path));
                 }
+                Ok(None) => {}
+                Err(e) => return Err((e.to_string(), span).into()),
             }
         }
 
-        None
+        for load_path in &self.options.load_paths {
+            if let Some(path) = resolve_import_path(&load_path.join(path), for_import, span)? {
+                return Ok(Some(path));
+            }
+        }
+
+        if self.options.node_modules {
+            let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+
+            if let Some(path) = resolve_node_module(dir, path, for_import, span)? {
+                return Ok(Some(path));
+            }
+        }
+
+        if path_buf.is_file() {
+            return Ok(Some(path_buf));
+        }
+
+        Ok(None)
     }
 
     pub(crate) fn parse_single_import(
@@ -95,16 +220,25 @@ impl<'a> Parser<'a> {
     ) -> SassResult<Vec<Stmt>> {
         let path: &Path = file_name.as_ref();
 
-        if let Some(name) = self.find_import(path) {
-            let file = self.map.add_file(
-                name.to_string_lossy().into(),
-                String::from_utf8(fs::read(&name)?)?,
-            );
+        if let Some(name) = self.find_import(path, true, span)? {
+            if is_indented_syntax_file(&name) {
+                return Err(("grass does not yet support the indented syntax.", span).into());
+            }
+
+            let flags = if is_css_file(&name) {
+                self.flags | ContextFlags::IN_PLAIN_CSS
+            } else {
+                self.flags
+            };
+
+            self.module_cache.add_loaded_url(&name);
+
+            let (file, toks) =
+                self.module_cache
+                    .load_file(self.map, &name, name.to_string_lossy().into())?;
+
             return Parser {
-                toks: &mut Lexer::new(&file)
-                    .collect::<Vec<Token>>()
-                    .into_iter()
-                    .peekmore(),
+                toks: &mut toks.into_iter().peekmore(),
                 map: self.map,
                 path: &name,
                 scopes: self.scopes,
@@ -112,7 +246,7 @@ impl<'a> Parser<'a> {
                 super_selectors: self.super_selectors,
                 span_before: file.span.subspan(0, 0),
                 content: self.content,
-                flags: self.flags,
+                flags,
                 at_root: self.at_root,
                 at_root_has_selector: self.at_root_has_selector,
                 extender: self.extender,
@@ -120,6 +254,8 @@ impl<'a> Parser<'a> {
                 options: self.options,
                 modules: self.modules,
                 module_config: self.module_config,
+                module_cache: self.module_cache,
+                media_queries: self.media_queries,
             }
             .parse();
         }
@@ -148,7 +284,7 @@ impl<'a> Parser<'a> {
 
         match file_name_as_value {
             Value::String(s, QuoteKind::Quoted) => {
-                if is_plain_css_import(&s) {
+                if is_plain_css_import(&s) || self.flags.in_plain_css() {
                     Ok(vec![Stmt::Import(format!("\"{}\"", s))])
                 } else {
                     self.parse_single_import(&s, span)
@@ -166,10 +302,7 @@ impl<'a> Parser<'a> {
                 for file_name_element in v {
                     match file_name_element {
                         Value::String(s, QuoteKind::Quoted) => {
-                            if s.ends_with(".css")
-                                || s.starts_with("http://")
-                                || s.starts_with("https://")
-                            {
+                            if is_plain_css_import(&s) || self.flags.in_plain_css() {
                                 list_of_imports.push(Stmt::Import(format!("\"{}\"", s)));
                             } else {
                                 list_of_imports.append(&mut self.parse_single_import(&s, span)?);
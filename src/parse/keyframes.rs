@@ -174,6 +174,8 @@ impl<'a> Parser<'a> {
                         options: self.options,
                         modules: self.modules,
                         module_config: self.module_config,
+                        module_cache: self.module_cache,
+                        media_queries: self.media_queries,
                     })
                     .parse_keyframes_selector()?;
 
@@ -211,6 +213,8 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_stmt()?;
 
@@ -1,10 +1,13 @@
+use std::mem;
+
 use crate::{
+    atrule::media::{MediaQuery, MediaQueryMergeResult, MediaRule},
     error::SassResult,
     utils::{is_name_start, peek_ident_no_interpolation},
     {Cow, Token},
 };
 
-use super::Parser;
+use super::{at_root::has_no_output, Parser, Stmt};
 
 impl<'a> Parser<'a> {
     /// Peeks to see if the `ident` is at the current position. If it is,
@@ -161,3 +164,97 @@ impl<'a> Parser<'a> {
         Ok(buf)
     }
 }
+
+impl MediaRule {
+    /// Moves any `@media` rules nested inside of this one to directly after it,
+    /// merging their queries with the query of this rule
+    ///
+    /// Nested rules whose queries can never match at the same time as this
+    /// rule are removed entirely, and those whose intersection with this rule
+    /// can't be represented in CSS are left in place.
+    pub(super) fn merge_nested_media_rules(mut self) -> Vec<Stmt> {
+        let mut merged = Vec::new();
+
+        let removed_any = take_mergeable_media_rules(&mut self.body, &self.query, &mut merged);
+
+        // rules that only contained other `@media` rules are removed entirely
+        if removed_any && has_no_output(&self.body) {
+            return merged;
+        }
+
+        let mut stmts = vec![Stmt::Media(Box::new(self))];
+        stmts.append(&mut merged);
+        stmts
+    }
+}
+
+/// Removes all `@media` rules from `body` whose queries can be merged with
+/// `query`, including those nested inside of style rules
+///
+/// Returns whether or not any rules were removed
+fn take_mergeable_media_rules(
+    body: &mut Vec<Stmt>,
+    query: &[MediaQuery],
+    merged: &mut Vec<Stmt>,
+) -> bool {
+    let mut removed_any = false;
+
+    for stmt in mem::take(body) {
+        match stmt {
+            Stmt::Media(mut media) => match merge_media_query_lists(query, &media.query) {
+                Some(merged_query) => {
+                    removed_any = true;
+
+                    if !merged_query.is_empty() {
+                        media.query = merged_query;
+                        merged.push(Stmt::Media(media));
+                    }
+                }
+                None => body.push(Stmt::Media(media)),
+            },
+            Stmt::RuleSet {
+                selector,
+                body: mut inner,
+            } => {
+                if take_mergeable_media_rules(&mut inner, query, merged) {
+                    removed_any = true;
+
+                    if has_no_output(&inner) {
+                        continue;
+                    }
+                }
+
+                body.push(Stmt::RuleSet {
+                    selector,
+                    body: inner,
+                });
+            }
+            stmt => body.push(stmt),
+        }
+    }
+
+    removed_any
+}
+
+/// Returns the queries matching the intersection of the query lists `outer`
+/// and `inner`, or `None` if that can't be represented in CSS
+///
+/// An empty list is returned if the two can never match at the same time.
+pub(super) fn merge_media_query_lists(
+    outer: &[MediaQuery],
+    inner: &[MediaQuery],
+) -> Option<Vec<MediaQuery>> {
+    let mut queries = Vec::new();
+
+    for query1 in outer {
+        for query2 in inner {
+            match query1.merge(query2) {
+                MediaQueryMergeResult::Empty => continue,
+                MediaQueryMergeResult::Unrepresentable => return None,
+                MediaQueryMergeResult::Success(query) => queries.push(query),
+            }
+        }
+    }
+
+    Some(queries)
+}
@@ -9,7 +9,7 @@ use crate::{
     atrule::mixin::{Content, Mixin, UserDefinedMixin},
     error::SassResult,
     scope::Scopes,
-    utils::read_until_closing_curly_brace,
+    utils::{read_mixin_body, read_until_closing_curly_brace},
     Token,
 };
 
@@ -43,23 +43,23 @@ impl<'a> Parser<'a> {
 
         self.whitespace();
 
-        let mut body = read_until_closing_curly_brace(self.toks)?;
+        let (mut body, accepts_content_block) = read_mixin_body(self.toks)?;
         body.push(match self.toks.next() {
             Some(tok) => tok,
             None => return Err(("expected \"}\".", self.span_before).into()),
         });
 
-        // todo: `@include` can only give content when `@content` is present within the body
-        // if `@content` is *not* present and `@include` attempts to give a body, we throw an error
-        // `Error: Mixin doesn't accept a content block.`
-        //
-        // this is blocked on figuring out just how to check for this. presumably we could have a check
-        // not when parsing initially, but rather when `@include`ing to see if an `@content` was found.
-
-        let mixin = Mixin::new_user_defined(args, body, false, self.at_root);
+        let mixin = Mixin::new_user_defined(
+            args,
+            body,
+            accepts_content_block,
+            self.at_root,
+            self.module_cache.current(),
+            span,
+        );
 
         if self.at_root {
-            self.global_scope.insert_mixin(name, mixin);
+            self.global_scope.borrow_mut().insert_mixin(name, mixin);
         } else {
             self.scopes.insert_mixin(name.into(), mixin);
         }
@@ -84,7 +84,7 @@ impl<'a> Parser<'a> {
                 .get(module.node, module.span)?
                 .get_mixin(name)?
         } else {
-            self.scopes.get_mixin(name, self.global_scope)?
+            self.scopes.get_mixin(name, &self.global_scope.borrow())?
         };
 
         self.whitespace_or_comment();
@@ -137,17 +137,74 @@ impl<'a> Parser<'a> {
             self.toks.next();
         }
 
+        let rejects_content = content.is_some() && !mixin.accepts_content();
+
+        let body = self.run_mixin(mixin, args, content, content_args)?;
+
+        // this is checked once the mixin has run, so that errors Sass reports
+        // while parsing its body, such as nested declarations, come first
+        if rejects_content {
+            return Err(("Mixin doesn't accept a content block.", name.span).into());
+        }
+
+        Ok(body)
+    }
+
+    /// Include an already resolved mixin, e.g. through `@include` or `meta.apply()`
+    pub(crate) fn run_mixin(
+        &mut self,
+        mixin: Mixin,
+        args: CallArgs,
+        content: Option<Vec<Token>>,
+        content_args: Option<FuncArgs>,
+    ) -> SassResult<Vec<Stmt>> {
+        let mixin = match mixin {
+            Mixin::UserDefined(u) => u,
+            Mixin::Builtin(b, ..) => {
+                // builtin mixins such as `meta.apply()` may forward their content block
+                self.content.push(Content {
+                    content,
+                    content_args,
+                    scope_len: self.scopes.len(),
+                    declared_at_root: false,
+                    module: self.module_cache.current(),
+                });
+
+                let stmts = b(args, self);
+
+                self.content.pop();
+
+                return stmts;
+            }
+        };
+
+        self.enter_call(self.span_before)?;
+
+        let calling_module = self.enter_module(mixin.module);
+
+        let body = self.run_user_defined_mixin(mixin, args, content, content_args, calling_module);
+
+        self.enter_module(calling_module);
+
+        self.exit_call();
+
+        body
+    }
+
+    fn run_user_defined_mixin(
+        &mut self,
+        mixin: UserDefinedMixin,
+        args: CallArgs,
+        content: Option<Vec<Token>>,
+        content_args: Option<FuncArgs>,
+        calling_module: Option<usize>,
+    ) -> SassResult<Vec<Stmt>> {
         let UserDefinedMixin {
             body,
             args: fn_args,
             declared_at_root,
             ..
-        } = match mixin {
-            Mixin::UserDefined(u) => u,
-            Mixin::Builtin(b) => {
-                return b(args, self);
-            }
-        };
+        } = mixin;
 
         let scope = self.eval_args(fn_args, args)?;
 
@@ -164,6 +221,7 @@ impl<'a> Parser<'a> {
             content_args,
             scope_len,
             declared_at_root,
+            module: calling_module,
         });
 
         let body = Parser {
@@ -183,6 +241,8 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_stmt()?;
 
@@ -222,6 +282,8 @@ impl<'a> Parser<'a> {
                 CallArgs::new(self.span_before)
             };
 
+            let mixin_module = self.enter_module(content.module);
+
             if let Some(ref content_args) = content.content_args {
                 call_args.max_args(content_args.len())?;
 
@@ -250,12 +312,16 @@ impl<'a> Parser<'a> {
                     options: self.options,
                     modules: self.modules,
                     module_config: self.module_config,
+                    module_cache: self.module_cache,
+                    media_queries: self.media_queries,
                 }
                 .parse_stmt()?
             } else {
                 Vec::new()
             };
 
+            self.enter_module(mixin_module);
+
             if entered_scope {
                 scope_at_decl.exit_scope();
             }
@@ -1,4 +1,4 @@
-use std::{convert::TryFrom, path::Path, vec::IntoIter};
+use std::{convert::TryFrom, mem, path::Path, vec::IntoIter};
 
 use codemap::{CodeMap, Span, Spanned};
 use peekmore::{PeekMore, PeekMoreIterator};
@@ -6,13 +6,13 @@ use peekmore::{PeekMore, PeekMoreIterator};
 use crate::{
     atrule::{
         keyframes::{Keyframes, KeyframesRuleSet},
-        media::MediaRule,
+        media::{MediaQuery, MediaRule},
         mixin::Content,
-        AtRuleKind, SupportsRule, UnknownAtRule,
+        AtRootQuery, AtRuleKind, SupportsRule, UnknownAtRule,
     },
-    builtin::modules::{ModuleConfig, Modules},
+    builtin::modules::{ModuleCache, ModuleConfig, Modules},
     error::SassResult,
-    scope::{Scope, Scopes},
+    scope::{Scopes, SharedScope},
     selector::{
         ComplexSelectorComponent, ExtendRule, ExtendedSelector, Extender, Selector, SelectorParser,
     },
@@ -23,10 +23,13 @@ use crate::{
 };
 
 use common::{Comment, ContextFlags, NeverEmptyVec, SelectorOrStyle};
+pub(crate) use import::{is_css_file, is_indented_syntax_file};
+use media::merge_media_query_lists;
 pub(crate) use value::{HigherIntermediateValue, ValueVisitor};
 use variable::VariableValue;
 
 mod args;
+mod at_root;
 pub mod common;
 mod control_flow;
 mod function;
@@ -36,6 +39,7 @@ mod keyframes;
 mod media;
 mod mixin;
 mod module;
+mod node_modules;
 mod style;
 mod throw_away;
 mod value;
@@ -53,6 +57,7 @@ pub(crate) enum Stmt {
     Supports(Box<SupportsRule>),
     AtRoot {
         body: Vec<Stmt>,
+        query: AtRootQuery,
     },
     Comment(String),
     Return(Box<Value>),
@@ -71,7 +76,7 @@ pub(crate) struct Parser<'a> {
     pub toks: &'a mut PeekMoreIterator<IntoIter<Token>>,
     pub map: &'a mut CodeMap,
     pub path: &'a Path,
-    pub global_scope: &'a mut Scope,
+    pub global_scope: &'a mut SharedScope,
     pub scopes: &'a mut Scopes,
     pub content_scopes: &'a mut Scopes,
     pub super_selectors: &'a mut NeverEmptyVec<Selector>,
@@ -86,10 +91,15 @@ pub(crate) struct Parser<'a> {
     pub at_root_has_selector: bool,
     pub extender: &'a mut Extender,
 
-    pub options: &'a Options<'a>,
+    pub options: &'a Options,
 
     pub modules: &'a mut Modules,
     pub module_config: &'a mut ModuleConfig,
+    pub module_cache: &'a mut ModuleCache,
+
+    /// The queries of the `@media` rules this parser is inside of, merged
+    /// together, or `None` if it is not inside of any
+    pub media_queries: &'a mut Option<Vec<MediaQuery>>,
 }
 
 impl<'a> Parser<'a> {
@@ -100,7 +110,10 @@ impl<'a> Parser<'a> {
         self.consume_char_if_exists('\u{feff}');
 
         self.whitespace();
-        stmts.append(&mut self.load_modules()?);
+
+        if !self.flags.in_plain_css() {
+            stmts.append(&mut self.load_modules()?);
+        }
 
         while self.toks.peek().is_some() {
             stmts.append(&mut self.parse_stmt()?);
@@ -112,6 +125,20 @@ impl<'a> Parser<'a> {
         Ok(stmts)
     }
 
+    /// Parses the entrypoint of a compilation, checking that every `@extend`
+    /// it contains was satisfied once the whole document has been parsed
+    pub fn parse_entrypoint(&mut self) -> SassResult<Vec<Stmt>> {
+        let stmts = self.parse()?;
+
+        self.extender.check_unsatisfied_extensions(
+            &self
+                .module_cache
+                .upstream_extenders(self.module_cache.css_module()),
+        )?;
+
+        Ok(stmts)
+    }
+
     pub fn expect_char(&mut self, c: char) -> SassResult<()> {
         match self.toks.peek() {
             Some(Token { kind, pos }) if *kind == c => {
@@ -156,7 +183,15 @@ impl<'a> Parser<'a> {
                     self.toks.next();
                     let kind_string = self.parse_identifier()?;
                     self.span_before = kind_string.span;
-                    match AtRuleKind::try_from(&kind_string)? {
+                    let kind = AtRuleKind::try_from(&kind_string)?;
+
+                    if self.flags.in_plain_css() && kind.is_sass_only() {
+                        return Err(
+                            ("This at-rule isn't allowed in plain CSS.", kind_string.span).into(),
+                        );
+                    }
+
+                    match kind {
                         AtRuleKind::Import => stmts.append(&mut self.import()?),
                         AtRuleKind::Mixin => self.parse_mixin()?,
                         AtRuleKind::Content => stmts.append(&mut self.parse_content_rule()?),
@@ -182,12 +217,12 @@ impl<'a> Parser<'a> {
                                     .into());
                             }
 
-                            if self.at_root {
-                                stmts.append(&mut self.parse_at_root()?);
+                            let (query, mut body) = self.parse_at_root()?;
+
+                            if self.at_root && query.is_default() {
+                                stmts.append(&mut body);
                             } else {
-                                stmts.push(Stmt::AtRoot {
-                                    body: self.parse_at_root()?,
-                                });
+                                stmts.push(Stmt::AtRoot { body, query });
                             }
                         }
                         AtRuleKind::Error => {
@@ -251,10 +286,16 @@ impl<'a> Parser<'a> {
                             }
                             continue;
                         }
-                        AtRuleKind::Media => stmts.push(self.parse_media()?),
-                        AtRuleKind::Unknown(_) => {
-                            stmts.push(self.parse_unknown_at_rule(kind_string.node)?)
+                        AtRuleKind::Media => {
+                            for media in self.parse_media()? {
+                                stmts.append(&mut media.hoist_at_root_rules());
+                            }
                         }
+                        AtRuleKind::Unknown(_) => stmts.append(
+                            &mut self
+                                .parse_unknown_at_rule(kind_string.node)?
+                                .hoist_at_root_rules(),
+                        ),
                         AtRuleKind::Use => {
                             return Err((
                                 "@use rules must be written before any other rules.",
@@ -264,13 +305,21 @@ impl<'a> Parser<'a> {
                         }
                         AtRuleKind::Forward => todo!("@forward not yet implemented"),
                         AtRuleKind::Extend => self.parse_extend()?,
-                        AtRuleKind::Supports => stmts.push(self.parse_supports()?),
+                        AtRuleKind::Supports => {
+                            stmts.append(&mut self.parse_supports()?.hoist_at_root_rules())
+                        }
                         AtRuleKind::Keyframes => {
                             stmts.push(self.parse_keyframes(kind_string.node)?)
                         }
                     }
                 }
-                '$' => self.parse_variable_declaration()?,
+                '$' => {
+                    if self.flags.in_plain_css() {
+                        return Err(("Sass variables aren't allowed in plain CSS.", *pos).into());
+                    }
+
+                    self.parse_variable_declaration()?
+                }
                 '\t' | '\n' | ' ' | ';' => {
                     self.toks.next();
                     continue;
@@ -280,6 +329,13 @@ impl<'a> Parser<'a> {
                     let comment = self.parse_comment()?;
                     self.whitespace();
                     match comment.node {
+                        Comment::Silent if self.flags.in_plain_css() => {
+                            return Err((
+                                "Silent comments aren't allowed in plain CSS.",
+                                comment.span,
+                            )
+                                .into());
+                        }
                         Comment::Silent => continue,
                         Comment::Loud(s) => {
                             if !self.flags.in_function() {
@@ -352,6 +408,15 @@ impl<'a> Parser<'a> {
                             }
                         }
                         SelectorOrStyle::Selector(init) => {
+                            if self.flags.in_plain_css() && !self.super_selectors.last().is_empty()
+                            {
+                                return Err((
+                                    "Nested rules aren't allowed in plain CSS.",
+                                    self.span_before,
+                                )
+                                    .into());
+                            }
+
                             let at_root = self.at_root;
                             self.at_root = false;
                             let selector = self
@@ -364,7 +429,9 @@ impl<'a> Parser<'a> {
                             self.scopes.enter_new_scope();
                             self.super_selectors.push(selector.clone());
 
-                            let extended_selector = self.extender.add_selector(selector.0, None);
+                            let extended_selector = self
+                                .extender
+                                .add_selector(selector.0, self.media_queries.clone())?;
 
                             let body = self.parse_stmt()?;
                             self.scopes.exit_scope();
@@ -471,6 +538,8 @@ impl<'a> Parser<'a> {
                 options: self.options,
                 modules: self.modules,
                 module_config: self.module_config,
+                module_cache: self.module_cache,
+                media_queries: self.media_queries,
             },
             allows_parent,
             true,
@@ -528,6 +597,14 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_interpolation(&mut self) -> SassResult<Spanned<Value>> {
+        if self.flags.in_plain_css() {
+            return Err((
+                "Interpolation isn't allowed in plain CSS.",
+                self.span_before,
+            )
+                .into());
+        }
+
         let val = self.parse_value(true, &|_| false)?;
 
         self.span_before = val.span;
@@ -679,18 +756,32 @@ impl<'a> Parser<'a> {
         })))
     }
 
-    fn parse_media(&mut self) -> SassResult<Stmt> {
+    fn parse_media(&mut self) -> SassResult<Vec<Stmt>> {
         if self.flags.in_function() {
             return Err(("This at-rule is not allowed here.", self.span_before).into());
         }
 
+        let query_span = self.toks.peek().map_or(self.span_before, |t| t.pos);
         let query = self.parse_media_query_list()?;
+        let query = MediaQuery::parse_list(&query).map_err(|e| (e, query_span))?;
 
         self.whitespace();
 
         self.expect_char('{')?;
 
-        let raw_body = self.parse_stmt()?;
+        // selectors inside of this rule may only be extended from within
+        // the same media context
+        let media_queries = match self.media_queries.as_deref() {
+            Some(outer) => merge_media_query_lists(outer, &query).unwrap_or_else(|| query.clone()),
+            None => query.clone(),
+        };
+        let outer_media_queries = mem::replace(self.media_queries, Some(media_queries));
+
+        let raw_body = self.parse_stmt();
+
+        *self.media_queries = outer_media_queries;
+
+        let raw_body = raw_body?;
 
         let mut rules = Vec::with_capacity(raw_body.len());
         let mut body = Vec::new();
@@ -711,17 +802,33 @@ impl<'a> Parser<'a> {
 
         body.append(&mut rules);
 
-        Ok(Stmt::Media(Box::new(MediaRule {
+        Ok(MediaRule {
             super_selector: Selector::new(self.span_before),
             query,
             body,
-        })))
+        }
+        .merge_nested_media_rules())
     }
 
-    fn parse_at_root(&mut self) -> SassResult<Vec<Stmt>> {
+    fn parse_at_root(&mut self) -> SassResult<(AtRootQuery, Vec<Stmt>)> {
         self.whitespace();
+
+        let has_query = matches!(self.toks.peek(), Some(Token { kind: '(', .. }));
+
+        let query = if has_query {
+            let query = self.parse_at_root_query()?;
+            self.expect_char('{')?;
+            query
+        } else {
+            AtRootQuery::default()
+        };
+
+        if !query.excludes_style_rules() {
+            return Ok((query, self.parse_at_root_in_style_rule()?));
+        }
+
         let mut at_root_has_selector = false;
-        let at_rule_selector = if self.consume_char_if_exists('{') {
+        let at_rule_selector = if has_query || self.consume_char_if_exists('{') {
             self.super_selectors.last().clone()
         } else {
             at_root_has_selector = true;
@@ -750,10 +857,21 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_stmt()?
         .into_iter()
         .filter_map(|s| match s {
+            // declarations may only be kept in the enclosing style rule when the
+            // query is the default one; an explicit query moves them out of it
+            Stmt::Style(style) if !query.is_default() && !at_rule_selector.is_empty() => {
+                Some(Err((
+                    "Declarations may only be used within style rules.",
+                    style.value.span,
+                )
+                    .into()))
+            }
             Stmt::Style(..) => {
                 styles.push(s);
                 None
@@ -766,7 +884,35 @@ impl<'a> Parser<'a> {
             body: styles,
         }];
         stmts.extend(raw_stmts);
-        Ok(stmts)
+        Ok((query, stmts))
+    }
+
+    /// The body of an `@at-root` rule whose query does not exclude style rules,
+    /// e.g. `@at-root (without: media)`, is evaluated as if it were still
+    /// inside of the enclosing style rule
+    fn parse_at_root_in_style_rule(&mut self) -> SassResult<Vec<Stmt>> {
+        let raw_body = self.parse_stmt()?;
+
+        let mut rules = Vec::with_capacity(raw_body.len());
+        let mut body = Vec::new();
+
+        for stmt in raw_body {
+            match stmt {
+                Stmt::Style(..) => body.push(stmt),
+                _ => rules.push(stmt),
+            }
+        }
+
+        if !self.super_selectors.last().is_empty() {
+            body = vec![Stmt::RuleSet {
+                selector: ExtendedSelector::new(self.super_selectors.last().clone().0),
+                body,
+            }];
+        }
+
+        body.append(&mut rules);
+
+        Ok(body)
     }
 
     fn parse_extend(&mut self) -> SassResult<()> {
@@ -796,6 +942,8 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_selector(false, true, String::new())?;
 
@@ -828,13 +976,29 @@ impl<'a> Parser<'a> {
                 , self.span_before).into());
             }
 
+            let target = compound.components.first().unwrap();
+
             self.extender.add_extension(
                 super_selector.clone().0,
-                compound.components.first().unwrap(),
+                target,
                 &extend_rule,
-                &None,
+                self.media_queries,
                 self.span_before,
-            )
+            )?;
+
+            // extensions also apply to the CSS of every module this module
+            // depends on, but never to modules that depend on it
+            let css_module = self.module_cache.css_module();
+
+            for extender in self.module_cache.upstream_extenders_mut(css_module) {
+                extender.add_extension(
+                    super_selector.clone().0,
+                    target,
+                    &extend_rule,
+                    self.media_queries,
+                    self.span_before,
+                )?;
+            }
         }
 
         Ok(())
@@ -915,25 +1079,64 @@ impl<'a> Parser<'a> {
             return;
         }
         let loc = self.map.look_up_span(message.span);
-        eprintln!(
+        let message = format!(
             "{}:{} Debug: {}",
             loc.file.name(),
             loc.begin.line + 1,
             message.node
         );
+        self.options.logger.debug(&message);
     }
 
-    fn warn(&self, message: &Spanned<Cow<'a, str>>) {
+    fn warn(&mut self, message: &Spanned<Cow<'a, str>>) {
         if self.options.quiet {
             return;
         }
         let loc = self.map.look_up_span(message.span);
-        eprintln!(
-            "Warning: {}\n    {} {}:{}  root stylesheet",
-            message.node,
-            loc.file.name(),
-            loc.begin.line + 1,
-            loc.begin.column + 1
+        self.module_cache.emit_warning(
+            format!(
+                "Warning: {}\n    {} {}:{}  root stylesheet",
+                message.node,
+                loc.file.name(),
+                loc.begin.line + 1,
+                loc.begin.column + 1
+            ),
+            &*self.options.logger,
         );
     }
+
+    /// Records that a mixin or function is about to be evaluated, emitting an
+    /// error if this exceeds `Options::max_call_depth`
+    ///
+    /// Every successful call must be paired with a call to `exit_call`.
+    pub(crate) fn enter_call(&mut self, span: Span) -> SassResult<()> {
+        let depth = self.module_cache.enter_call();
+
+        match self.options.max_call_depth {
+            Some(max) if depth > max => {
+                self.module_cache.exit_call();
+                Err((format!("Maximum call depth ({}) exceeded.", max), span).into())
+            }
+            Some(..) | None => Ok(()),
+        }
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.module_cache.exit_call();
+    }
+
+    /// Records a single iteration of the loop at `span`, emitting an error if
+    /// the total number of iterations exceeds `Options::max_loop_iterations`
+    fn add_loop_iteration(&mut self, span: Span) -> SassResult<()> {
+        let iterations = self.module_cache.add_loop_iteration();
+
+        match self.options.max_loop_iterations {
+            Some(max) if iterations > max => Err((
+                format!("Maximum number of loop iterations ({}) exceeded.", max),
+                span,
+            )
+                .into()),
+            Some(..) | None => Ok(()),
+        }
+    }
 }
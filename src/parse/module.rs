@@ -1,4 +1,4 @@
-use std::{convert::TryFrom, fs};
+use std::{cell::RefCell, convert::TryFrom, fs, mem, rc::Rc};
 
 use codemap::Spanned;
 use peekmore::PeekMore;
@@ -8,12 +8,16 @@ use crate::{
     builtin::modules::{
         declare_module_color, declare_module_list, declare_module_map, declare_module_math,
         declare_module_meta, declare_module_selector, declare_module_string, Module, ModuleConfig,
+        ModuleEnvironment, Modules, SharedModule,
     },
     common::Identifier,
     error::SassResult,
-    lexer::Lexer,
-    parse::{common::Comment, Parser, Stmt, VariableValue},
-    scope::Scope,
+    parse::{
+        common::{Comment, ContextFlags, NeverEmptyVec},
+        is_css_file, is_indented_syntax_file, Parser, Stmt, VariableValue,
+    },
+    scope::{Scopes, SharedScope},
+    selector::{Extender, Selector},
     utils::peek_ident_no_interpolation,
     Token,
 };
@@ -96,45 +100,105 @@ impl<'a> Parser<'a> {
         &mut self,
         name: &str,
         config: &mut ModuleConfig,
-    ) -> SassResult<(Module, Vec<Stmt>)> {
+    ) -> SassResult<(SharedModule, Vec<Stmt>)> {
         Ok(match name {
-            "sass:color" => (declare_module_color(), Vec::new()),
-            "sass:list" => (declare_module_list(), Vec::new()),
-            "sass:map" => (declare_module_map(), Vec::new()),
-            "sass:math" => (declare_module_math(), Vec::new()),
-            "sass:meta" => (declare_module_meta(), Vec::new()),
-            "sass:selector" => (declare_module_selector(), Vec::new()),
-            "sass:string" => (declare_module_string(), Vec::new()),
+            "sass:color" => builtin_module(declare_module_color()),
+            "sass:list" => builtin_module(declare_module_list()),
+            "sass:map" => builtin_module(declare_module_map()),
+            "sass:math" => builtin_module(declare_module_math()),
+            "sass:meta" => builtin_module(declare_module_meta()),
+            "sass:selector" => builtin_module(declare_module_selector()),
+            "sass:string" => builtin_module(declare_module_string()),
             _ => {
-                if let Some(import) = self.find_import(name.as_ref()) {
-                    let mut global_scope = Scope::new();
+                if let Some(import) = self.find_import(name.as_ref(), false, self.span_before)? {
+                    if is_indented_syntax_file(&import) {
+                        return Err((
+                            "grass does not yet support the indented syntax.",
+                            self.span_before,
+                        )
+                            .into());
+                    }
+
+                    let canonical_path =
+                        fs::canonicalize(&import).unwrap_or_else(|_| import.clone());
+
+                    let loading_module = self.module_cache.css_module();
+
+                    if let Some((id, module)) = self.module_cache.get(&canonical_path) {
+                        if !config.is_empty() {
+                            return Err((
+                                "This module was already loaded, so it can't be configured using \"with\".",
+                                self.span_before,
+                            )
+                                .into());
+                        }
+
+                        self.module_cache.add_upstream(loading_module, id);
+
+                        return Ok((module, Vec::new()));
+                    }
+
+                    if !self.module_cache.start_loading(&canonical_path) {
+                        return Err((
+                            "Module loop: this module is already being loaded.",
+                            self.span_before,
+                        )
+                            .into());
+                    }
+
+                    self.module_cache.add_loaded_url(&canonical_path);
+
+                    let mut global_scope = SharedScope::default();
+                    let mut modules = Modules::default();
 
-                    let file = self
-                        .map
-                        .add_file(name.to_owned(), String::from_utf8(fs::read(&import)?)?);
+                    let (file, toks) =
+                        self.module_cache
+                            .load_file(self.map, &import, name.to_owned())?;
 
+                    let empty_span = file.span.subspan(0, 0);
+
+                    let mut extender = Extender::new(empty_span);
+
+                    let id = self.module_cache.new_module_id();
+                    let calling_module = self.module_cache.current();
+
+                    self.module_cache.add_upstream(loading_module, id);
+                    self.module_cache.set_current(Some(id));
+                    self.module_cache.set_css_module(Some(id));
+
+                    // modules are evaluated in a fresh environment, and only share
+                    // their public members with the module that loads them
                     let stmts = Parser {
-                        toks: &mut Lexer::new(&file)
-                            .collect::<Vec<Token>>()
-                            .into_iter()
-                            .peekmore(),
+                        toks: &mut toks.into_iter().peekmore(),
                         map: self.map,
                         path: &import,
-                        scopes: self.scopes,
+                        scopes: &mut Scopes::new(),
                         global_scope: &mut global_scope,
-                        super_selectors: self.super_selectors,
-                        span_before: file.span.subspan(0, 0),
-                        content: self.content,
-                        flags: self.flags,
-                        at_root: self.at_root,
-                        at_root_has_selector: self.at_root_has_selector,
-                        extender: self.extender,
-                        content_scopes: self.content_scopes,
+                        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
+                        span_before: empty_span,
+                        content: &mut Vec::new(),
+                        flags: if is_css_file(&import) {
+                            ContextFlags::empty() | ContextFlags::IN_PLAIN_CSS
+                        } else {
+                            ContextFlags::empty()
+                        },
+                        at_root: true,
+                        at_root_has_selector: false,
+                        extender: &mut extender,
+                        content_scopes: &mut Scopes::new(),
                         options: self.options,
-                        modules: self.modules,
+                        modules: &mut modules,
                         module_config: config,
+                        module_cache: self.module_cache,
+                        media_queries: &mut None,
                     }
-                    .parse()?;
+                    .parse();
+
+                    self.module_cache.set_current(calling_module);
+                    self.module_cache.set_css_module(loading_module);
+                    self.module_cache.finish_loading(&canonical_path);
+
+                    let stmts = stmts?;
 
                     if !config.is_empty() {
                         return Err((
@@ -144,7 +208,27 @@ impl<'a> Parser<'a> {
                             .into());
                     }
 
-                    (Module::new_from_scope(global_scope, false), stmts)
+                    extender.check_unsatisfied_extensions(
+                        &self.module_cache.upstream_extenders(Some(id)),
+                    )?;
+
+                    let module = Rc::new(RefCell::new(Module::new_from_scope(
+                        Rc::clone(&global_scope),
+                        id,
+                    )));
+
+                    self.module_cache
+                        .insert(canonical_path, id, Rc::clone(&module));
+                    self.module_cache.store_extender(id, extender);
+                    self.module_cache.store(
+                        Some(id),
+                        ModuleEnvironment {
+                            scope: global_scope,
+                            modules,
+                        },
+                    );
+
+                    (module, stmts)
                 } else {
                     return Err(("Can't find stylesheet to import.", self.span_before).into());
                 }
@@ -152,6 +236,38 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Makes the module `id`, or the entrypoint if `id` is `None`, the module
+    /// currently being evaluated
+    ///
+    /// Returns the id of the module that was previously being evaluated, so
+    /// that it may be restored afterwards. Mixins and functions are always
+    /// evaluated in the module they were declared in, regardless of where
+    /// they are called from.
+    pub(crate) fn enter_module(&mut self, id: Option<usize>) -> Option<usize> {
+        let previous = self.module_cache.current();
+
+        if id == previous {
+            return previous;
+        }
+
+        self.module_cache.store(
+            previous,
+            ModuleEnvironment {
+                scope: mem::take(self.global_scope),
+                modules: mem::take(self.modules),
+            },
+        );
+
+        let ModuleEnvironment { scope, modules } = self.module_cache.take(id);
+
+        *self.global_scope = scope;
+        *self.modules = modules;
+
+        self.module_cache.set_current(id);
+
+        previous
+    }
+
     /// Returns any multiline comments that may have been found
     /// while loading modules
     pub(super) fn load_modules(&mut self) -> SassResult<Vec<Stmt>> {
@@ -218,7 +334,9 @@ impl<'a> Parser<'a> {
 
                     let module_name = match module_alias.as_deref() {
                         Some("*") => {
-                            self.global_scope.merge_module(module);
+                            self.global_scope
+                                .borrow_mut()
+                                .merge_module(&module.borrow());
                             continue;
                         }
                         Some(..) => module_alias.unwrap(),
@@ -286,8 +404,12 @@ impl<'a> Parser<'a> {
 
         self.modules
             .get_mut(module, variable.span)?
-            .update_var(variable, value.node)?;
-
-        Ok(())
+            .update_var(variable, value.node)
     }
 }
+
+/// Builtin modules are created anew each time they are loaded, so they are
+/// never actually shared
+fn builtin_module(module: Module) -> (SharedModule, Vec<Stmt>) {
+    (Rc::new(RefCell::new(module)), Vec::new())
+}
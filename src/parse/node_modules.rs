@@ -0,0 +1,190 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use codemap::Span;
+
+use crate::error::SassResult;
+
+use super::import::resolve_import_path;
+
+/// Resolves a bare import such as `bootstrap/scss/bootstrap` by walking up from
+/// `dir` looking for a `node_modules` directory containing the package
+///
+/// If the import is only the name of the package, the file named by the
+/// `sass` or `style` entry of its `package.json` is used, falling back to an
+/// index file inside of the package.
+pub(super) fn resolve_node_module(
+    dir: &Path,
+    url: &Path,
+    for_import: bool,
+    span: Span,
+) -> SassResult<Option<PathBuf>> {
+    let package_name = match package_name(url) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let dir = fs::canonicalize(if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    })
+    .unwrap_or_else(|_| dir.to_path_buf());
+
+    for ancestor in dir.ancestors() {
+        let package_dir = ancestor.join("node_modules").join(&package_name);
+
+        if !package_dir.is_dir() {
+            continue;
+        }
+
+        if url != package_name {
+            return resolve_import_path(&ancestor.join("node_modules").join(url), for_import, span);
+        }
+
+        if let Some(entry) = package_entry(&package_dir) {
+            if let Some(path) = resolve_import_path(&package_dir.join(entry), for_import, span)? {
+                return Ok(Some(path));
+            }
+        }
+
+        return resolve_import_path(&package_dir, for_import, span);
+    }
+
+    Ok(None)
+}
+
+/// The name of the package a bare import refers to, e.g. `bootstrap` for
+/// `bootstrap/scss/bootstrap` or `@scope/name` for `@scope/name/foo`
+///
+/// Returns `None` for relative and absolute imports.
+fn package_name(url: &Path) -> Option<PathBuf> {
+    let mut components = url.components();
+
+    let first = match components.next() {
+        Some(Component::Normal(first)) => first,
+        Some(..) | None => return None,
+    };
+
+    if first.to_string_lossy().starts_with('@') {
+        match components.next() {
+            Some(Component::Normal(second)) => Some(Path::new(first).join(second)),
+            Some(..) | None => None,
+        }
+    } else {
+        Some(PathBuf::from(first))
+    }
+}
+
+/// The stylesheet named by the `sass` or `style` field of the `package.json`
+/// in `package_dir`, if any
+fn package_entry(package_dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(package_dir.join("package.json")).ok()?;
+    let fields = top_level_string_fields(&contents)?;
+
+    ["sass", "style"].iter().find_map(|name| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+/// The keys and values of all string-valued fields of the JSON object in
+/// `json`, ignoring nested values
+///
+/// Returns `None` if `json` is not a well-formed object.
+fn top_level_string_fields(json: &str) -> Option<Vec<(String, String)>> {
+    let mut chars = json.chars().peekable();
+    let mut fields = Vec::new();
+
+    skip_json_whitespace(&mut chars);
+
+    if chars.next()? != '{' {
+        return None;
+    }
+
+    loop {
+        skip_json_whitespace(&mut chars);
+
+        match chars.next()? {
+            '}' => return Some(fields),
+            ',' => continue,
+            '"' => {}
+            _ => return None,
+        }
+
+        let key = parse_json_string(&mut chars)?;
+
+        skip_json_whitespace(&mut chars);
+
+        if chars.next()? != ':' {
+            return None;
+        }
+
+        skip_json_whitespace(&mut chars);
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            fields.push((key, parse_json_string(&mut chars)?));
+        } else {
+            skip_json_value(&mut chars)?;
+        }
+    }
+}
+
+fn skip_json_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = chars.peek() {
+        chars.next();
+    }
+}
+
+/// Parses the remainder of a JSON string, assuming the opening quote has
+/// already been consumed
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut string = String::new();
+
+    loop {
+        match chars.next()? {
+            '"' => return Some(string),
+            '\\' => match chars.next()? {
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                'r' => string.push('\r'),
+                'b' => string.push('\u{8}'),
+                'f' => string.push('\u{c}'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    string.push(std::char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => string.push(c),
+            },
+            c => string.push(c),
+        }
+    }
+}
+
+/// Skips a JSON value that is not a string, including any nested objects and
+/// arrays
+fn skip_json_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<()> {
+    let mut depth = 0_usize;
+
+    loop {
+        match chars.peek()? {
+            '{' | '[' => depth += 1,
+            '}' | ']' if depth == 0 => return Some(()),
+            '}' | ']' => depth -= 1,
+            ',' if depth == 0 => return Some(()),
+            '"' => {
+                chars.next();
+                parse_json_string(chars)?;
+                continue;
+            }
+            _ => {}
+        }
+
+        chars.next();
+    }
+}
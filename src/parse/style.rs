@@ -203,6 +203,11 @@ impl<'a> Parser<'a> {
         self.whitespace();
         while let Some(tok) = self.toks.peek().cloned() {
             match tok.kind {
+                '{' if self.flags.in_plain_css() => {
+                    return Err(
+                        ("Nested declarations aren't allowed in plain CSS.", tok.pos).into(),
+                    );
+                }
                 '{' => {
                     self.toks.next();
                     self.whitespace();
@@ -0,0 +1,659 @@
+//! Parsing and simplification of `calc()`, `min()`, `max()` and `clamp()`
+//!
+//! Calculations are parsed in two passes: first the arguments are parsed into
+//! a tree of unevaluated values, and only once the entire argument list has
+//! been successfully parsed are they evaluated and simplified. This allows
+//! `min()` and `max()` to fall back to the Sass functions of the same name
+//! when their arguments aren't valid calculation syntax.
+
+use num_traits::Signed;
+
+use codemap::{Span, Spanned};
+
+use crate::{
+    common::{Op, QuoteKind},
+    error::SassResult,
+    unit::{Unit, UnitKind},
+    utils::is_name_start,
+    value::{CalculationArg, CalculationName, Number, SassCalculation, Value},
+    Token,
+};
+
+use super::{
+    super::Parser,
+    eval::{HigherIntermediateValue, ValueVisitor},
+    parse::IntermediateValue,
+};
+
+/// A calculation argument that has been parsed, but not yet evaluated
+#[derive(Debug, Clone)]
+enum CalculationExpr {
+    Value(Spanned<HigherIntermediateValue>),
+    Operation(Box<CalculationExpr>, Op, Box<CalculationExpr>),
+    Parens(Box<CalculationExpr>),
+}
+
+impl<'a> Parser<'a> {
+    /// Parse the arguments to `calc()` or `clamp()`
+    ///
+    /// Assumes the opening parenthesis has already been consumed
+    pub(super) fn parse_calculation(&mut self, name: CalculationName) -> SassResult<Value> {
+        let span = self.span_before;
+        let max_args = match name {
+            CalculationName::Calc => Some(1),
+            CalculationName::Clamp => Some(3),
+            CalculationName::Min | CalculationName::Max => None,
+        };
+        let args = self.parse_calculation_args(max_args)?;
+        self.eval_calculation(name, args, span)
+    }
+
+    /// `min()` and `max()` are parsed as calculations if possible, and
+    /// otherwise are parsed as normal Sass functions
+    ///
+    /// If these arguments are not a valid calculation, no tokens are consumed
+    pub(super) fn try_parse_min_max_calculation(
+        &mut self,
+        name: CalculationName,
+    ) -> SassResult<Option<Value>> {
+        let span = self.span_before;
+
+        self.toks.reset_cursor();
+        let start = self.toks.clone();
+
+        let args = match self.parse_calculation_args(None) {
+            Ok(args) => args,
+            Err(..) => {
+                *self.toks = start;
+                self.span_before = span;
+                return Ok(None);
+            }
+        };
+
+        self.eval_calculation(name, args, span).map(Some)
+    }
+
+    fn parse_calculation_args(
+        &mut self,
+        max_args: Option<usize>,
+    ) -> SassResult<Vec<CalculationExpr>> {
+        self.whitespace_or_comment();
+
+        if self.contains_calculation_interpolation() {
+            let span = self.span_before;
+            let mut buf = String::new();
+            self.parse_calc_args(&mut buf)?;
+            let contents = buf[1..buf.len() - 1].trim().to_owned();
+            return Ok(vec![CalculationExpr::Value(
+                HigherIntermediateValue::Literal(Value::String(contents, QuoteKind::None))
+                    .span(span),
+            )]);
+        }
+
+        let mut args = vec![self.parse_calculation_sum()?];
+
+        while max_args.map_or(true, |max| args.len() < max) {
+            self.whitespace_or_comment();
+            if !self.consume_char_if_exists(',') {
+                break;
+            }
+            self.whitespace_or_comment();
+            args.push(self.parse_calculation_sum()?);
+        }
+
+        self.whitespace_or_comment();
+
+        match self.toks.next() {
+            Some(Token { kind: ')', pos }) => {
+                self.span_before = pos;
+                Ok(args)
+            }
+            Some(Token { pos, .. }) => Err((
+                if max_args == Some(args.len()) {
+                    "expected \"+\", \"-\", \"*\", \"/\", or \")\"."
+                } else {
+                    "expected \"+\", \"-\", \"*\", \"/\", \",\", or \")\"."
+                },
+                pos,
+            )
+                .into()),
+            None => Err(("expected \")\".", self.span_before).into()),
+        }
+    }
+
+    /// Whether the arguments to this calculation contain interpolation, in
+    /// which case the entire argument list is treated as a single unquoted
+    /// string
+    fn contains_calculation_interpolation(&mut self) -> bool {
+        let mut nesting = 0;
+
+        while let Some(tok) = self.toks.peek() {
+            match tok.kind {
+                '\\' => {
+                    self.toks.advance_cursor();
+                }
+                q @ '"' | q @ '\'' => {
+                    self.toks.advance_cursor();
+                    while let Some(tok) = self.toks.peek() {
+                        if tok.kind == '\\' {
+                            self.toks.advance_cursor();
+                        } else if tok.kind == q {
+                            break;
+                        }
+                        self.toks.advance_cursor();
+                    }
+                }
+                '(' => nesting += 1,
+                ')' => {
+                    if nesting == 0 {
+                        break;
+                    }
+                    nesting -= 1;
+                }
+                '#' => {
+                    if let Some(Token { kind: '{', .. }) = self.toks.peek_next() {
+                        self.toks.reset_cursor();
+                        return true;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            self.toks.advance_cursor();
+        }
+
+        self.toks.reset_cursor();
+        false
+    }
+
+    fn parse_calculation_sum(&mut self) -> SassResult<CalculationExpr> {
+        let (mut sum, mut found_whitespace) = self.parse_calculation_product()?;
+
+        loop {
+            let (op, pos) = match self.toks.peek() {
+                Some(Token { kind: '+', pos }) => (Op::Plus, *pos),
+                Some(Token { kind: '-', pos }) => (Op::Minus, *pos),
+                Some(..) | None => return Ok(sum),
+            };
+
+            let next_is_whitespace = matches!(
+                self.toks.peek_next(),
+                Some(Token { kind: ' ', .. })
+                    | Some(Token { kind: '\t', .. })
+                    | Some(Token { kind: '\n', .. })
+            );
+            self.toks.reset_cursor();
+
+            if !found_whitespace || !next_is_whitespace {
+                return Err((
+                    "\"+\" and \"-\" must be surrounded by whitespace in calculations.",
+                    pos,
+                )
+                    .into());
+            }
+
+            self.toks.next();
+            self.whitespace_or_comment();
+
+            let (rhs, whitespace_after_rhs) = self.parse_calculation_product()?;
+            found_whitespace = whitespace_after_rhs;
+            sum = CalculationExpr::Operation(Box::new(sum), op, Box::new(rhs));
+        }
+    }
+
+    /// Returns the parsed product along with whether it was followed by
+    /// whitespace, which is needed to validate a following `+` or `-`
+    fn parse_calculation_product(&mut self) -> SassResult<(CalculationExpr, bool)> {
+        let mut product = self.parse_calculation_value()?;
+
+        loop {
+            let found_whitespace = self.whitespace_or_comment();
+
+            let op = match self.toks.peek() {
+                Some(Token { kind: '*', .. }) => Op::Mul,
+                Some(Token { kind: '/', .. }) => Op::Div,
+                Some(..) | None => return Ok((product, found_whitespace)),
+            };
+
+            self.toks.next();
+            self.whitespace_or_comment();
+
+            let rhs = self.parse_calculation_value()?;
+            product = CalculationExpr::Operation(Box::new(product), op, Box::new(rhs));
+        }
+    }
+
+    fn parse_calculation_value(&mut self) -> SassResult<CalculationExpr> {
+        let (kind, pos) = match self.toks.peek() {
+            Some(Token { kind, pos }) => (*kind, *pos),
+            None => return Err(("Expected expression.", self.span_before).into()),
+        };
+
+        self.span_before = pos;
+
+        match kind {
+            '+' | '-' | '.' | '0'..='9' => {
+                let is_number = match kind {
+                    '+' | '-' => {
+                        let next_is_number = matches!(
+                            self.toks.peek_next(),
+                            Some(Token {
+                                kind: '0'..='9',
+                                ..
+                            }) | Some(Token { kind: '.', .. })
+                        );
+                        self.toks.reset_cursor();
+                        next_is_number
+                    }
+                    _ => true,
+                };
+
+                if is_number {
+                    let is_negative = match kind {
+                        '+' => {
+                            self.toks.next();
+                            false
+                        }
+                        '-' => {
+                            self.toks.next();
+                            true
+                        }
+                        _ => false,
+                    };
+
+                    let number = self.parse_dimension(&|_| false)?;
+                    let value = calculation_value(number.node, pos)?;
+
+                    return Ok(CalculationExpr::Value(if is_negative {
+                        HigherIntermediateValue::UnaryOp(Op::Minus, Box::new(value))
+                            .span(pos.merge(number.span))
+                    } else {
+                        value.span(pos.merge(number.span))
+                    }));
+                }
+
+                if kind == '-' && self.next_is_hypen() {
+                    return self.parse_calculation_identifier();
+                }
+
+                Err(("Expected number, variable, function, or calculation.", pos).into())
+            }
+            '$' => {
+                self.toks.next();
+
+                if self.flags.in_plain_css() {
+                    return Err(("Sass variables aren't allowed in plain CSS.", pos).into());
+                }
+
+                let name = self
+                    .parse_identifier_no_interpolation(false)?
+                    .map_node(|n| n.into());
+                let value = self
+                    .scopes
+                    .get_var(name, &self.global_scope.borrow())?
+                    .clone();
+                Ok(CalculationExpr::Value(
+                    HigherIntermediateValue::Literal(value).span(name.span),
+                ))
+            }
+            '(' => {
+                self.toks.next();
+                self.whitespace_or_comment();
+                let inner = self.parse_calculation_sum()?;
+                self.whitespace_or_comment();
+                self.expect_char(')')?;
+                Ok(CalculationExpr::Parens(Box::new(inner)))
+            }
+            c if is_name_start(c) || c == '\\' => self.parse_calculation_identifier(),
+            _ => Err(("Expected number, variable, function, or calculation.", pos).into()),
+        }
+    }
+
+    /// A function call or namespaced member inside of a calculation
+    fn parse_calculation_identifier(&mut self) -> SassResult<CalculationExpr> {
+        let Spanned { node: name, span } = self.parse_identifier_no_interpolation(false)?;
+
+        self.span_before = span;
+
+        let value = match self.toks.peek() {
+            Some(Token { kind: '(', .. }) => {
+                self.toks.next();
+                let lower = name.to_ascii_lowercase();
+                self.parse_fn_call(name, lower)?
+            }
+            Some(Token { kind: '.', .. }) => {
+                self.toks.next();
+                self.parse_module_item(&name, span)?
+            }
+            Some(..) | None => return Err(("Expected \"(\" or \".\".", span).into()),
+        };
+
+        Ok(CalculationExpr::Value(
+            calculation_value(value.node, span)?.span(value.span),
+        ))
+    }
+
+    fn eval_calculation(
+        &mut self,
+        name: CalculationName,
+        args: Vec<CalculationExpr>,
+        span: Span,
+    ) -> SassResult<Value> {
+        let args = args
+            .into_iter()
+            .map(|arg| self.eval_calculation_expr(arg, span))
+            .collect::<SassResult<Vec<CalculationArg>>>()?;
+
+        match name {
+            CalculationName::Calc => Ok(calc(args.into_iter().next().unwrap())),
+            CalculationName::Min => min_max(args, true, span),
+            CalculationName::Max => min_max(args, false, span),
+            CalculationName::Clamp => clamp(args, span),
+        }
+    }
+
+    fn eval_calculation_expr(
+        &mut self,
+        expr: CalculationExpr,
+        span: Span,
+    ) -> SassResult<CalculationArg> {
+        match expr {
+            CalculationExpr::Value(Spanned { node, span }) => {
+                let value = ValueVisitor::new(self, span).eval(node, true)?;
+                calculation_arg_from_value(value, span)
+            }
+            CalculationExpr::Operation(lhs, op, rhs) => {
+                let lhs = self.eval_calculation_expr(*lhs, span)?;
+                let rhs = self.eval_calculation_expr(*rhs, span)?;
+                self.operate(lhs, op, rhs, span)
+            }
+            CalculationExpr::Parens(inner) => {
+                Ok(match self.eval_calculation_expr(*inner, span)? {
+                    CalculationArg::String(s) => CalculationArg::String(format!("({})", s)),
+                    arg => arg,
+                })
+            }
+        }
+    }
+
+    fn operate(
+        &mut self,
+        lhs: CalculationArg,
+        op: Op,
+        rhs: CalculationArg,
+        span: Span,
+    ) -> SassResult<CalculationArg> {
+        match op {
+            Op::Plus | Op::Minus => {
+                if let (
+                    CalculationArg::Number(Some(num1), unit1),
+                    CalculationArg::Number(Some(num2), unit2),
+                ) = (&lhs, &rhs)
+                {
+                    if is_comparable(unit1, unit2) {
+                        let num2 = convert(num2.clone(), unit2, unit1);
+                        return Ok(CalculationArg::Number(
+                            Some(if op == Op::Plus {
+                                num1.clone() + num2
+                            } else {
+                                num1.clone() - num2
+                            }),
+                            unit1.clone(),
+                        ));
+                    }
+                }
+
+                verify_compatible_numbers(&[&lhs, &rhs], span)?;
+
+                Ok(match rhs {
+                    CalculationArg::Number(Some(num), unit) if num.is_negative() => {
+                        CalculationArg::Operation(
+                            Box::new(lhs),
+                            if op == Op::Plus { Op::Minus } else { Op::Plus },
+                            Box::new(CalculationArg::Number(Some(-num), unit)),
+                        )
+                    }
+                    rhs => CalculationArg::Operation(Box::new(lhs), op, Box::new(rhs)),
+                })
+            }
+            _ => match (lhs, rhs) {
+                (CalculationArg::Number(num1, unit1), CalculationArg::Number(num2, unit2)) => {
+                    let value = ValueVisitor::new(self, span).eval(
+                        HigherIntermediateValue::BinaryOp(
+                            Box::new(HigherIntermediateValue::Literal(Value::Dimension(
+                                num1, unit1, true,
+                            ))),
+                            op,
+                            Box::new(HigherIntermediateValue::Literal(Value::Dimension(
+                                num2, unit2, true,
+                            ))),
+                        ),
+                        true,
+                    )?;
+                    calculation_arg_from_value(value, span)
+                }
+                (lhs, rhs) => Ok(CalculationArg::Operation(Box::new(lhs), op, Box::new(rhs))),
+            },
+        }
+    }
+}
+
+/// Only values, not operators, may appear as a single calculation operand
+fn calculation_value(value: IntermediateValue, span: Span) -> SassResult<HigherIntermediateValue> {
+    match value {
+        IntermediateValue::Value(v @ HigherIntermediateValue::Literal(..))
+        | IntermediateValue::Value(v @ HigherIntermediateValue::Function(..)) => Ok(v),
+        _ => Err(("Expected number, variable, function, or calculation.", span).into()),
+    }
+}
+
+fn calculation_arg_from_value(value: Value, span: Span) -> SassResult<CalculationArg> {
+    match value {
+        Value::Dimension(num, unit, _) => Ok(CalculationArg::Number(num, unit)),
+        Value::Calculation(mut calc) => {
+            if calc.name == CalculationName::Calc && calc.args.len() == 1 {
+                Ok(calc.args.pop().unwrap())
+            } else {
+                Ok(CalculationArg::Calculation(calc))
+            }
+        }
+        Value::String(s, QuoteKind::None) => Ok(CalculationArg::String(s)),
+        Value::String(..) => Err((
+            format!(
+                "Quoted string {} can't be used in a calculation.",
+                value.inspect(span)?
+            ),
+            span,
+        )
+            .into()),
+        v => Err((
+            format!("Value {} can't be used in a calculation.", v.inspect(span)?),
+            span,
+        )
+            .into()),
+    }
+}
+
+fn calc(arg: CalculationArg) -> Value {
+    match arg {
+        CalculationArg::Number(num, unit) => Value::Dimension(num, unit, true),
+        CalculationArg::Calculation(calc) => Value::Calculation(calc),
+        arg => Value::Calculation(SassCalculation::new(CalculationName::Calc, vec![arg])),
+    }
+}
+
+fn min_max(args: Vec<CalculationArg>, is_min: bool, span: Span) -> SassResult<Value> {
+    let mut extremum: Option<(&Number, &Unit)> = None;
+
+    for arg in &args {
+        match arg {
+            CalculationArg::Number(Some(num), unit) => match extremum {
+                Some((ext_num, ext_unit)) => {
+                    if !is_comparable(ext_unit, unit) {
+                        extremum = None;
+                        break;
+                    }
+                    let num_converted = convert(num.clone(), unit, ext_unit);
+                    if (is_min && num_converted < *ext_num) || (!is_min && num_converted > *ext_num)
+                    {
+                        extremum = Some((num, unit));
+                    }
+                }
+                None => extremum = Some((num, unit)),
+            },
+            _ => {
+                extremum = None;
+                break;
+            }
+        }
+    }
+
+    if let Some((num, unit)) = extremum {
+        return Ok(Value::Dimension(Some(num.clone()), unit.clone(), true));
+    }
+
+    verify_compatible_numbers(&args.iter().collect::<Vec<&CalculationArg>>(), span)?;
+
+    Ok(Value::Calculation(SassCalculation::new(
+        if is_min {
+            CalculationName::Min
+        } else {
+            CalculationName::Max
+        },
+        args,
+    )))
+}
+
+fn clamp(args: Vec<CalculationArg>, span: Span) -> SassResult<Value> {
+    if args.len() != 3
+        && !args
+            .iter()
+            .any(|arg| matches!(arg, CalculationArg::String(..)))
+    {
+        return Err((
+            format!(
+                "3 arguments required, but only {} {} passed.",
+                args.len(),
+                if args.len() == 1 { "was" } else { "were" }
+            ),
+            span,
+        )
+            .into());
+    }
+
+    if let [CalculationArg::Number(Some(min), min_unit), CalculationArg::Number(Some(value), value_unit), CalculationArg::Number(Some(max), max_unit)] =
+        args.as_slice()
+    {
+        if is_comparable(min_unit, value_unit) && is_comparable(min_unit, max_unit) {
+            let value_converted = convert(value.clone(), value_unit, min_unit);
+            let max_converted = convert(max.clone(), max_unit, min_unit);
+
+            return Ok(if value_converted <= *min {
+                Value::Dimension(Some(min.clone()), min_unit.clone(), true)
+            } else if value_converted >= max_converted {
+                Value::Dimension(Some(max.clone()), max_unit.clone(), true)
+            } else {
+                Value::Dimension(Some(value.clone()), value_unit.clone(), true)
+            });
+        }
+    }
+
+    verify_compatible_numbers(&args.iter().collect::<Vec<&CalculationArg>>(), span)?;
+
+    Ok(Value::Calculation(SassCalculation::new(
+        CalculationName::Clamp,
+        args,
+    )))
+}
+
+/// Whether two numbers can be combined into a single number
+///
+/// Unlike `Unit::comparable`, unitless numbers are not considered
+/// comparable to numbers with units
+fn is_comparable(unit1: &Unit, unit2: &Unit) -> bool {
+    if *unit1 == Unit::None || *unit2 == Unit::None {
+        return unit1 == unit2;
+    }
+
+    unit1.comparable(unit2)
+}
+
+/// Converts between two comparable units, where unitless numbers are only
+/// comparable to other unitless numbers
+fn convert(num: Number, from: &Unit, to: &Unit) -> Number {
+    if from == to {
+        num
+    } else {
+        num.convert(from, to)
+    }
+}
+
+/// Whether two units may be compatible once resolved by the browser
+///
+/// For example, `px` and `%` may be compatible depending on context,
+/// but `px` and `s` are known to never be compatible.
+fn has_possibly_compatible_units(unit1: &Unit, unit2: &Unit) -> bool {
+    if *unit1 == Unit::None || *unit2 == Unit::None {
+        return unit1 == unit2;
+    }
+
+    fn known_compatibility(unit: &Unit) -> Option<UnitKind> {
+        match unit.kind() {
+            UnitKind::Absolute | UnitKind::FontRelative | UnitKind::ViewportRelative => {
+                Some(UnitKind::Absolute)
+            }
+            UnitKind::Other | UnitKind::None => None,
+            kind => Some(kind),
+        }
+    }
+
+    match (known_compatibility(unit1), known_compatibility(unit2)) {
+        (Some(kind1), Some(kind2)) => kind1 == kind2,
+        _ => true,
+    }
+}
+
+fn verify_compatible_numbers(args: &[&CalculationArg], span: Span) -> SassResult<()> {
+    for arg in args {
+        if let CalculationArg::Number(num, unit @ Unit::Mul(..))
+        | CalculationArg::Number(num, unit @ Unit::Div(..)) = arg
+        {
+            return Err((
+                format!(
+                    "Number {} isn't compatible with CSS calculations.",
+                    Value::Dimension(num.clone(), unit.clone(), true).inspect(span)?
+                ),
+                span,
+            )
+                .into());
+        }
+    }
+
+    for (idx, arg1) in args.iter().enumerate() {
+        let (num1, unit1) = match arg1 {
+            CalculationArg::Number(num, unit) => (num, unit),
+            _ => continue,
+        };
+
+        for arg2 in &args[idx + 1..] {
+            let (num2, unit2) = match arg2 {
+                CalculationArg::Number(num, unit) => (num, unit),
+                _ => continue,
+            };
+
+            if !has_possibly_compatible_units(unit1, unit2) {
+                return Err((
+                    format!(
+                        "{} and {} are incompatible.",
+                        Value::Dimension(num1.clone(), unit1.clone(), true).inspect(span)?,
+                        Value::Dimension(num2.clone(), unit2.clone(), true).inspect(span)?
+                    ),
+                    span,
+                )
+                    .into());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -167,13 +167,24 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
             v => panic!("{:?}", v),
         };
         Ok(match left {
-            Value::Map(..) | Value::FunctionRef(..) => {
+            Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                 return Err((
                     format!("{} isn't a valid CSS value.", left.inspect(self.span)?),
                     self.span,
                 )
                     .into())
             }
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} + {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
             Value::True | Value::False => match right {
                 Value::String(s, QuoteKind::Quoted) => Value::String(
                     format!("{}{}", left.to_css_string(self.span)?, s),
@@ -240,14 +251,14 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     format!("{}{}{}", num, unit, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
                     )
                         .into())
                 }
-                Value::Color(..) => {
+                Value::Color(..) | Value::Calculation(..) => {
                     return Err((
                         format!(
                             "Undefined operation \"{}{} + {}\".",
@@ -347,14 +358,14 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     format!("{}{}-{}", num, unit, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
                     )
                         .into())
                 }
-                Value::Color(..) => {
+                Value::Color(..) | Value::Calculation(..) => {
                     return Err((
                         format!(
                             "Undefined operation \"{}{} - {}\".",
@@ -397,6 +408,17 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                 ),
                 QuoteKind::None,
             ),
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} - {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
             _ => match right {
                 Value::String(s, q) => Value::String(
                     format!("{}-{}{}{}", left.to_css_string(self.span)?, q, s, q),
@@ -555,13 +577,25 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     QuoteKind::None,
                 ),
                 Value::Null => Value::String(format!("{}{}/", num, unit), QuoteKind::None),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
                     )
                         .into())
                 }
+                Value::Calculation(..) => {
+                    return Err((
+                        format!(
+                            "Undefined operation \"{}{} / {}\".",
+                            num,
+                            unit,
+                            right.inspect(self.span)?
+                        ),
+                        self.span,
+                    )
+                        .into())
+                }
             },
             Value::Color(c) => match right {
                 Value::String(s, q) => {
@@ -595,12 +629,13 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                 | Value::Dimension(..)
                 | Value::Color(..)
                 | Value::List(..)
-                | Value::ArgList(..) => Value::String(
+                | Value::ArgList(..)
+                | Value::Calculation(..) => Value::String(
                     format!("{}{}{}/{}", q1, s1, q1, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
                 Value::Null => Value::String(format!("{}{}{}/", q1, s1, q1), QuoteKind::None),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
@@ -608,6 +643,17 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                         .into())
                 }
             },
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} / {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
             _ => match right {
                 Value::String(s, q) => Value::String(
                     format!("{}/{}{}{}", left.to_css_string(self.span)?, q, s, q),
@@ -15,7 +15,7 @@ use crate::{
     error::SassResult,
     unit::Unit,
     utils::{eat_whole_number, is_name, IsWhitespace, ParsedNumber},
-    value::{Number, SassFunction, SassMap, Value},
+    value::{CalculationName, Number, SassFunction, SassMap, Value},
     Token,
 };
 
@@ -24,7 +24,7 @@ use super::eval::{HigherIntermediateValue, ValueVisitor};
 use super::super::Parser;
 
 #[derive(Clone, Debug)]
-enum IntermediateValue {
+pub(super) enum IntermediateValue {
     Value(HigherIntermediateValue),
     Op(Op),
     Comma,
@@ -189,12 +189,26 @@ impl<'a> Parser<'a> {
             options: self.options,
             modules: self.modules,
             module_config: self.module_config,
+            module_cache: self.module_cache,
+            media_queries: self.media_queries,
         }
         .parse_value(in_paren, &|_| false)
     }
 
+    /// Parses a value that must make up the rest of the input, such as a
+    /// global variable passed in through `Options`
+    pub(crate) fn parse_entire_value(&mut self) -> SassResult<Spanned<Value>> {
+        let value = self.parse_value(false, &|_| false)?;
+        self.whitespace_or_comment();
+
+        match self.toks.peek() {
+            Some(tok) => Err(("expected no more input.", tok.pos()).into()),
+            None => Ok(value),
+        }
+    }
+
     #[allow(clippy::eval_order_dependence)]
-    fn parse_module_item(
+    pub(super) fn parse_module_item(
         &mut self,
         module: &str,
         mut module_span: Span,
@@ -208,7 +222,7 @@ impl<'a> Parser<'a> {
                 module_span = module_span.merge(var.span);
 
                 let value = self.modules.get(module.into(), module_span)?.get_var(var)?;
-                HigherIntermediateValue::Literal(value.clone())
+                HigherIntermediateValue::Literal(value)
             } else {
                 let fn_name = self
                     .parse_identifier_no_interpolation(false)?
@@ -230,31 +244,57 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn parse_fn_call(
+    pub(super) fn parse_fn_call(
         &mut self,
         mut s: String,
         lower: String,
     ) -> SassResult<Spanned<IntermediateValue>> {
-        if lower == "min" || lower == "max" {
-            match self.try_parse_min_max(&lower, true)? {
-                Some(val) => {
-                    self.toks.truncate_iterator_to_cursor();
-                    return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
-                        Value::String(val, QuoteKind::None),
-                    ))
-                    .span(self.span_before));
-                }
-                None => {
-                    self.toks.reset_cursor();
+        if self.flags.in_plain_css() {
+            return self.parse_plain_css_fn_call(s, &lower);
+        }
+
+        match lower.as_str() {
+            "calc" => {
+                return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
+                    self.parse_calculation(CalculationName::Calc)?,
+                ))
+                .span(self.span_before))
+            }
+            "clamp" => {
+                return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
+                    self.parse_calculation(CalculationName::Clamp)?,
+                ))
+                .span(self.span_before))
+            }
+            "min" | "max" => {
+                let name = if lower == "min" {
+                    CalculationName::Min
+                } else {
+                    CalculationName::Max
+                };
+
+                if let Some(val) = self.try_parse_min_max_calculation(name)? {
+                    return Ok(
+                        IntermediateValue::Value(HigherIntermediateValue::Literal(val))
+                            .span(self.span_before),
+                    );
                 }
             }
+            _ => {}
         }
 
         let as_ident = Identifier::from(&s);
-        let func = match self.scopes.get_fn(as_ident, self.global_scope) {
+        let func = self.scopes.get_fn(as_ident, &self.global_scope.borrow());
+        let func = match func {
             Some(f) => f,
             None => {
-                if let Some(f) = GLOBAL_FUNCTIONS.get(as_ident.as_str()) {
+                if let Some(index) = self.options.custom_function(as_ident) {
+                    return Ok(IntermediateValue::Value(HigherIntermediateValue::Function(
+                        SassFunction::Custom(index, as_ident),
+                        self.parse_call_args()?,
+                    ))
+                    .span(self.span_before));
+                } else if let Some(f) = GLOBAL_FUNCTIONS.get(as_ident.as_str()) {
                     return Ok(IntermediateValue::Value(HigherIntermediateValue::Function(
                         SassFunction::Builtin(f.clone(), as_ident),
                         self.parse_call_args()?,
@@ -289,6 +329,42 @@ impl<'a> Parser<'a> {
         )
     }
 
+    /// Function calls in plain CSS are never evaluated, but may not be calls
+    /// to Sass-only functions
+    fn parse_plain_css_fn_call(
+        &mut self,
+        mut s: String,
+        lower: &str,
+    ) -> SassResult<Spanned<IntermediateValue>> {
+        if GLOBAL_FUNCTIONS.contains_key(lower) && !is_plain_css_function(lower) {
+            return Err((
+                "This function isn't allowed in plain CSS.",
+                self.span_before,
+            )
+                .into());
+        }
+
+        match unvendor(lower) {
+            "calc" | "element" | "expression" => {
+                s = lower.to_owned();
+                self.parse_calc_args(&mut s)?;
+            }
+            "url" => match self.try_parse_url()? {
+                Some(val) => s = val,
+                None => s.push_str(&self.parse_call_args()?.to_css_string()?),
+            },
+            _ => s.push_str(&self.parse_call_args()?.to_css_string()?),
+        }
+
+        Ok(
+            IntermediateValue::Value(HigherIntermediateValue::Literal(Value::String(
+                s,
+                QuoteKind::None,
+            )))
+            .span(self.span_before),
+        )
+    }
+
     fn parse_ident_value(
         &mut self,
         predicate: &dyn Fn(&mut PeekMoreIterator<IntoIter<Token>>) -> bool,
@@ -341,9 +417,9 @@ impl<'a> Parser<'a> {
                 "true" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::True)),
                 "false" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::False)),
                 "null" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Null)),
-                "not" => IntermediateValue::Op(Op::Not),
-                "and" => IntermediateValue::Op(Op::And),
-                "or" => IntermediateValue::Op(Op::Or),
+                "not" if !self.flags.in_plain_css() => IntermediateValue::Op(Op::Not),
+                "and" if !self.flags.in_plain_css() => IntermediateValue::Op(Op::And),
+                "or" if !self.flags.in_plain_css() => IntermediateValue::Op(Op::Or),
                 _ => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::String(
                     s,
                     QuoteKind::None,
@@ -353,7 +429,7 @@ impl<'a> Parser<'a> {
         .span(span))
     }
 
-    fn next_is_hypen(&mut self) -> bool {
+    pub(super) fn next_is_hypen(&mut self) -> bool {
         if let Some(Token { kind, .. }) = self.toks.peek_forward(1) {
             matches!(kind, '-' | '_' | 'a'..='z' | 'A'..='Z')
         } else {
@@ -471,7 +547,7 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_dimension(
+    pub(super) fn parse_dimension(
         &mut self,
         predicate: &dyn Fn(&mut PeekMoreIterator<IntoIter<Token>>) -> bool,
     ) -> SassResult<Spanned<IntermediateValue>> {
@@ -578,10 +654,12 @@ impl<'a> Parser<'a> {
         }
 
         let mut map = SassMap::new();
-        let key = self.parse_value(
-            true,
-            &|c| matches!(c.peek(), Some(Token { kind: ':', .. }) | Some(Token { kind: ')', .. })),
-        )?;
+        let key = self.parse_value(true, &|c| {
+            matches!(
+                c.peek(),
+                Some(Token { kind: ':', .. }) | Some(Token { kind: ')', .. })
+            )
+        })?;
 
         match self.toks.next() {
             Some(Token { kind: ':', .. }) => {}
@@ -594,10 +672,12 @@ impl<'a> Parser<'a> {
             Some(..) | None => return Err(("expected \")\".", key.span).into()),
         }
 
-        let val = self.parse_value(
-            true,
-            &|c| matches!(c.peek(), Some(Token { kind: ',', .. }) | Some(Token { kind: ')', .. })),
-        )?;
+        let val = self.parse_value(true, &|c| {
+            matches!(
+                c.peek(),
+                Some(Token { kind: ',', .. }) | Some(Token { kind: ')', .. })
+            )
+        })?;
 
         map.insert(key.node, val.node);
 
@@ -630,14 +710,22 @@ impl<'a> Parser<'a> {
         }
 
         loop {
-            let key =
-                self.parse_value(true, &|c| matches!(c.peek(), Some(Token { kind: ':', .. }) | Some(Token { kind: ',', .. })))?;
+            let key = self.parse_value(true, &|c| {
+                matches!(
+                    c.peek(),
+                    Some(Token { kind: ':', .. }) | Some(Token { kind: ',', .. })
+                )
+            })?;
 
             self.expect_char(':')?;
 
             self.whitespace_or_comment();
-            let val =
-                self.parse_value(true, &|c| matches!(c.peek(), Some(Token { kind: ',', .. }) | Some(Token { kind: ')', .. })))?;
+            let val = self.parse_value(true, &|c| {
+                matches!(
+                    c.peek(),
+                    Some(Token { kind: ',', .. }) | Some(Token { kind: ')', .. })
+                )
+            })?;
 
             span = span.merge(val.span);
 
@@ -843,13 +931,20 @@ impl<'a> Parser<'a> {
             }
             '[' => return Some(self.parse_bracketed_list()),
             '$' => {
-                self.toks.next();
+                let span = self.toks.next().unwrap().pos();
+
+                if self.flags.in_plain_css() {
+                    return Some(Err(
+                        ("Sass variables aren't allowed in plain CSS.", span).into()
+                    ));
+                }
+
                 let val = match self.parse_identifier_no_interpolation(false) {
                     Ok(v) => v.map_node(|i| i.into()),
                     Err(e) => return Some(Err(e)),
                 };
                 IntermediateValue::Value(HigherIntermediateValue::Literal(
-                    match self.scopes.get_var(val, self.global_scope) {
+                    match self.scopes.get_var(val, &self.global_scope.borrow()) {
                         Ok(v) => v.clone(),
                         Err(e) => return Some(Err(e)),
                     },
@@ -858,6 +953,9 @@ impl<'a> Parser<'a> {
             }
             '+' => {
                 let span = self.toks.next().unwrap().pos();
+                if let Err(e) = self.check_plain_css_operator(span, true) {
+                    return Some(Err(e));
+                }
                 IntermediateValue::Op(Op::Plus).span(span)
             }
             '-' => {
@@ -869,14 +967,23 @@ impl<'a> Parser<'a> {
                 }
                 self.toks.reset_cursor();
                 let span = self.toks.next().unwrap().pos();
+                if let Err(e) = self.check_plain_css_operator(span, true) {
+                    return Some(Err(e));
+                }
                 IntermediateValue::Op(Op::Minus).span(span)
             }
             '*' => {
                 let span = self.toks.next().unwrap().pos();
+                if let Err(e) = self.check_plain_css_operator(span, false) {
+                    return Some(Err(e));
+                }
                 IntermediateValue::Op(Op::Mul).span(span)
             }
             '%' => {
                 let span = self.toks.next().unwrap().pos();
+                if let Err(e) = self.check_plain_css_operator(span, false) {
+                    return Some(Err(e));
+                }
                 IntermediateValue::Op(Op::Rem).span(span)
             }
             ',' => {
@@ -885,6 +992,9 @@ impl<'a> Parser<'a> {
             }
             q @ '>' | q @ '<' => {
                 let mut span = self.toks.next().unwrap().pos;
+                if let Err(e) = self.check_plain_css_operator(span, false) {
+                    return Some(Err(e));
+                }
                 #[allow(clippy::eval_order_dependence)]
                 IntermediateValue::Op(if let Some(Token { kind: '=', .. }) = self.toks.peek() {
                     span = span.merge(self.toks.next().unwrap().pos);
@@ -906,6 +1016,9 @@ impl<'a> Parser<'a> {
                 let mut span = self.toks.next().unwrap().pos();
                 if let Some(Token { kind: '=', pos }) = self.toks.next() {
                     span = span.merge(pos);
+                    if let Err(e) = self.check_plain_css_operator(span, false) {
+                        return Some(Err(e));
+                    }
                     IntermediateValue::Op(Op::Equal).span(span)
                 } else {
                     return Some(Err(("expected \"=\".", span).into()));
@@ -915,7 +1028,10 @@ impl<'a> Parser<'a> {
                 let mut span = self.toks.next().unwrap().pos();
                 if let Some(Token { kind: '=', .. }) = self.toks.peek() {
                     span = span.merge(self.toks.next().unwrap().pos());
-                    return Some(Ok(IntermediateValue::Op(Op::NotEqual).span(span)));
+                    return Some(
+                        self.check_plain_css_operator(span, false)
+                            .map(|()| IntermediateValue::Op(Op::NotEqual).span(span)),
+                    );
                 }
                 self.whitespace();
                 let v = match self.parse_identifier() {
@@ -961,6 +1077,25 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    /// Plain CSS only has the `/` operator, although `+` and `-` may still be
+    /// the sign of a number
+    fn check_plain_css_operator(&mut self, span: Span, is_sign: bool) -> SassResult<()> {
+        if !self.flags.in_plain_css()
+            || (is_sign
+                && matches!(
+                    self.toks.peek(),
+                    Some(Token {
+                        kind: '0'..='9',
+                        ..
+                    }) | Some(Token { kind: '.', .. })
+                ))
+        {
+            return Ok(());
+        }
+
+        Err(("Operators aren't allowed in plain CSS.", span).into())
+    }
+
     fn parse_hex(&mut self) -> SassResult<Spanned<Value>> {
         let mut s = String::with_capacity(7);
         s.push('#');
@@ -1371,3 +1506,24 @@ fn parse_i64(s: &str) -> i64 {
 fn is_keyword_operator(s: &str) -> bool {
     matches!(s, "and" | "or" | "not")
 }
+
+/// Whether or not `name` is a global Sass function that is also a plain CSS
+/// function, and so may be used in plain CSS files
+fn is_plain_css_function(name: &str) -> bool {
+    matches!(
+        name,
+        "rgb"
+            | "rgba"
+            | "hsl"
+            | "hsla"
+            | "grayscale"
+            | "invert"
+            | "alpha"
+            | "opacity"
+            | "saturate"
+            | "min"
+            | "max"
+            | "round"
+            | "abs"
+    )
+}
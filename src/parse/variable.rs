@@ -40,7 +40,7 @@ impl<'a> Parser<'a> {
             let config_val = self.module_config.get(ident).filter(|v| !v.is_null());
 
             let value = if (self.at_root && !self.flags.in_control_flow()) || global {
-                if self.global_scope.default_var_exists(ident) {
+                if self.global_scope.borrow().default_var_exists(ident) {
                     return Ok(());
                 } else if let Some(value) = config_val {
                     value
@@ -48,7 +48,7 @@ impl<'a> Parser<'a> {
                     var_value?.node
                 }
             } else if self.at_root && self.flags.in_control_flow() {
-                if self.global_scope.default_var_exists(ident) {
+                if self.global_scope.borrow().default_var_exists(ident) {
                     return Ok(());
                 }
 
@@ -63,19 +63,25 @@ impl<'a> Parser<'a> {
                 var_value?.node
             };
 
-            if self.at_root && self.global_scope.var_exists(ident) {
-                if !self.global_scope.default_var_exists(ident) {
-                    self.global_scope.insert_var(ident, value.clone());
+            if self.at_root && self.global_scope.borrow().var_exists(ident) {
+                if !self.global_scope.borrow().default_var_exists(ident) {
+                    self.global_scope
+                        .borrow_mut()
+                        .insert_var(ident, value.clone());
                 }
             } else if self.at_root
                 && !self.flags.in_control_flow()
-                && !self.global_scope.default_var_exists(ident)
+                && !self.global_scope.borrow().default_var_exists(ident)
             {
-                self.global_scope.insert_var(ident, value.clone());
+                self.global_scope
+                    .borrow_mut()
+                    .insert_var(ident, value.clone());
             }
 
             if global {
-                self.global_scope.insert_var(ident, value.clone());
+                self.global_scope
+                    .borrow_mut()
+                    .insert_var(ident, value.clone());
             }
 
             if self.at_root && !self.flags.in_control_flow() {
@@ -90,18 +96,20 @@ impl<'a> Parser<'a> {
         let value = var_value?.node;
 
         if global {
-            self.global_scope.insert_var(ident, value.clone());
+            self.global_scope
+                .borrow_mut()
+                .insert_var(ident, value.clone());
         }
 
         if self.at_root {
             if self.flags.in_control_flow() {
-                if self.global_scope.var_exists(ident) {
-                    self.global_scope.insert_var(ident, value);
+                if self.global_scope.borrow().var_exists(ident) {
+                    self.global_scope.borrow_mut().insert_var(ident, value);
                 } else {
                     self.scopes.insert_var(ident, value);
                 }
             } else {
-                self.global_scope.insert_var(ident, value);
+                self.global_scope.borrow_mut().insert_var(ident, value);
             }
         } else if !(self.flags.in_control_flow() && global) {
             self.scopes.insert_var(ident, value);
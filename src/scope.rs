@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
 use codemap::Spanned;
 
@@ -10,7 +10,11 @@ use crate::{
     value::{SassFunction, Value},
 };
 
-#[derive(Debug, Default)]
+/// The global scope of a stylesheet, shared with the module that stylesheet is
+/// loaded as so that the module's namespace always sees its current members
+pub(crate) type SharedScope = Rc<RefCell<Scope>>;
+
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Scope {
     pub vars: BTreeMap<Identifier, Value>,
     pub mixins: BTreeMap<Identifier, Mixin>,
@@ -78,14 +82,29 @@ impl Scope {
         self.functions.contains_key(&name)
     }
 
-    fn merge(&mut self, other: Scope) {
-        self.vars.extend(other.vars);
-        self.mixins.extend(other.mixins);
-        self.functions.extend(other.functions);
-    }
-
-    pub fn merge_module(&mut self, other: Module) {
-        self.merge(other.scope);
+    /// Adds the public members of `other` to this scope, e.g. through
+    /// `@use "foo" as *`
+    pub fn merge_module(&mut self, other: &Module) {
+        let Scope {
+            vars,
+            mixins,
+            functions,
+        } = other.scope.borrow().clone();
+
+        self.vars.extend(
+            vars.into_iter()
+                .filter(|(name, _)| !name.as_str().starts_with('-')),
+        );
+        self.mixins.extend(
+            mixins
+                .into_iter()
+                .filter(|(name, _)| !name.as_str().starts_with('-')),
+        );
+        self.functions.extend(
+            functions
+                .into_iter()
+                .filter(|(name, _)| !name.as_str().starts_with('-')),
+        );
     }
 
     pub fn default_var_exists(&self, s: Identifier) -> bool {
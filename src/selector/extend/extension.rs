@@ -1,6 +1,8 @@
 use codemap::Span;
 
-use super::{ComplexSelector, CssMediaQuery, SimpleSelector};
+use crate::{atrule::media::MediaQuery, error::SassResult};
+
+use super::{ComplexSelector, SimpleSelector};
 
 #[derive(Clone, Debug)]
 pub(crate) struct Extension {
@@ -25,7 +27,7 @@ pub(crate) struct Extension {
 
     /// The media query context to which this extend is restricted, or `None` if
     /// it can apply within any context.
-    pub media_context: Option<Vec<CssMediaQuery>>,
+    pub media_context: Option<Vec<MediaQuery>>,
 
     /// The span in which `extender` was defined.
     pub span: Span,
@@ -56,15 +58,19 @@ impl Extension {
 
     /// Asserts that the `media_context` for a selector is compatible with the
     /// query context for this extender.
-    // todo: this should return a `Result`. it currently does not because the cascade effect
-    // from this returning a `Result` will make some code returning `Option`s much uglier (we can't
-    // use `?` to return both `Option` and `Result` from the same function)
-    pub fn assert_compatible_media_context(&self, media_context: &Option<Vec<CssMediaQuery>>) {
-        if &self.media_context == media_context {
-            return;
+    pub fn assert_compatible_media_context(
+        &self,
+        media_context: &Option<Vec<MediaQuery>>,
+    ) -> SassResult<()> {
+        if self.media_context.is_none() || &self.media_context == media_context {
+            return Ok(());
         }
 
-        // Err(("You may not @extend selectors across media queries.", self.span).into())
+        Err((
+            "You may not @extend selectors across media queries.",
+            self.span,
+        )
+            .into())
     }
 
     #[allow(clippy::missing_const_for_fn)]
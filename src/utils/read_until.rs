@@ -51,6 +51,23 @@ pub(crate) fn read_until_open_curly_brace(
 
 pub(crate) fn read_until_closing_curly_brace(
     toks: &mut PeekMoreIterator<IntoIter<Token>>,
+) -> SassResult<Vec<Token>> {
+    read_block(toks, &mut false)
+}
+
+/// Like `read_until_closing_curly_brace`, but also returns whether or not the
+/// block contains an `@content` rule, for reading the body of a mixin
+pub(crate) fn read_mixin_body(
+    toks: &mut PeekMoreIterator<IntoIter<Token>>,
+) -> SassResult<(Vec<Token>, bool)> {
+    let mut contains_content_rule = false;
+    let body = read_block(toks, &mut contains_content_rule)?;
+    Ok((body, contains_content_rule))
+}
+
+fn read_block(
+    toks: &mut PeekMoreIterator<IntoIter<Token>>,
+    contains_content_rule: &mut bool,
 ) -> SassResult<Vec<Token>> {
     let mut buf = Vec::new();
     let mut nesting = 0;
@@ -79,6 +96,11 @@ pub(crate) fn read_until_closing_curly_brace(
                         read_until_newline(toks);
                         devour_whitespace(toks);
                     }
+                    Some(Token { kind: '*', .. }) => {
+                        buf.push(next);
+                        buf.push(toks.next().unwrap());
+                        read_until_comment_end(toks, &mut buf);
+                    }
                     Some(..) | None => buf.push(next),
                 };
                 continue;
@@ -87,6 +109,25 @@ pub(crate) fn read_until_closing_curly_brace(
                 buf.push(toks.next().unwrap());
                 buf.extend(read_until_closing_paren(toks)?);
             }
+            '@' => {
+                buf.push(toks.next().unwrap());
+
+                let name_start = buf.len();
+                while let Some(tok) = toks.peek() {
+                    if !(tok.kind.is_alphanumeric() || tok.kind == '-' || tok.kind == '_') {
+                        break;
+                    }
+                    buf.push(toks.next().unwrap());
+                }
+
+                if buf[name_start..]
+                    .iter()
+                    .map(|tok| tok.kind)
+                    .eq("content".chars())
+                {
+                    *contains_content_rule = true;
+                }
+            }
             '\\' => {
                 buf.push(toks.next().unwrap());
                 buf.push(match toks.next() {
@@ -101,6 +142,19 @@ pub(crate) fn read_until_closing_curly_brace(
     Ok(buf)
 }
 
+/// Read the rest of a multiline comment into `buf`, including the closing `*/`
+fn read_until_comment_end(toks: &mut PeekMoreIterator<IntoIter<Token>>, buf: &mut Vec<Token>) {
+    while let Some(tok) = toks.next() {
+        buf.push(tok);
+        if tok.kind == '*' {
+            if let Some(Token { kind: '/', .. }) = toks.peek() {
+                buf.push(toks.next().unwrap());
+                return;
+            }
+        }
+    }
+}
+
 /// Read tokens into a vector until a matching closing quote is found
 ///
 /// The closing quote is included in the output
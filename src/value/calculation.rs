@@ -0,0 +1,155 @@
+//! First-class CSS calculations, e.g. `calc()`, `min()`, `max()` and `clamp()`
+//!
+//! Calculations are simplified as much as possible while they are being
+//! evaluated. If a calculation can be fully resolved (that is, all of its
+//! arguments are numbers with compatible units) it is replaced by the
+//! resulting number; otherwise it is preserved as a `Value::Calculation`.
+//!
+//! <https://github.com/sass/sass/blob/main/proposal/plain-css-functions.md>
+
+use std::fmt;
+
+use codemap::Span;
+
+use crate::{
+    common::Op,
+    error::SassResult,
+    unit::Unit,
+    value::{Number, Value},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum CalculationName {
+    Calc,
+    Min,
+    Max,
+    Clamp,
+}
+
+impl CalculationName {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Calc => "calc",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Clamp => "clamp",
+        }
+    }
+}
+
+impl fmt::Display for CalculationName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single, already simplified, argument to a calculation
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum CalculationArg {
+    /// A `None` value for `Number` indicates a `NaN` value
+    Number(Option<Number>, Unit),
+    Calculation(SassCalculation),
+    /// An unquoted string, e.g. the result of interpolation or `var()`
+    String(String),
+    Operation(Box<CalculationArg>, Op, Box<CalculationArg>),
+}
+
+impl CalculationArg {
+    fn write(&self, buf: &mut String, span: Span) -> SassResult<()> {
+        match self {
+            CalculationArg::Number(num, unit) => {
+                if let Unit::Mul(..) | Unit::Div(..) = unit {
+                    return Err((
+                        format!(
+                            "Number {} isn't compatible with CSS calculations.",
+                            Value::Dimension(num.clone(), unit.clone(), true).inspect(span)?
+                        ),
+                        span,
+                    )
+                        .into());
+                }
+                match num {
+                    Some(num) => buf.push_str(&format!("{}{}", num, unit)),
+                    None => buf.push_str(&format!("NaN{}", unit)),
+                }
+            }
+            CalculationArg::Calculation(calc) => calc.write(buf, span)?,
+            CalculationArg::String(s) => buf.push_str(s),
+            CalculationArg::Operation(lhs, op, rhs) => {
+                let paren_left = match &**lhs {
+                    CalculationArg::Operation(_, lhs_op, _) => {
+                        lhs_op.precedence() < op.precedence()
+                    }
+                    _ => false,
+                };
+
+                if paren_left {
+                    buf.push('(');
+                }
+                lhs.write(buf, span)?;
+                if paren_left {
+                    buf.push(')');
+                }
+
+                buf.push_str(&format!(" {} ", op));
+
+                let paren_right = match &**rhs {
+                    CalculationArg::Operation(_, rhs_op, _) => {
+                        *op == Op::Div
+                            || (*op != Op::Plus && matches!(rhs_op, Op::Plus | Op::Minus))
+                    }
+                    _ => false,
+                };
+
+                if paren_right {
+                    buf.push('(');
+                }
+                rhs.write(buf, span)?;
+                if paren_right {
+                    buf.push(')');
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A calculation that could not be simplified down to a single number
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct SassCalculation {
+    pub name: CalculationName,
+    pub args: Vec<CalculationArg>,
+}
+
+impl SassCalculation {
+    pub const fn new(name: CalculationName, args: Vec<CalculationArg>) -> Self {
+        Self { name, args }
+    }
+
+    pub fn to_css_string(&self, span: Span) -> SassResult<String> {
+        let mut buf = String::new();
+        self.write(&mut buf, span)?;
+        Ok(buf)
+    }
+
+    fn write(&self, buf: &mut String, span: Span) -> SassResult<()> {
+        buf.push_str(self.name.as_str());
+        buf.push('(');
+
+        let mut args = self.args.iter();
+
+        if let Some(first) = args.next() {
+            first.write(buf, span)?;
+        }
+
+        for arg in args {
+            buf.push_str(", ");
+            arg.write(buf, span)?;
+        }
+
+        buf.push(')');
+
+        Ok(())
+    }
+}
@@ -15,15 +15,19 @@ use crate::{
     {Cow, Token},
 };
 
+pub(crate) use calculation::{CalculationArg, CalculationName, SassCalculation};
 use css_function::is_special_function;
 pub(crate) use map::SassMap;
-pub(crate) use number::Number;
+pub(crate) use number::{Number, PrecisionGuard, DEFAULT_PRECISION};
 pub(crate) use sass_function::SassFunction;
+pub(crate) use sass_mixin::SassMixin;
 
+mod calculation;
 pub(crate) mod css_function;
 mod map;
 mod number;
 mod sass_function;
+mod sass_mixin;
 
 #[derive(Debug, Clone)]
 pub(crate) enum Value {
@@ -40,6 +44,10 @@ pub(crate) enum Value {
     ArgList(Vec<Spanned<Value>>),
     /// Returned by `get-function()`
     FunctionRef(SassFunction),
+    /// Returned by `meta.get-mixin()`
+    MixinRef(SassMixin),
+    /// A calculation that could not be simplified, e.g. `calc(100% - 10px)`
+    Calculation(SassCalculation),
 }
 
 impl PartialEq for Value {
@@ -90,6 +98,13 @@ impl PartialEq for Value {
                     false
                 }
             }
+            Value::MixinRef(mixin1) => {
+                if let Value::MixinRef(mixin2) = other {
+                    mixin1 == mixin2
+                } else {
+                    false
+                }
+            }
             Value::Map(map1) => {
                 if let Value::Map(map2) = other {
                     map1 == map2
@@ -111,6 +126,13 @@ impl PartialEq for Value {
                     false
                 }
             }
+            Value::Calculation(calc1) => {
+                if let Value::Calculation(calc2) = other {
+                    calc1 == calc2
+                } else {
+                    false
+                }
+            }
         }
     }
 }
@@ -217,7 +239,7 @@ impl Value {
                     }
                 }
             },
-            Value::Map(..) | Value::FunctionRef(..) => {
+            Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                 return Err((
                     format!("{} isn't a valid CSS value.", self.inspect(span)?),
                     span,
@@ -242,6 +264,7 @@ impl Value {
                 )),
             },
             Value::Color(c) => Cow::owned(c.to_string()),
+            Value::Calculation(calc) => Cow::owned(calc.to_css_string(span)?),
             Value::String(string, QuoteKind::None) => {
                 let mut after_newline = false;
                 let mut buf = String::with_capacity(string.len());
@@ -310,6 +333,8 @@ impl Value {
             Value::Dimension(..) => "number",
             Value::List(..) => "list",
             Value::FunctionRef(..) => "function",
+            Value::MixinRef(..) => "mixin",
+            Value::Calculation(..) => "calculation",
             Value::ArgList(..) => "arglist",
             Value::True | Value::False => "bool",
             Value::Null => "null",
@@ -320,6 +345,7 @@ impl Value {
     pub fn is_special_function(&self) -> bool {
         match self {
             Value::String(s, QuoteKind::None) => is_special_function(s),
+            Value::Calculation(..) => true,
             _ => false,
         }
     }
@@ -449,6 +475,7 @@ impl Value {
                 ),
             }),
             Value::FunctionRef(f) => Cow::owned(format!("get-function(\"{}\")", f.name())),
+            Value::MixinRef(m) => Cow::owned(format!("get-mixin(\"{}\")", m.name())),
             Value::Null => Cow::const_str("null"),
             Value::Map(map) => Cow::owned(format!(
                 "({})",
@@ -479,7 +506,8 @@ impl Value {
             | Value::True
             | Value::False
             | Value::Color(..)
-            | Value::String(..) => self.to_css_string(span)?,
+            | Value::String(..)
+            | Value::Calculation(..) => self.to_css_string(span)?,
         })
     }
 
@@ -532,6 +560,8 @@ impl Value {
             options: parser.options,
             modules: parser.modules,
             module_config: parser.module_config,
+            module_cache: parser.module_cache,
+            media_queries: parser.media_queries,
         }
         .parse_selector(allows_parent, true, String::new())?
         .0)
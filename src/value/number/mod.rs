@@ -1,4 +1,5 @@
 use std::{
+    cell::Cell,
     cmp::Ordering,
     convert::{From, TryFrom},
     fmt::{self, Display, Write},
@@ -18,7 +19,32 @@ use integer::Integer;
 
 mod integer;
 
-const PRECISION: usize = 10;
+/// The number of digits after the decimal point emitted when no precision
+/// has been configured
+pub(crate) const DEFAULT_PRECISION: usize = 10;
+
+thread_local!(static PRECISION: Cell<usize> = Cell::new(DEFAULT_PRECISION));
+
+/// Sets the precision used to serialize numbers on the current thread for as
+/// long as this guard is alive
+///
+/// Numbers are serialized in many places that have no access to `Options`
+/// (e.g. error messages and `inspect()`), so the precision is stored
+/// alongside the rest of our per-thread state.
+#[derive(Debug)]
+pub(crate) struct PrecisionGuard(usize);
+
+impl PrecisionGuard {
+    pub fn new(precision: usize) -> Self {
+        Self(PRECISION.with(|p| p.replace(precision)))
+    }
+}
+
+impl Drop for PrecisionGuard {
+    fn drop(&mut self) {
+        PRECISION.with(|p| p.set(self.0));
+    }
+}
 
 #[derive(Clone, Eq, PartialEq)]
 pub(crate) enum Number {
@@ -356,12 +382,17 @@ impl ToPrimitive for Number {
 
 impl Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = PRECISION.with(Cell::get);
         let mut whole = self.to_integer().abs();
         let has_decimal = self.is_decimal();
         let mut frac = self.abs().fract();
-        let mut dec = String::with_capacity(if has_decimal { PRECISION } else { 0 });
-        if has_decimal {
-            for _ in 0..(PRECISION - 1) {
+        let mut dec = String::with_capacity(if has_decimal { precision } else { 0 });
+        if has_decimal && precision == 0 {
+            if !(frac * 2_i64).to_integer().is_zero() {
+                whole += 1;
+            }
+        } else if has_decimal {
+            for _ in 0..(precision - 1) {
                 frac *= 10_i64;
                 write!(dec, "{}", frac.to_integer())?;
                 frac = frac.fract();
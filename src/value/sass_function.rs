@@ -8,6 +8,9 @@
 //!
 //! Builtin functions are those that have been implemented in rust and are
 //! in the global scope.
+//!
+//! Custom functions are those passed to `Options::function`, and are
+//! identified by their index in the options.
 
 use std::fmt;
 
@@ -26,6 +29,7 @@ use crate::{
 pub(crate) enum SassFunction {
     Builtin(Builtin, Identifier),
     UserDefined(Box<Function>, Identifier),
+    Custom(usize, Identifier),
 }
 
 impl SassFunction {
@@ -34,7 +38,7 @@ impl SassFunction {
     /// Used mainly in debugging and `inspect()`
     pub fn name(&self) -> &Identifier {
         match self {
-            Self::Builtin(_, name) | Self::UserDefined(_, name) => name,
+            Self::Builtin(_, name) | Self::UserDefined(_, name) | Self::Custom(_, name) => name,
         }
     }
 
@@ -45,6 +49,7 @@ impl SassFunction {
         match &self {
             Self::Builtin(..) => "Builtin",
             Self::UserDefined(..) => "UserDefined",
+            Self::Custom(..) => "Custom",
         }
     }
 
@@ -52,6 +57,7 @@ impl SassFunction {
         match self {
             Self::Builtin(f, ..) => f.0(args, parser),
             Self::UserDefined(f, ..) => parser.eval_function(*f, args),
+            Self::Custom(index, ..) => parser.eval_custom_function(index, args),
         }
     }
 }
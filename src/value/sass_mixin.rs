@@ -0,0 +1,37 @@
+//! Sass mixins may be referenced as first-class values using `meta.get-mixin()`,
+//! in the same way that `get-function()` returns a reference to a function
+//!
+//! Mixin references can later be included using `meta.apply()`.
+
+use crate::{atrule::mixin::Mixin, common::Identifier};
+
+/// A reference to a mixin
+///
+/// The mixin name is stored in addition to the body
+/// for use in the builtin function `inspect()`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct SassMixin {
+    mixin: Mixin,
+    name: Identifier,
+}
+
+impl SassMixin {
+    pub const fn new(mixin: Mixin, name: Identifier) -> Self {
+        Self { mixin, name }
+    }
+
+    /// Get the name of the mixin referenced
+    ///
+    /// Used mainly in debugging and `inspect()`
+    pub const fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    pub fn accepts_content(&self) -> bool {
+        self.mixin.accepts_content()
+    }
+
+    pub fn into_mixin(self) -> Mixin {
+        self.mixin
+    }
+}
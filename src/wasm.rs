@@ -0,0 +1,229 @@
+//! The JavaScript API, exported using wasm-bindgen
+//!
+//! JavaScript values can't be shared between threads, so importers and
+//! custom functions are only required to be `Send` and `Sync` when not
+//! compiling to wasm.
+
+use std::{io, path::Path};
+
+use codemap::CodeMap;
+use js_sys::{Array, Error as JsError, Function, Object, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::{
+    builtin::modules::ModuleCache, compile_string_with, CompileResult, CustomFunction, Error,
+    Importer, ImporterResult, Options, OutputStyle,
+};
+
+/// Compiles `source` as SCSS
+///
+/// `options` may be `undefined`, or an object with any of the following
+/// properties:
+///
+/// - `style`: must be `"expanded"`, the default. Compressed output is not yet
+///   supported, so `"compressed"` is an error rather than being ignored
+/// - `quiet`: if `true`, warnings are not emitted
+/// - `charset`: if `false`, neither `@charset` nor a byte-order mark is
+///   emitted
+/// - `importers`: an array of functions that are called with the URL of a
+///   stylesheet that couldn't be found and the path of the file that loaded
+///   it. Each returns either `null` or an object with the `contents` of the
+///   stylesheet and, optionally, its `path`
+/// - `functions`: an object whose keys are function signatures, such as
+///   `"double($number)"`, and whose values are functions that take an array
+///   of arguments as Sass source and return a Sass expression
+///
+/// Errors are thrown as an `Error` whose `message` doesn't include the
+/// location of the error. Instead, the `line`, `column`, and `file` are
+/// properties of the error if it has a location, and `formatted` is the
+/// error as printed by the command line interface.
+#[wasm_bindgen(js_name = compile)]
+pub fn compile(source: String, options: JsValue) -> Result<String, JsValue> {
+    let options = options_from_js(&options)?;
+
+    compile_string_with(
+        &mut CodeMap::new(),
+        source,
+        &options,
+        &mut ModuleCache::default(),
+    )
+    .map(CompileResult::into_css)
+    .map_err(|e| error_to_js(&e))
+}
+
+/// Compiles `source` as SCSS using the default options, throwing the
+/// formatted error as a string
+///
+/// @deprecated Use `compile` instead, which accepts options and throws errors
+/// with their location.
+#[wasm_bindgen(js_name = from_string)]
+pub fn from_string(source: String) -> Result<String, JsValue> {
+    compile(source, JsValue::UNDEFINED).map_err(|e| property(&e, "formatted").unwrap_or(e))
+}
+
+fn options_from_js(options: &JsValue) -> Result<Options, JsValue> {
+    let mut result = Options::default();
+
+    if options.is_undefined() || options.is_null() {
+        return Ok(result);
+    }
+
+    let style = property(options, "style")?;
+    if !style.is_undefined() {
+        result = result.style(match style.as_string().as_deref() {
+            Some("expanded") => OutputStyle::Expanded,
+            Some("compressed") => {
+                return Err(JsError::new("Compressed output is not yet supported.").into())
+            }
+            _ => return Err(JsError::new("style must be \"expanded\".").into()),
+        });
+    }
+
+    if let Some(quiet) = property(options, "quiet")?.as_bool() {
+        result = result.quiet(quiet);
+    }
+
+    if let Some(charset) = property(options, "charset")?.as_bool() {
+        result = result.allows_charset(charset);
+    }
+
+    let importers = property(options, "importers")?;
+    if !importers.is_undefined() {
+        let importers = importers
+            .dyn_into::<Array>()
+            .map_err(|_| JsError::new("importers must be an array."))?;
+
+        for importer in importers.iter() {
+            let importer = importer
+                .dyn_into::<Function>()
+                .map_err(|_| JsError::new("importers must be functions."))?;
+            result = result.importer(Box::new(JsImporter(importer)));
+        }
+    }
+
+    let functions = property(options, "functions")?;
+    if !functions.is_undefined() {
+        let functions = functions
+            .dyn_into::<Object>()
+            .map_err(|_| JsError::new("functions must be an object."))?;
+
+        for entry in Object::entries(&functions).iter() {
+            let entry = Array::from(&entry);
+            let signature = entry.get(0).as_string().unwrap_or_default();
+            let function = entry
+                .get(1)
+                .dyn_into::<Function>()
+                .map_err(|_| JsError::new(&format!("{} must be a function.", signature)))?;
+            result = result
+                .function(&signature, Box::new(JsFunction(function)))
+                .map_err(|e| error_to_js(&e))?;
+        }
+    }
+
+    Ok(result)
+}
+
+fn property(target: &JsValue, key: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(target, &JsValue::from_str(key))
+}
+
+/// The message of a value thrown by JavaScript
+fn js_error_message(error: &JsValue) -> String {
+    match error.dyn_ref::<JsError>() {
+        Some(error) => String::from(error.message()),
+        None => error.as_string().unwrap_or_else(|| format!("{:?}", error)),
+    }
+}
+
+fn error_to_js(error: &Error) -> JsValue {
+    let js_error = JsError::new(&error.message());
+
+    // setting a property of a newly created object can't fail
+    let _ = Reflect::set(
+        &js_error,
+        &JsValue::from_str("formatted"),
+        &JsValue::from_str(&error.to_string()),
+    );
+
+    if let Some(line) = error.line() {
+        let _ = Reflect::set(
+            &js_error,
+            &JsValue::from_str("line"),
+            &JsValue::from_f64(line as f64),
+        );
+    }
+
+    if let Some(column) = error.column() {
+        let _ = Reflect::set(
+            &js_error,
+            &JsValue::from_str("column"),
+            &JsValue::from_f64(column as f64),
+        );
+    }
+
+    if let Some(file) = error.file_name() {
+        let _ = Reflect::set(
+            &js_error,
+            &JsValue::from_str("file"),
+            &JsValue::from_str(file),
+        );
+    }
+
+    js_error.into()
+}
+
+/// An `Importer` implemented by a JavaScript function
+#[derive(Debug)]
+struct JsImporter(Function);
+
+impl Importer for JsImporter {
+    fn import(&self, url: &str, from: &Path) -> io::Result<Option<ImporterResult>> {
+        let to_io_error = |e: JsValue| io::Error::new(io::ErrorKind::Other, js_error_message(&e));
+
+        let result = self
+            .0
+            .call2(
+                &JsValue::NULL,
+                &JsValue::from_str(url),
+                &JsValue::from_str(&from.to_string_lossy()),
+            )
+            .map_err(to_io_error)?;
+
+        if result.is_undefined() || result.is_null() {
+            return Ok(None);
+        }
+
+        let contents = property(&result, "contents")
+            .map_err(to_io_error)?
+            .as_string()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "The contents of an imported stylesheet must be a string.",
+                )
+            })?;
+
+        let path = property(&result, "path")
+            .map_err(to_io_error)?
+            .as_string()
+            .unwrap_or_else(|| url.to_owned());
+
+        Ok(Some(ImporterResult::new(path, contents)))
+    }
+}
+
+/// A `CustomFunction` implemented by a JavaScript function
+#[derive(Debug)]
+struct JsFunction(Function);
+
+impl CustomFunction for JsFunction {
+    fn call(&self, args: &[String]) -> Result<String, String> {
+        let args: Array = args.iter().map(|arg| JsValue::from_str(arg)).collect();
+
+        self.0
+            .call1(&JsValue::NULL, &args)
+            .map_err(|e| js_error_message(&e))?
+            .as_string()
+            .ok_or_else(|| "Custom functions must return a string.".to_owned())
+    }
+}
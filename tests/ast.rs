@@ -0,0 +1,344 @@
+use std::io::Write;
+
+use grass::ast::{
+    BinaryOperator, ExpressionKind, Import, InterpolationPart, ListSeparator, Span, Statement,
+    StatementKind,
+};
+
+#[macro_use]
+mod macros;
+
+fn parse(input: &str) -> Vec<Statement> {
+    grass::parse_string(input.to_string()).unwrap().statements
+}
+
+#[test]
+fn style_rule_with_declaration() {
+    let input = "a {\n  color: red;\n}";
+    let statements = parse(input);
+    assert_eq!(Span { start: 0, end: 19 }, statements[0].span);
+    match &statements[0].kind {
+        StatementKind::StyleRule { selector, body } => {
+            assert_eq!(Some("a"), selector.as_plain());
+            assert_eq!(Span { start: 6, end: 16 }, body[0].span);
+            match &body[0].kind {
+                StatementKind::Declaration { name, value, body } => {
+                    assert_eq!(Some("color"), name.as_plain());
+                    assert!(body.is_empty());
+                    let value = value.as_ref().unwrap();
+                    assert_eq!("red", &input[value.span.start..value.span.end]);
+                }
+                kind => panic!("unexpected statement {:?}", kind),
+            }
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn pseudo_class_selector_is_not_declaration() {
+    let statements = parse("a:hover { color: red; }");
+    match &statements[0].kind {
+        StatementKind::StyleRule { selector, .. } => {
+            assert_eq!(Some("a:hover"), selector.as_plain());
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn nested_properties() {
+    let statements = parse("a { font: bold { family: serif; } }");
+    match &statements[0].kind {
+        StatementKind::StyleRule { body, .. } => match &body[0].kind {
+            StatementKind::Declaration { name, value, body } => {
+                assert_eq!(Some("font"), name.as_plain());
+                assert!(value.is_some());
+                assert_eq!(1, body.len());
+            }
+            kind => panic!("unexpected statement {:?}", kind),
+        },
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn variable_declaration_flags() {
+    let statements = parse("$a: 1px + 2px !default !global;");
+    match &statements[0].kind {
+        StatementKind::VariableDeclaration {
+            namespace,
+            name,
+            value,
+            guarded,
+            global,
+        } => {
+            assert_eq!(&None, namespace);
+            assert_eq!("a", name);
+            assert!(*guarded);
+            assert!(*global);
+            match &value.kind {
+                ExpressionKind::BinaryOperation { operator, .. } => {
+                    assert_eq!(BinaryOperator::Plus, *operator);
+                }
+                kind => panic!("unexpected expression {:?}", kind),
+            }
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn namespaced_variable_declaration() {
+    let statements = parse("a.$b: c;");
+    match &statements[0].kind {
+        StatementKind::VariableDeclaration {
+            namespace, name, ..
+        } => {
+            assert_eq!(&Some("a".to_string()), namespace);
+            assert_eq!("b", name);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn operator_precedence() {
+    let statements = parse("$a: 1 + 2 * 3;");
+    match &statements[0].kind {
+        StatementKind::VariableDeclaration { value, .. } => match &value.kind {
+            ExpressionKind::BinaryOperation { operator, rhs, .. } => {
+                assert_eq!(BinaryOperator::Plus, *operator);
+                assert!(matches!(
+                    rhs.kind,
+                    ExpressionKind::BinaryOperation {
+                        operator: BinaryOperator::Times,
+                        ..
+                    }
+                ));
+            }
+            kind => panic!("unexpected expression {:?}", kind),
+        },
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn comma_and_space_lists() {
+    let statements = parse("$a: 1px 2px, 3px -4px;");
+    match &statements[0].kind {
+        StatementKind::VariableDeclaration { value, .. } => match &value.kind {
+            ExpressionKind::List {
+                elements,
+                separator,
+                bracketed,
+            } => {
+                assert_eq!(ListSeparator::Comma, *separator);
+                assert!(!bracketed);
+                assert_eq!(2, elements.len());
+                for element in elements {
+                    assert!(matches!(
+                        element.kind,
+                        ExpressionKind::List {
+                            separator: ListSeparator::Space,
+                            ..
+                        }
+                    ));
+                }
+            }
+            kind => panic!("unexpected expression {:?}", kind),
+        },
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn map_literal() {
+    let statements = parse("$a: (b: 1, c: 2);");
+    match &statements[0].kind {
+        StatementKind::VariableDeclaration { value, .. } => match &value.kind {
+            ExpressionKind::Map(pairs) => assert_eq!(2, pairs.len()),
+            kind => panic!("unexpected expression {:?}", kind),
+        },
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn interpolated_selector() {
+    let statements = parse(".a-#{$b} { c: d; }");
+    match &statements[0].kind {
+        StatementKind::StyleRule { selector, .. } => {
+            assert_eq!(None, selector.as_plain());
+            assert!(matches!(
+                selector.contents.as_slice(),
+                [InterpolationPart::Text(_), InterpolationPart::Expression(_)]
+            ));
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn use_and_forward() {
+    let statements = parse("@use \"a\" as b with ($c: 1);\n@forward \"d\" as e-* hide $f, g;");
+    match &statements[0].kind {
+        StatementKind::Use {
+            url,
+            namespace,
+            configuration,
+        } => {
+            assert_eq!("a", url);
+            assert_eq!(&Some("b".to_string()), namespace);
+            assert_eq!("c", configuration[0].name);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    match &statements[1].kind {
+        StatementKind::Forward {
+            url, prefix, hide, ..
+        } => {
+            assert_eq!("d", url);
+            assert_eq!(&Some("e-".to_string()), prefix);
+            assert_eq!(&Some(vec!["$f".to_string(), "g".to_string()]), hide);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn import_is_not_followed() {
+    let statements = parse("@import \"does-not-exist\", \"a.css\", url(b);");
+    match &statements[0].kind {
+        StatementKind::Import(imports) => {
+            assert!(matches!(&imports[0], Import::Dynamic { url, .. } if url == "does-not-exist"));
+            assert!(matches!(&imports[1], Import::Static(..)));
+            assert!(matches!(&imports[2], Import::Static(..)));
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn mixin_and_include() {
+    let statements = parse(
+        "@mixin a($b, $c: 1, $d...) { @content; }\nx { @include a(1, $c: 2) using ($e) { y: z; } }",
+    );
+    match &statements[0].kind {
+        StatementKind::Mixin {
+            name, parameters, ..
+        } => {
+            assert_eq!("a", name);
+            assert_eq!(2, parameters.parameters.len());
+            assert!(parameters.parameters[1].default.is_some());
+            assert_eq!(Some("d".to_string()), parameters.rest);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    match &statements[1].kind {
+        StatementKind::StyleRule { body, .. } => match &body[0].kind {
+            StatementKind::Include {
+                name,
+                arguments,
+                content_parameters,
+                content,
+                ..
+            } => {
+                assert_eq!("a", name);
+                assert_eq!(1, arguments.positional.len());
+                assert_eq!("c", arguments.named[0].0);
+                assert!(content_parameters.is_some());
+                assert_eq!(1, content.as_ref().unwrap().len());
+            }
+            kind => panic!("unexpected statement {:?}", kind),
+        },
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+}
+
+#[test]
+fn control_flow() {
+    let statements = parse(
+        "@if $a { b: c; } @else if $d { e: f; } @else { g: h; }\n@for $i from 1 through 3 {}\n@each $k, $v in $map {}\n@while $a {}",
+    );
+    match &statements[0].kind {
+        StatementKind::If { clauses, else_body } => {
+            assert_eq!(2, clauses.len());
+            assert!(else_body.is_some());
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    assert!(matches!(
+        statements[1].kind,
+        StatementKind::For {
+            inclusive: true,
+            ..
+        }
+    ));
+    match &statements[2].kind {
+        StatementKind::Each { variables, .. } => {
+            assert_eq!(&vec!["k".to_string(), "v".to_string()], variables);
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    assert!(matches!(statements[3].kind, StatementKind::While { .. }));
+}
+
+#[test]
+fn comments() {
+    let statements = parse("// silent\n/* loud */");
+    assert_eq!(
+        StatementKind::SilentComment("// silent".to_string()),
+        statements[0].kind
+    );
+    assert!(matches!(statements[1].kind, StatementKind::LoudComment(..)));
+}
+
+#[test]
+fn unknown_at_rule() {
+    let statements = parse("@keyframes a { from { b: c; } }\n@charset \"utf-8\";");
+    match &statements[0].kind {
+        StatementKind::AtRule {
+            name,
+            prelude,
+            body,
+        } => {
+            assert_eq!("keyframes", name);
+            assert_eq!(Some("a"), prelude.as_ref().unwrap().as_plain());
+            assert!(body.is_some());
+        }
+        kind => panic!("unexpected statement {:?}", kind),
+    }
+    assert!(matches!(
+        &statements[1].kind,
+        StatementKind::AtRule { body: None, .. }
+    ));
+}
+
+#[test]
+fn parse_path() {
+    tempfile!(
+        "ast_parse_path.scss",
+        "@use \"does-not-exist\";\na { b: c; }"
+    );
+    let stylesheet = grass::parse_path("ast_parse_path.scss").unwrap();
+    assert_eq!(2, stylesheet.statements.len());
+}
+
+#[test]
+fn missing_semicolon_is_error() {
+    let err = grass::parse_string("a { b: c d: e }".to_string()).unwrap_err();
+    assert_eq!(
+        "Error: expected \";\".",
+        err.to_string().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn unmatched_brace_is_error() {
+    let err = grass::parse_string("a { b: c; } }".to_string()).unwrap_err();
+    assert_eq!(
+        "Error: unmatched \"}\".",
+        err.to_string().lines().next().unwrap()
+    );
+}
@@ -71,3 +71,73 @@ error!(
     missing_closing_curly_brace,
     "@at-root {", "Error: expected \"}\"."
 );
+test!(
+    without_media_inside_style_rule,
+    "@media screen {\n  a {\n    @at-root (without: media) {\n      color: red;\n    }\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    without_media_nested_style_rule,
+    "@media screen {\n  a {\n    @at-root (without: media) {\n      b {\n        color: red;\n      }\n    }\n  }\n}\n",
+    "a b {\n  color: red;\n}\n"
+);
+test!(
+    without_media_media_inside_style_rule,
+    "a {\n  @media screen {\n    @at-root (without: media) {\n      color: red;\n    }\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    with_media_removes_style_rule,
+    "@media screen {\n  a {\n    @at-root (with: media) {\n      b {\n        color: red;\n      }\n    }\n  }\n}\n",
+    "@media screen {\n  b {\n    color: red;\n  }\n}\n"
+);
+error!(
+    with_media_declaration_outside_style_rule,
+    "@media screen {\n  a {\n    @at-root (with: media) {\n      color: red;\n    }\n  }\n}\n",
+    "Error: Declarations may only be used within style rules."
+);
+test!(
+    without_all,
+    "@media screen {\n  a {\n    @at-root (without: all) {\n      b {\n        color: red;\n      }\n    }\n  }\n}\n",
+    "b {\n  color: red;\n}\n"
+);
+test!(
+    without_supports_keeps_media,
+    "@supports (display: grid) {\n  @media screen {\n    a {\n      @at-root (without: supports) {\n        color: red;\n      }\n    }\n  }\n}\n",
+    "@media screen {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    with_supports_removes_media,
+    "@supports (display: grid) {\n  @media screen {\n    a {\n      @at-root (with: supports) {\n        b {\n          color: red;\n        }\n      }\n    }\n  }\n}\n",
+    "@supports (display: grid) {\n  b {\n    color: red;\n  }\n}\n"
+);
+test!(
+    without_unknown_at_rule,
+    "@foo {\n  a {\n    @at-root (without: foo) {\n      color: red;\n    }\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    with_rule_keeps_style_rule,
+    "a {\n  @at-root (with: rule) {\n    color: red;\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    with_rule_after_without_rule_keeps_style_rule,
+    "@media screen {\n  a {\n    @at-root (without: rule) {\n      x {\n        e: f;\n      }\n    }\n    @at-root (with: rule) {\n      g: h;\n    }\n  }\n}\n",
+    "@media screen {\n  x {\n    e: f;\n  }\n}\na {\n  g: h;\n}\n"
+);
+test!(
+    explicit_without_rule_followed_by_declaration,
+    "@media screen {\n  a {\n    @at-root (without: rule) {\n      x {\n        e: f;\n      }\n    }\n    k: l;\n  }\n}\n",
+    "@media screen {\n  a {\n    k: l;\n  }\n  x {\n    e: f;\n  }\n}\n"
+);
+test!(
+    default_query_inside_media_stays_in_media,
+    "@media screen {\n  a {\n    color: blue;\n    @at-root {\n      b {\n        color: red;\n      }\n    }\n  }\n}\n",
+    "@media screen {\n  a {\n    color: blue;\n  }\n  b {\n    color: red;\n  }\n}\n"
+);
+error!(
+    query_neither_with_nor_without,
+    "a {\n  @at-root (foo: media) {\n    color: red;\n  }\n}\n",
+    "Error: expected \"with\" or \"without\"."
+);
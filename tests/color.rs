@@ -735,7 +735,7 @@ error!(
 test!(
     rgba_special_fn_4th_arg_max,
     "a {\n  color: rgba(1 2 max(3, 3));\n}\n",
-    "a {\n  color: rgba(1, 2, max(3, 3));\n}\n"
+    "a {\n  color: #010203;\n}\n"
 );
 test!(
     #[ignore = "we do not check if interpolation occurred"]
@@ -0,0 +1,86 @@
+use std::io::Write;
+
+#[macro_use]
+mod macros;
+
+#[test]
+fn compile_string_css() {
+    let input = "a { b { color: red; } }";
+    let result = grass::compile_string(input.to_string(), &grass::Options::default()).unwrap();
+    assert_eq!("a b {\n  color: red;\n}\n", result.css());
+    assert_eq!(None, result.source_map());
+    assert!(result.loaded_urls().is_empty());
+    assert!(result.warnings().is_empty());
+}
+
+#[test]
+fn compile_string_loaded_urls() {
+    let input = "@use \"compile_string_loaded_urls__a\";\n@import \"compile_string_loaded_urls__b\";\n@import \"compile_string_loaded_urls__b\";";
+    tempfile!("compile_string_loaded_urls__a.scss", "a { color: red; }");
+    tempfile!("compile_string_loaded_urls__b.scss", "b { color: red; }");
+    let result = grass::compile_string(input.to_string(), &grass::Options::default()).unwrap();
+    assert_eq!(
+        vec![
+            std::fs::canonicalize("compile_string_loaded_urls__a.scss").unwrap(),
+            std::fs::canonicalize("compile_string_loaded_urls__b.scss").unwrap(),
+        ],
+        result.loaded_urls()
+    );
+}
+
+#[test]
+fn compile_string_load_css_loaded_urls() {
+    let input = "@use \"sass:meta\";\na { @include meta.load-css(\"compile_string_load_css_loaded_urls__a\"); }";
+    tempfile!(
+        "compile_string_load_css_loaded_urls__a.scss",
+        "b { color: red; }"
+    );
+    let result = grass::compile_string(input.to_string(), &grass::Options::default()).unwrap();
+    assert_eq!(
+        vec![std::fs::canonicalize("compile_string_load_css_loaded_urls__a.scss").unwrap()],
+        result.loaded_urls()
+    );
+}
+
+#[test]
+fn compile_path_includes_entrypoint() {
+    tempfile!(
+        "compile_path_includes_entrypoint.scss",
+        "@import \"compile_path_includes_entrypoint__a\";"
+    );
+    tempfile!(
+        "compile_path_includes_entrypoint__a.scss",
+        "a { color: red; }"
+    );
+    let result = grass::compile_path(
+        "compile_path_includes_entrypoint.scss",
+        &grass::Options::default(),
+    )
+    .unwrap();
+    assert_eq!("a {\n  color: red;\n}\n", result.css());
+    assert_eq!(
+        vec![
+            std::fs::canonicalize("compile_path_includes_entrypoint.scss").unwrap(),
+            std::fs::canonicalize("compile_path_includes_entrypoint__a.scss").unwrap(),
+        ],
+        result.loaded_urls()
+    );
+}
+
+#[test]
+fn compile_string_collects_warnings() {
+    let input = "@warn \"foo\";\na { color: red; }";
+    let result = grass::compile_string(input.to_string(), &grass::Options::default()).unwrap();
+    assert_eq!(
+        vec!["Warning: \"foo\"\n    stdin 1:7  root stylesheet".to_string()],
+        result.warnings()
+    );
+}
+
+#[test]
+fn compile_string_quiet_has_no_warnings() {
+    let input = "@warn \"foo\";";
+    let result =
+        grass::compile_string(input.to_string(), &grass::Options::default().quiet(true)).unwrap();
+    assert!(result.warnings().is_empty());
+}
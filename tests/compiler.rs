@@ -0,0 +1,145 @@
+use std::{
+    fs::{self, OpenOptions},
+    time::{Duration, SystemTime},
+};
+
+fn set_modified(path: &std::path::Path, time: SystemTime) {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+#[test]
+fn compiler_reuses_unmodified_files() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("compiler_reuses_unmodified_files")
+        .tempdir_in("")
+        .unwrap();
+    let partial = root.path().join("_a.scss");
+    let input = root.path().join("input.scss");
+    let time = SystemTime::now() - Duration::from_secs(60);
+    fs::write(&partial, "a { color: red; }").unwrap();
+    fs::write(&input, "@import \"a\";").unwrap();
+    set_modified(&partial, time);
+
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    let path = input.to_str().unwrap();
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        compiler.compile_path(path).unwrap().css()
+    );
+
+    fs::write(&partial, "a { color: blue; }").unwrap();
+    set_modified(&partial, time);
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        compiler.compile_path(path).unwrap().css()
+    );
+
+    compiler.invalidate(&partial);
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        compiler.compile_path(path).unwrap().css()
+    );
+}
+
+#[test]
+fn compiler_rereads_modified_files() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("compiler_rereads_modified_files")
+        .tempdir_in("")
+        .unwrap();
+    let partial = root.path().join("_a.scss");
+    let time = SystemTime::now() - Duration::from_secs(60);
+    fs::write(&partial, "$a: red;").unwrap();
+    set_modified(&partial, time);
+
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    let input = "@use \"compiler_rereads_modified_files/a\" as a;\na { color: a.$a; }";
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        compiler.compile_string(input.to_string()).unwrap().css()
+    );
+
+    fs::write(&partial, "$a: blue;").unwrap();
+    set_modified(&partial, time + Duration::from_secs(1));
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        compiler.compile_string(input.to_string()).unwrap().css()
+    );
+}
+
+#[test]
+fn compiler_invalidate_all() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("compiler_invalidate_all")
+        .tempdir_in("")
+        .unwrap();
+    let partial = root.path().join("_a.scss");
+    let time = SystemTime::now() - Duration::from_secs(60);
+    fs::write(&partial, "a { color: red; }").unwrap();
+    set_modified(&partial, time);
+
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    let input = "@import \"compiler_invalidate_all/a\";";
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        compiler.compile_string(input.to_string()).unwrap().css()
+    );
+
+    fs::write(&partial, "a { color: blue; }").unwrap();
+    set_modified(&partial, time);
+    compiler.invalidate_all();
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        compiler.compile_string(input.to_string()).unwrap().css()
+    );
+}
+
+#[test]
+fn compiler_error_after_successful_compilation() {
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    assert!(compiler.compile_string("a { b: c; }".to_string()).is_ok());
+    match compiler.compile_string("a { b: $c; }".to_string()) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e.to_string().starts_with("Error: Undefined variable.")),
+    }
+    assert!(compiler.compile_string("a { b: c; }".to_string()).is_ok());
+}
+
+#[test]
+fn compiler_repeated_compilation_of_cached_file() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("compiler_repeated_compilation_of_cached_file")
+        .tempdir_in("")
+        .unwrap();
+    let partial = root.path().join("_a.scss");
+    let input = root.path().join("input.scss");
+    fs::write(&partial, "a {\n  color: red;\n  width: $b;\n}\n").unwrap();
+    fs::write(&input, "@import \"a\";").unwrap();
+    set_modified(&partial, SystemTime::now() - Duration::from_secs(60));
+
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    let path = input.to_str().unwrap();
+    for _ in 0..1000 {
+        match compiler.compile_path(path) {
+            Ok(..) => panic!("did not fail"),
+            Err(e) => {
+                assert!(e.to_string().starts_with("Error: Undefined variable."));
+                assert_eq!(Some(3), e.line());
+                assert_eq!(Some(10), e.column());
+            }
+        }
+    }
+}
@@ -1394,7 +1394,6 @@ test!(
     "@media screen {\n  @unknown {\n    .foo, .bar {\n      a: b;\n    }\n  }\n}\n"
 );
 test!(
-    #[ignore = "media queries are not yet parsed correctly"]
     extend_within_separate_media_queries,
     "@media screen {.foo {a: b}}
     @media screen {.bar {@extend .foo}}
@@ -1563,7 +1562,6 @@ test!(
     ".parent1 .child {\n  a: b;\n}\n"
 );
 test!(
-    #[ignore = "media queries are not yet parsed correctly"]
     extend_inside_double_nested_media,
     "@media all {
         @media (orientation: landscape) {
@@ -1894,6 +1892,36 @@ error!(
     }",
     "Error: Parent selectors aren't allowed here."
 );
+error!(
+    extend_target_not_found,
+    ".foo {@extend .bar}", "Error: The target selector was not found."
+);
+error!(
+    extend_target_not_found_with_other_satisfied_extend,
+    ".bar {color: red}\n.foo {@extend .bar; @extend .baz}",
+    "Error: The target selector was not found."
+);
+test!(
+    extend_media_selector_from_outside_media,
+    "@media screen {.foo {a: b}}
+    .bar {@extend .foo}
+    ",
+    "@media screen {\n  .foo, .bar {\n    a: b;\n  }\n}\n"
+);
+error!(
+    extend_across_media_queries,
+    "@media screen {.foo {a: b}}
+    @media print {.bar {@extend .foo}}
+    ",
+    "Error: From line 1, column 16 of stdin:"
+);
+error!(
+    extend_top_level_selector_from_within_media,
+    ".foo {a: b}
+    @media screen {.bar {@extend .foo}}
+    ",
+    "Error: From line 1, column 1 of stdin:"
+);
 
 // todo: extend_loop (massive test)
 // todo: extend tests in folders
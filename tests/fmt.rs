@@ -0,0 +1,124 @@
+use std::io::Write;
+
+#[macro_use]
+mod macros;
+
+macro_rules! fmt {
+    ($func:ident, $input:expr, $output:expr) => {
+        #[test]
+        fn $func() {
+            let formatted = grass::format_string($input.to_string()).unwrap();
+            assert_eq!(String::from($output), formatted);
+            assert_eq!(
+                formatted,
+                grass::format_string(formatted.clone()).unwrap(),
+                "formatting is not idempotent"
+            );
+        }
+    };
+}
+
+fmt!(
+    indentation_and_spacing,
+    "a{color:red;b  {  c : d }}",
+    "a {\n  color: red;\n  b {\n    c: d;\n  }\n}\n"
+);
+fmt!(
+    one_declaration_per_line,
+    "a { b: c; d: e; }",
+    "a {\n  b: c;\n  d: e;\n}\n"
+);
+fmt!(empty_block, "a {   }", "a {}\n");
+fmt!(
+    variable_flags,
+    "$a:1px+2px!default   !global;",
+    "$a: 1px + 2px !default !global;\n"
+);
+fmt!(
+    normalizes_quotes,
+    "a { b: 'c'; d: 'e\"f'; g: 'h\\'i'; }",
+    "a {\n  b: \"c\";\n  d: 'e\"f';\n  g: \"h'i\";\n}\n"
+);
+fmt!(
+    use_and_include,
+    "@use 'sass:math' as m;\n@mixin a($b,$c:1){d:$b}\ne{@include a( 1 , $c : 2 )}",
+    "@use \"sass:math\" as m;\n@mixin a($b, $c: 1) {\n  d: $b;\n}\ne {\n  @include a(1, $c: 2);\n}\n"
+);
+fmt!(
+    control_flow,
+    "@if $a==1{b{c:d}}@else if $a{e{f:g}}@else{h{i:j}}",
+    "@if $a == 1 {\n  b {\n    c: d;\n  }\n} @else if $a {\n  e {\n    f: g;\n  }\n} @else {\n  h {\n    i: j;\n  }\n}\n"
+);
+fmt!(
+    maps_and_lists,
+    "$a : ( b:1 ,c : 2 );\n$d: 1px  2px ,3px;",
+    "$a: (b: 1, c: 2);\n$d: 1px 2px, 3px;\n"
+);
+fmt!(
+    collapses_blank_lines,
+    "a {\n  b: c;\n\n\n\n  d: e;\n}\n\n\nf {}",
+    "a {\n  b: c;\n\n  d: e;\n}\n\nf {}\n"
+);
+fmt!(
+    preserves_newlines_in_selector_lists,
+    "a,\n   b ,c {d: e}",
+    "a,\nb, c {\n  d: e;\n}\n"
+);
+fmt!(
+    preserves_whitespace_around_division,
+    "a { b: 1/2; c: 1 / 2; }",
+    "a {\n  b: 1/2;\n  c: 1 / 2;\n}\n"
+);
+fmt!(
+    keeps_comments_between_statements,
+    "// a\n/* b */\nc {\n  // d\n  e: f; // g\n  /* h */\n}",
+    "// a\n/* b */\nc {\n  // d\n  e: f; // g\n  /* h */\n}\n"
+);
+fmt!(
+    moves_trailing_silent_comment_after_semicolon,
+    "a {\n  b: c // d\n}",
+    "a {\n  b: c; // d\n}\n"
+);
+fmt!(
+    moves_trailing_silent_comment_after_brace,
+    "a // b\n{ c: d }",
+    "a { // b\n  c: d;\n}\n"
+);
+fmt!(
+    keeps_code_with_inner_comments_as_written,
+    "a {\n     b:  c  /* d */  e;\n}",
+    "a {\n  b:  c  /* d */  e;\n}\n"
+);
+fmt!(
+    custom_property_value_is_unchanged,
+    "a { --b:   {  c : d } }",
+    "a {\n  --b: {  c : d };\n}\n"
+);
+
+#[test]
+fn formatted_css_is_unchanged() {
+    let input = "a,\nb{c:1px+2px;/* d */\n@media screen{e:f}}";
+    let formatted = grass::format_string(input.to_string()).unwrap();
+    assert_eq!(
+        grass::from_string(input.to_string(), &grass::Options::default()).unwrap(),
+        grass::from_string(formatted, &grass::Options::default()).unwrap()
+    );
+}
+
+#[test]
+fn format_path() {
+    tempfile!("fmt_format_path.scss", "a{b:c}");
+    assert_eq!(
+        "a {\n  b: c;\n}\n",
+        grass::format_path("fmt_format_path.scss").unwrap()
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let err = grass::format_string("a { b: c d: e }".to_string()).unwrap_err();
+    assert_eq!(
+        "Error: expected \";\".",
+        err.to_string().lines().next().unwrap()
+    );
+}
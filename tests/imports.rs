@@ -146,6 +146,127 @@ fn chained_imports_in_directory() {
     );
 }
 
+#[test]
+fn import_ambiguous_partial() {
+    let input = "@import \"import_ambiguous_partial\";";
+    tempfile!("import_ambiguous_partial.scss", "a { color: red; }");
+    tempfile!("_import_ambiguous_partial.scss", "a { color: blue; }");
+    assert_err!("Error: It's not clear which file to import. Found:", input);
+}
+
+#[test]
+fn import_prefers_scss_over_css() {
+    let input = "@import \"import_prefers_scss_over_css\";";
+    tempfile!("import_prefers_scss_over_css.scss", "a { color: red; }");
+    tempfile!("import_prefers_scss_over_css.css", "a { color: blue; }");
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn import_directory_index_css() {
+    let input = "@import \"import_directory_index_css\";";
+    tempfile!(
+        "index.css",
+        "a { color: red; }",
+        dir = "import_directory_index_css"
+    );
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn import_sass_file_in_load_path_subdirectory() {
+    let input = "@import \"sub/foo\";";
+    let _root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("import_sass_file_in_load_path_subdirectory")
+        .tempdir_in("")
+        .unwrap();
+    let _sub = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("sub")
+        .tempdir_in("import_sass_file_in_load_path_subdirectory")
+        .unwrap();
+    let mut f = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("_foo.scss")
+        .tempfile_in("import_sass_file_in_load_path_subdirectory/sub")
+        .unwrap();
+    write!(f, "a {{ color: red; }}").unwrap();
+
+    let load_path = std::path::Path::new("import_sass_file_in_load_path_subdirectory");
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(
+            input.to_string(),
+            &grass::Options::default().load_path(load_path)
+        )
+        .expect(input)
+    );
+}
+
+#[test]
+fn import_prefers_import_only_file() {
+    let input = "@import \"import_prefers_import_only_file\";";
+    tempfile!("_import_prefers_import_only_file.scss", "a { color: red; }");
+    tempfile!(
+        "_import_prefers_import_only_file.import.scss",
+        "a { color: blue; }"
+    );
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn import_prefers_import_only_file_with_extension() {
+    let input = "@import \"import_prefers_import_only_file_with_extension.scss\";";
+    tempfile!(
+        "import_prefers_import_only_file_with_extension.scss",
+        "a { color: red; }"
+    );
+    tempfile!(
+        "import_prefers_import_only_file_with_extension.import.scss",
+        "a { color: blue; }"
+    );
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn import_plain_css_file_without_extension() {
+    let input = "@import \"import_plain_css_file_without_extension\";\na {\n color: red;\n}";
+    tempfile!(
+        "import_plain_css_file_without_extension.css",
+        "@import \"foo\";\nb { color: blue; }"
+    );
+    assert_eq!(
+        "@import \"foo\";\nb {\n  color: blue;\n}\n\na {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn import_plain_css_file_with_interpolation() {
+    let input = "@import \"import_plain_css_file_with_interpolation\";";
+    tempfile!(
+        "import_plain_css_file_with_interpolation.css",
+        "a { color: #{red}; }"
+    );
+    assert_err!("Error: Interpolation isn't allowed in plain CSS.", input);
+}
+
 error!(
     missing_input_after_import,
     "@import", "Error: expected more input."
@@ -212,3 +333,112 @@ test!(
 // todo: edge case tests for plain css imports moved to top
 // todo: test for calling paths, e.g. `grass b\index.scss`
 // todo: test for absolute paths (how?)
+
+#[test]
+fn import_node_module_package_json_sass_field() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("import_node_module_package_json_sass_field")
+        .tempdir_in("")
+        .unwrap();
+    let package = root.path().join("node_modules/foo");
+    std::fs::create_dir_all(package.join("scss")).unwrap();
+    std::fs::write(
+        package.join("package.json"),
+        "{\"name\": \"foo\", \"files\": [\"scss\"], \"style\": \"foo.css\", \"sass\": \"scss/_foo.scss\"}",
+    )
+    .unwrap();
+    std::fs::write(package.join("scss/_foo.scss"), "a { color: red; }").unwrap();
+    std::fs::create_dir_all(root.path().join("src")).unwrap();
+    std::fs::write(root.path().join("src/input.scss"), "@import \"foo\";").unwrap();
+
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_path(
+            "import_node_module_package_json_sass_field/src/input.scss",
+            &grass::Options::default().resolve_node_modules(true)
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn import_node_module_package_json_style_field() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("import_node_module_package_json_style_field")
+        .tempdir_in("")
+        .unwrap();
+    let package = root.path().join("node_modules/foo");
+    std::fs::create_dir_all(&package).unwrap();
+    std::fs::write(
+        package.join("package.json"),
+        "{\"style\": \"dist/foo.css\"}",
+    )
+    .unwrap();
+    std::fs::create_dir_all(package.join("dist")).unwrap();
+    std::fs::write(package.join("dist/foo.css"), "a { color: red; }").unwrap();
+    std::fs::write(root.path().join("input.scss"), "@use \"foo\";").unwrap();
+
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_path(
+            "import_node_module_package_json_style_field/input.scss",
+            &grass::Options::default().resolve_node_modules(true)
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn import_node_module_scoped_package_subpath() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("import_node_module_scoped_package_subpath")
+        .tempdir_in("")
+        .unwrap();
+    let package = root.path().join("node_modules/@scope/foo");
+    std::fs::create_dir_all(package.join("scss")).unwrap();
+    std::fs::write(package.join("scss/_bar.scss"), "a { color: red; }").unwrap();
+    std::fs::write(
+        root.path().join("input.scss"),
+        "@import \"@scope/foo/scss/bar\";",
+    )
+    .unwrap();
+
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_path(
+            "import_node_module_scoped_package_subpath/input.scss",
+            &grass::Options::default().resolve_node_modules(true)
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn import_node_module_not_resolved_by_default() {
+    let root = tempfile::Builder::new()
+        .rand_bytes(0)
+        .prefix("")
+        .suffix("import_node_module_not_resolved_by_default")
+        .tempdir_in("")
+        .unwrap();
+    let package = root.path().join("node_modules/foo");
+    std::fs::create_dir_all(&package).unwrap();
+    std::fs::write(package.join("_index.scss"), "a { color: red; }").unwrap();
+    std::fs::write(root.path().join("input.scss"), "@use \"foo\";").unwrap();
+
+    match grass::from_path(
+        "import_node_module_not_resolved_by_default/input.scss",
+        &grass::Options::default(),
+    ) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e
+            .to_string()
+            .starts_with("Error: Can't find stylesheet to import.")),
+    }
+}
@@ -13,6 +13,19 @@ macro_rules! test {
             );
         }
     };
+    ($( #[$attr:meta] ),*$func:ident, $input:expr, $output:expr, $options:expr) => {
+        $(#[$attr])*
+        #[test]
+        #[allow(non_snake_case)]
+        fn $func() {
+            let sass = grass::from_string($input.to_string(), &$options)
+                .expect(concat!("failed to parse on ", $input));
+            assert_eq!(
+                String::from($output),
+                sass
+            );
+        }
+    };
 }
 
 /// Verify the error *message*
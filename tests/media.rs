@@ -117,3 +117,48 @@ error!(
     media_feature_missing_curly_brace_after_hash,
     "@media foo and # {}", "Error: expected \"{\"."
 );
+test!(
+    nested_media_merges_queries,
+    "@media screen {\n  @media (min-width: 10px) {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    "@media screen and (min-width: 10px) {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    nested_media_inside_style_rule_merges_queries,
+    "@media (max-width: 20px) {\n  a {\n    @media screen and (min-width: 10px) {\n      color: red;\n    }\n  }\n}\n",
+    "@media screen and (max-width: 20px) and (min-width: 10px) {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    nested_media_is_emitted_after_outer_media,
+    "@media screen {\n  a {\n    color: blue;\n    @media (color) {\n      color: red;\n    }\n    b: c;\n  }\n}\n",
+    "@media screen {\n  a {\n    color: blue;\n    b: c;\n  }\n}\n@media screen and (color) {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    nested_media_different_types_is_removed,
+    "@media screen {\n  a {\n    color: blue;\n    @media print {\n      color: red;\n    }\n  }\n}\n",
+    "@media screen {\n  a {\n    color: blue;\n  }\n}\n"
+);
+test!(
+    nested_media_negated_same_type_is_removed,
+    "@media not screen {\n  @media screen {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    ""
+);
+test!(
+    nested_media_unrepresentable_is_not_merged,
+    "@media not screen {\n  @media (color) {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    "@media not screen {\n  @media (color) {\n    a {\n      color: red;\n    }\n  }\n}\n"
+);
+test!(
+    nested_media_merges_each_query_in_list,
+    "@media screen, print {\n  @media (color) {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    "@media screen and (color), print and (color) {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    nested_media_keeps_modifier,
+    "@media only screen {\n  @media (color) {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    "@media only screen and (color) {\n  a {\n    color: red;\n  }\n}\n"
+);
+test!(
+    nested_media_all_type_is_omitted,
+    "$q: \"screen and (color)\";\n@media #{$q} {\n  @media all {\n    a {\n      color: red;\n    }\n  }\n}\n",
+    "@media screen and (color) {\n  a {\n    color: red;\n  }\n}\n"
+);
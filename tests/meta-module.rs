@@ -6,7 +6,7 @@ mod macros;
 test!(
     module_functions_builtin,
     "@use 'sass:meta';\na {\n  color: inspect(meta.module-functions(meta));\n}\n",
-    "a {\n  color: (\"feature-exists\": get-function(\"feature-exists\"), \"inspect\": get-function(\"inspect\"), \"type-of\": get-function(\"type-of\"), \"keywords\": get-function(\"keywords\"), \"global-variable-exists\": get-function(\"global-variable-exists\"), \"variable-exists\": get-function(\"variable-exists\"), \"function-exists\": get-function(\"function-exists\"), \"mixin-exists\": get-function(\"mixin-exists\"), \"content-exists\": get-function(\"content-exists\"), \"module-variables\": get-function(\"module-variables\"), \"module-functions\": get-function(\"module-functions\"), \"get-function\": get-function(\"get-function\"), \"call\": get-function(\"call\"));\n}\n"
+    "a {\n  color: (\"feature-exists\": get-function(\"feature-exists\"), \"inspect\": get-function(\"inspect\"), \"type-of\": get-function(\"type-of\"), \"keywords\": get-function(\"keywords\"), \"global-variable-exists\": get-function(\"global-variable-exists\"), \"variable-exists\": get-function(\"variable-exists\"), \"function-exists\": get-function(\"function-exists\"), \"mixin-exists\": get-function(\"mixin-exists\"), \"content-exists\": get-function(\"content-exists\"), \"module-variables\": get-function(\"module-variables\"), \"module-functions\": get-function(\"module-functions\"), \"module-mixins\": get-function(\"module-mixins\"), \"get-function\": get-function(\"get-function\"), \"get-mixin\": get-function(\"get-mixin\"), \"accepts-content\": get-function(\"accepts-content\"), \"call\": get-function(\"call\"));\n}\n"
 );
 test!(
     module_variables_builtin,
@@ -29,6 +29,115 @@ error!(
     "Error: There is no module with the namespace \"d\"."
 );
 
+test!(
+    get_mixin_type_of,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  color: meta.type-of(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: mixin;\n}\n"
+);
+test!(
+    get_mixin_inspect,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  color: meta.inspect(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: get-mixin(\"foo\");\n}\n"
+);
+test!(
+    get_mixin_equality,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  color: meta.get-mixin(foo) == meta.get-mixin(foo);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+error!(
+    get_mixin_not_found,
+    "@use 'sass:meta';\na {\n  color: meta.get-mixin(foo);\n}\n", "Error: Mixin not found: foo"
+);
+error!(
+    get_mixin_as_css_value,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  color: meta.get-mixin(foo);\n}\n",
+    "Error: get-mixin(\"foo\") isn't a valid CSS value."
+);
+test!(
+    accepts_content_without_content_rule,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  color: meta.accepts-content(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    accepts_content_with_content_rule,
+    "@use 'sass:meta';\n@mixin foo { @content; }\na {\n  color: meta.accepts-content(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    accepts_content_content_rule_in_string,
+    "@use 'sass:meta';\n@mixin foo { content: \"@content\"; }\na {\n  color: meta.accepts-content(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    accepts_content_content_rule_in_comment,
+    "@use 'sass:meta';\n@mixin foo {\n  // @content;\n  /* @content; */\n}\na {\n  color: meta.accepts-content(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    accepts_content_nested_content_rule,
+    "@use 'sass:meta';\n@mixin foo { @if false { b { @content; } } }\na {\n  color: meta.accepts-content(meta.get-mixin(foo));\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+error!(
+    apply_content_rule_in_string,
+    "@use 'sass:meta';\n@mixin foo { content: \"@content\"; }\na {\n  @include meta.apply(meta.get-mixin(foo)) { b: c; }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+test!(
+    accepts_content_builtin,
+    "@use 'sass:meta';\na {\n  color: meta.accepts-content(meta.get-mixin(apply, meta));\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+error!(
+    accepts_content_not_mixin,
+    "@use 'sass:meta';\na {\n  color: meta.accepts-content(foo);\n}\n",
+    "Error: $mixin: foo is not a mixin reference."
+);
+test!(
+    apply_with_args,
+    "@use 'sass:meta';\n@mixin foo($a, $b: 2) {\n  a: $a;\n  b: $b;\n}\na {\n  @include meta.apply(meta.get-mixin(foo), 1, $b: 3);\n}\n",
+    "a {\n  a: 1;\n  b: 3;\n}\n"
+);
+test!(
+    apply_forwards_content_block,
+    "@use 'sass:meta';\n@mixin foo {\n  @content;\n}\na {\n  @include meta.apply(meta.get-mixin(foo)) {\n    color: red;\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    apply_dynamically_chosen_mixin,
+    "@use 'sass:meta';\n@mixin small {\n  size: 1px;\n}\n@mixin large {\n  size: 2px;\n}\n$mixins: (small: meta.get-mixin(small), large: meta.get-mixin(large));\na {\n  @include meta.apply(map-get($mixins, large));\n}\n",
+    "a {\n  size: 2px;\n}\n"
+);
+error!(
+    apply_content_block_not_accepted,
+    "@use 'sass:meta';\n@mixin foo {}\na {\n  @include meta.apply(meta.get-mixin(foo)) {\n    color: red;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+error!(
+    apply_not_mixin,
+    "@use 'sass:meta';\na {\n  @include meta.apply(foo);\n}\n",
+    "Error: $mixin: foo is not a mixin reference."
+);
+#[test]
+fn module_mixins() {
+    let input = "@use \"sass:meta\";\n@use \"module_mixins\" as module;\na {\n color: inspect(meta.module-mixins(module));\n}";
+    tempfile!("module_mixins.scss", "@mixin foo {}\n@mixin -bar {}");
+    assert_eq!(
+        "a {\n  color: (\"foo\": get-mixin(\"foo\"));\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn get_mixin_from_module() {
+    let input = "@use \"sass:meta\";\n@use \"get_mixin_from_module\" as module;\na {\n @include meta.apply(meta.get-mixin(foo, module));\n}";
+    tempfile!("get_mixin_from_module.scss", "@mixin foo { color: red; }");
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
 #[test]
 fn mixin_exists_module() {
     let input = "@use \"mixin_exists_module\" as module;\na {\n color: mixin-exists(foo, $module: module);\n}";
@@ -2,19 +2,19 @@
 mod macros;
 
 test!(
-    min_not_evaluated_units_percent,
+    min_evaluated_units_percent,
     "a {\n  color: min(1%, 2%);\n}\n",
-    "a {\n  color: min(1%, 2%);\n}\n"
+    "a {\n  color: 1%;\n}\n"
 );
 test!(
-    min_not_evaluated_units_px,
+    min_evaluated_units_px,
     "a {\n  color: min(1px, 2px);\n}\n",
-    "a {\n  color: min(1px, 2px);\n}\n"
+    "a {\n  color: 1px;\n}\n"
 );
 test!(
-    min_not_evaluated_no_units,
+    min_evaluated_no_units,
     "a {\n  color: min(1, 2);\n}\n",
-    "a {\n  color: min(1, 2);\n}\n"
+    "a {\n  color: 1;\n}\n"
 );
 test!(
     min_not_evaluated_incompatible_units,
@@ -44,26 +44,34 @@ error!(
     min_too_few_args,
     "a {\n  color: min();\n}\n", "Error: At least one argument must be passed."
 );
-// note: we explicitly have units in the opposite order of `dart-sass`.
-// see https://github.com/sass/dart-sass/issues/766
+test!(
+    min_possibly_compatible_units_is_calculation,
+    "$a: 1px;\n$b: 2%;\na {\n  color: min($a, $b);\n}\n",
+    "a {\n  color: min(1px, 2%);\n}\n"
+);
 error!(
     min_incompatible_units,
-    "$a: 1px;\n$b: 2%;\na {\n  color: min($a, $b);\n}\n", "Error: Incompatible units px and %."
+    "a {\n  color: min(1px, 2s);\n}\n", "Error: 1px and 2s are incompatible."
+);
+test!(
+    min_falls_back_to_sass_function,
+    "$a: (1px, 2px);\na {\n  color: min($a...);\n}\n",
+    "a {\n  color: 1px;\n}\n"
 );
 test!(
-    max_not_evaluated_units_percent,
+    max_evaluated_units_percent,
     "a {\n  color: max(1%, 2%);\n}\n",
-    "a {\n  color: max(1%, 2%);\n}\n"
+    "a {\n  color: 2%;\n}\n"
 );
 test!(
-    max_not_evaluated_units_px,
+    max_evaluated_units_px,
     "a {\n  color: max(1px, 2px);\n}\n",
-    "a {\n  color: max(1px, 2px);\n}\n"
+    "a {\n  color: 2px;\n}\n"
 );
 test!(
-    max_not_evaluated_no_units,
+    max_evaluated_no_units,
     "a {\n  color: max(1, 2);\n}\n",
-    "a {\n  color: max(1, 2);\n}\n"
+    "a {\n  color: 2;\n}\n"
 );
 test!(
     max_not_evaluated_incompatible_units,
@@ -98,10 +106,17 @@ error!(
     max_too_few_args,
     "a {\n  color: max();\n}\n", "Error: At least one argument must be passed."
 );
-// note: we explicitly have units in the opposite order of `dart-sass`.
-// see https://github.com/sass/dart-sass/issues/766
+test!(
+    max_possibly_compatible_units_is_calculation,
+    "$a: 1px;\n$b: 2%;\na {\n  color: max($a, $b);\n}\n",
+    "a {\n  color: max(1px, 2%);\n}\n"
+);
 error!(
     max_incompatible_units,
-    "$a: 1px;\n$b: 2%;\na {\n  color: max($a, $b);\n}\n", "Error: Incompatible units px and %."
+    "a {\n  color: max(1px, 2s);\n}\n", "Error: 1px and 2s are incompatible."
+);
+test!(
+    max_falls_back_to_sass_function,
+    "$a: (1px, 2px);\na {\n  color: max($a...);\n}\n",
+    "a {\n  color: 2px;\n}\n"
 );
-// todo: special functions, min(calc(1), $b);
@@ -603,3 +603,13 @@ error!(
     }",
     "Error: expected \"{\"."
 );
+error!(
+    include_content_block_not_accepted,
+    "@mixin foo {\n  color: red;\n}\n\na {\n  @include foo {\n    color: blue;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+error!(
+    include_content_block_with_content_rule_in_comment,
+    "@mixin foo {\n  /* @content; { */\n  color: red;\n}\n\na {\n  @include foo {\n    color: blue;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
@@ -49,32 +49,32 @@ error!(
 );
 test!(
     unitless_nan_min_first_arg,
-    "$n: (0/0);\na {\n  color: min($n, 1px);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.min($n, 1px);\n}\n",
     "a {\n  color: NaN;\n}\n"
 );
 test!(
     unitless_nan_min_last_arg,
-    "$n: (0/0);\na {\n  color: min(1px, $n);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.min(1px, $n);\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 test!(
     unitless_nan_min_middle_arg,
-    "$n: (0/0);\na {\n  color: min(1px, $n, 0);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.min(1px, $n, 0);\n}\n",
     "a {\n  color: 0;\n}\n"
 );
 test!(
     unitless_nan_max_first_arg,
-    "$n: (0/0);\na {\n  color: max($n, 1px);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.max($n, 1px);\n}\n",
     "a {\n  color: NaN;\n}\n"
 );
 test!(
     unitless_nan_max_last_arg,
-    "$n: (0/0);\na {\n  color: max(1px, $n);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.max(1px, $n);\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 test!(
     unitless_nan_max_middle_arg,
-    "$n: (0/0);\na {\n  color: max(1px, $n, 0);\n}\n",
+    "@use \"sass:math\";\n$n: (0/0);\na {\n  color: math.max(1px, $n, 0);\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 error!(
@@ -121,32 +121,32 @@ error!(
 );
 test!(
     unitful_nan_min_first_arg,
-    "@use \"sass:math\";\na {\n  color: min(math.acos(2), 1px);\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.min(math.acos(2), 1px);\n}\n",
     "a {\n  color: NaNdeg;\n}\n"
 );
 test!(
     unitful_nan_min_last_arg,
-    "@use \"sass:math\";\na {\n  color: min(1px, math.acos(2));\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.min(1px, math.acos(2));\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 test!(
     unitful_nan_min_middle_arg,
-    "@use \"sass:math\";\na {\n  color: min(1px, math.acos(2), 0);\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.min(1px, math.acos(2), 0);\n}\n",
     "a {\n  color: 0;\n}\n"
 );
 test!(
     unitful_nan_max_first_arg,
-    "@use \"sass:math\";\na {\n  color: max(math.acos(2), 1px);\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.max(math.acos(2), 1px);\n}\n",
     "a {\n  color: NaNdeg;\n}\n"
 );
 test!(
     unitful_nan_max_last_arg,
-    "@use \"sass:math\";\na {\n  color: max(1px, math.acos(2));\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.max(1px, math.acos(2));\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 test!(
     unitful_nan_max_middle_arg,
-    "@use \"sass:math\";\na {\n  color: max(1px, math.acos(2), 0);\n}\n",
+    "@use \"sass:math\";\na {\n  color: math.max(1px, math.acos(2), 0);\n}\n",
     "a {\n  color: 1px;\n}\n"
 );
 error!(
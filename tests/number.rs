@@ -189,3 +189,27 @@ error!(
     scientific_notation_too_negative,
     "a {\n  color: 1e-100;\n}\n", "Error: Exponent too negative."
 );
+test!(
+    precision_five,
+    "a {\n  color: (2 / 3);\n}\n",
+    "a {\n  color: 0.66667;\n}\n",
+    grass::Options::default().precision(5)
+);
+test!(
+    precision_five_rounds_up_to_whole_number,
+    "a {\n  color: 1.999999;\n}\n",
+    "a {\n  color: 2;\n}\n",
+    grass::Options::default().precision(5)
+);
+test!(
+    precision_affects_inspect,
+    "a {\n  color: inspect(1.123456789px);\n}\n",
+    "a {\n  color: 1.12346px;\n}\n",
+    grass::Options::default().precision(5)
+);
+test!(
+    precision_zero,
+    "a {\n  color: 1.5 2.4;\n}\n",
+    "a {\n  color: 2 2;\n}\n",
+    grass::Options::default().precision(0)
+);
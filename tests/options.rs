@@ -0,0 +1,648 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+#[macro_use]
+mod macros;
+
+test!(
+    global_variable_unquoted_string,
+    "a {\n  color: $theme;\n}\n",
+    "a {\n  color: dark;\n}\n",
+    grass::Options::default().global_variable_source("theme", "dark")
+);
+test!(
+    global_variable_number,
+    "a {\n  width: $gutter * 2px;\n}\n",
+    "a {\n  width: 20px;\n}\n",
+    grass::Options::default().global_variable_source("gutter", 10)
+);
+test!(
+    global_variable_bool,
+    "a {\n  @if $dark { color: black; }\n}\n",
+    "a {\n  color: black;\n}\n",
+    grass::Options::default().global_variable_source("dark", true)
+);
+test!(
+    global_variable_expression,
+    "a {\n  color: type-of($map);\n  width: map-get($map, b);\n}\n",
+    "a {\n  color: map;\n  width: 2px;\n}\n",
+    grass::Options::default().global_variable_source("map", "(a: 1px, b: 1px + 1px)")
+);
+test!(
+    global_variable_leading_dollar_sign,
+    "a {\n  color: $theme;\n}\n",
+    "a {\n  color: dark;\n}\n",
+    grass::Options::default().global_variable_source("$theme", "dark")
+);
+test!(
+    global_variable_replaces_earlier_value,
+    "a {\n  color: $theme;\n}\n",
+    "a {\n  color: light;\n}\n",
+    grass::Options::default()
+        .global_variable_source("theme", "dark")
+        .global_variable_source("theme", "light")
+);
+test!(
+    global_variable_is_not_overridden_by_default,
+    "$theme: light !default;\na {\n  color: $theme;\n}\n",
+    "a {\n  color: dark;\n}\n",
+    grass::Options::default().global_variable_source("theme", "dark")
+);
+test!(
+    global_variable_is_overridden_by_declaration,
+    "$theme: light;\na {\n  color: $theme;\n}\n",
+    "a {\n  color: light;\n}\n",
+    grass::Options::default().global_variable_source("theme", "dark")
+);
+test!(
+    global_variable_source_unquoted_words,
+    "a {\n  color: length($font);\n}\n",
+    "a {\n  color: 2;\n}\n",
+    grass::Options::default().global_variable_source("font", "Helvetica Neue")
+);
+test!(
+    global_variable_string,
+    "a {\n  font-family: $font;\n  color: length($font);\n}\n",
+    "a {\n  font-family: \"Helvetica Neue\";\n  color: 1;\n}\n",
+    grass::Options::default().global_variable_string("font", "Helvetica Neue")
+);
+test!(
+    global_variable_string_is_not_parsed,
+    "a {\n  content: $text;\n}\n",
+    "a {\n  content: 'say \"hi\"; $x';\n}\n",
+    grass::Options::default().global_variable_string("$text", "say \"hi\"; $x")
+);
+test!(
+    global_variable_string_replaces_source,
+    "a {\n  color: $theme;\n}\n",
+    "a {\n  color: \"dark\";\n}\n",
+    grass::Options::default()
+        .global_variable_source("theme", "dark")
+        .global_variable_string("theme", "dark")
+);
+test!(
+    prepend_variable,
+    "a {\n  color: $primary;\n}\n",
+    "a {\n  color: red;\n}\n",
+    grass::Options::default().prepend("$primary: red;", "config")
+);
+test!(
+    prepend_trailing_silent_comment,
+    "a {\n  color: $primary;\n}\n",
+    "a {\n  color: red;\n}\n",
+    grass::Options::default().prepend("$primary: red; // comment", "config")
+);
+test!(
+    prepend_sees_global_variable,
+    "a {\n  color: $primary;\n}\n",
+    "a {\n  color: blue;\n}\n",
+    grass::Options::default()
+        .global_variable_source("theme", "dark")
+        .prepend("$primary: if($theme == dark, blue, red);", "config")
+);
+test!(
+    prepend_emits_css_first,
+    "b {\n  color: red;\n}\n",
+    "a {\n  color: red;\n}\n\nb {\n  color: red;\n}\n",
+    grass::Options::default().prepend("a { color: red; }", "config")
+);
+
+#[test]
+fn prepend_use() {
+    tempfile!("options_prepend_use__a.scss", "$primary: red;");
+    let options =
+        grass::Options::default().prepend("@use \"options_prepend_use__a\" as a;", "config");
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        grass::from_string("a { color: a.$primary; }".to_string(), &options).unwrap()
+    );
+}
+
+#[test]
+fn prepend_use_before_entry_use() {
+    tempfile!("options_prepend_use_before_entry_use__a.scss", "$a: red;");
+    tempfile!("options_prepend_use_before_entry_use__b.scss", "$b: blue;");
+    let options = grass::Options::default().prepend(
+        "@use \"options_prepend_use_before_entry_use__a\" as a;",
+        "config",
+    );
+    assert_eq!(
+        "a {\n  color: red;\n  background: blue;\n}\n",
+        grass::from_string(
+            "@use \"options_prepend_use_before_entry_use__b\" as b;\na { color: a.$a; background: b.$b; }"
+                .to_string(),
+            &options
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn prepend_is_not_applied_to_plain_css() {
+    tempfile!(
+        "options_prepend_is_not_applied_to_plain_css.css",
+        "a { color: red; }"
+    );
+    let options = grass::Options::default().prepend("$a: b;", "config");
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        grass::from_path("options_prepend_is_not_applied_to_plain_css.css", &options).unwrap()
+    );
+}
+
+#[test]
+fn global_variable_not_visible_in_used_module() {
+    tempfile!(
+        "options_global_variable_not_visible_in_used_module__a.scss",
+        "a { color: $theme; }"
+    );
+    let options = grass::Options::default().global_variable_source("theme", "dark");
+    let err = grass::from_string(
+        "@use \"options_global_variable_not_visible_in_used_module__a\";".to_string(),
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        "Error: Undefined variable.",
+        err.to_string().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn global_variable_with_trailing_input() {
+    let options = grass::Options::default().global_variable_source("theme", "red; garbage {");
+    let err = grass::from_string("a { color: $theme; }".to_string(), &options).unwrap_err();
+    assert_eq!(
+        "Error: expected no more input.",
+        err.to_string().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn error_in_prepended_source_uses_its_name() {
+    let options = grass::Options::default().prepend("$a: 1px + 1em;", "config");
+    let err = grass::from_string("a { b: c; }".to_string(), &options).unwrap_err();
+    let err = err.to_string();
+    assert_eq!(
+        "Error: Incompatible units em and px.",
+        err.lines().next().unwrap()
+    );
+    assert!(err.contains("./config:1:5"), "{}", err);
+}
+
+#[test]
+fn error_in_global_variable_uses_its_name() {
+    let options = grass::Options::default().global_variable_source("theme", "1px + 1em");
+    let err = grass::from_string("a { b: c; }".to_string(), &options).unwrap_err();
+    let err = err.to_string();
+    assert_eq!(
+        "Error: Incompatible units em and px.",
+        err.lines().next().unwrap()
+    );
+    assert!(err.contains("./$theme:1:1"), "{}", err);
+}
+
+fn error_message(input: &str, options: &grass::Options) -> String {
+    grass::from_string(input.to_string(), options)
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn max_call_depth_recursive_mixin() {
+    let err = error_message(
+        "@mixin a {\n  @include a;\n}\nb {\n  @include a;\n}\n",
+        &grass::Options::default().max_call_depth(10),
+    );
+    assert_eq!(
+        "Error: Maximum call depth (10) exceeded.",
+        err.lines().next().unwrap()
+    );
+}
+
+#[test]
+fn max_call_depth_recursive_function() {
+    let err = error_message(
+        "@function a($n) {\n  @return a($n + 1);\n}\nb {\n  c: a(0);\n}\n",
+        &grass::Options::default().max_call_depth(10),
+    );
+    assert_eq!(
+        "Error: Maximum call depth (10) exceeded.",
+        err.lines().next().unwrap()
+    );
+}
+
+test!(
+    max_call_depth_not_exceeded,
+    "@function a($n) {\n  @if $n == 0 {\n    @return 0;\n  }\n  @return a($n - 1);\n}\nb {\n  c: a(10);\n}\n",
+    "b {\n  c: 0;\n}\n",
+    grass::Options::default().max_call_depth(11)
+);
+test!(
+    max_call_depth_counts_nesting_not_calls,
+    "@function a() {\n  @return 1;\n}\nb {\n  @for $i from 1 through 20 {\n    c: a();\n  }\n}\n",
+    "b {\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n  c: 1;\n}\n",
+    grass::Options::default().max_call_depth(1)
+);
+
+#[test]
+fn max_loop_iterations_infinite_while() {
+    let err = error_message(
+        "$a: 0;\n@while $a < 1 {\n  $b: 0;\n}\n",
+        &grass::Options::default().max_loop_iterations(100),
+    );
+    assert_eq!(
+        "Error: Maximum number of loop iterations (100) exceeded.",
+        err.lines().next().unwrap()
+    );
+    assert!(err.contains("2 │ @while $a < 1 {"), "{}", err);
+}
+
+#[test]
+fn max_loop_iterations_counts_all_loops() {
+    let err = error_message(
+        "@for $i from 1 through 3 {}\n@each $a in 1 2 3 {}\n",
+        &grass::Options::default().max_loop_iterations(5),
+    );
+    assert_eq!(
+        "Error: Maximum number of loop iterations (5) exceeded.",
+        err.lines().next().unwrap()
+    );
+    assert!(err.contains("2 │ @each $a in 1 2 3 {}"), "{}", err);
+}
+
+#[test]
+fn max_loop_iterations_nested_for() {
+    let err = error_message(
+        "@for $i from 1 through 10 {\n  @for $j from 1 through 10 {}\n}\n",
+        &grass::Options::default().max_loop_iterations(50),
+    );
+    assert_eq!(
+        "Error: Maximum number of loop iterations (50) exceeded.",
+        err.lines().next().unwrap()
+    );
+}
+
+test!(
+    max_loop_iterations_not_exceeded,
+    "a {\n  @for $i from 1 through 3 {\n    b: $i;\n  }\n}\n",
+    "a {\n  b: 1;\n  b: 2;\n  b: 3;\n}\n",
+    grass::Options::default().max_loop_iterations(3)
+);
+
+#[test]
+fn max_output_size_exceeded() {
+    let err = error_message(
+        "a {\n  b: c;\n}\n",
+        &grass::Options::default().max_output_size(10),
+    );
+    assert_eq!(
+        "Error: Maximum output size (10 bytes) exceeded.",
+        err.lines().next().unwrap()
+    );
+}
+
+test!(
+    max_output_size_not_exceeded,
+    "a {\n  b: c;\n}\n",
+    "a {\n  b: c;\n}\n",
+    grass::Options::default().max_output_size(14)
+);
+
+#[test]
+fn owned_load_paths() {
+    tempfile!(
+        "_owned_load_paths.scss",
+        "a { color: red; }",
+        dir = "options_owned_load_paths"
+    );
+    let options = grass::Options::default()
+        .load_path(String::from("does_not_exist"))
+        .load_paths(&[std::path::PathBuf::from("options_owned_load_paths")]);
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        grass::from_string("@import \"owned_load_paths\";".to_string(), &options).unwrap()
+    );
+}
+
+#[test]
+fn options_outlive_their_configuration() {
+    fn options() -> grass::Options {
+        let load_path = format!("options_outlive_their_{}", "configuration");
+        grass::Options::default().load_path(&load_path)
+    }
+
+    tempfile!(
+        "_a.scss",
+        "a { color: red; }",
+        dir = "options_outlive_their_configuration"
+    );
+    let options = options();
+    let css = std::thread::spawn(move || {
+        grass::from_string("@import \"a\";".to_string(), &options).unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!("a {\n  color: red;\n}\n", css);
+}
+
+#[derive(Debug, Default)]
+struct CollectingLogger {
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl grass::Logger for CollectingLogger {
+    fn warn(&self, message: &str) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(format!("warn: {}", message));
+    }
+
+    fn debug(&self, message: &str) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(format!("debug: {}", message));
+    }
+}
+
+#[test]
+fn logger_receives_warnings_and_debug() {
+    let logger = CollectingLogger::default();
+    let messages = Arc::clone(&logger.messages);
+    let options = grass::Options::default().logger(Box::new(logger));
+    grass::from_string("@warn foo;\n@debug 1 + 1;".to_string(), &options).unwrap();
+    assert_eq!(
+        vec![
+            "warn: Warning: foo\n    stdin 1:7  root stylesheet".to_string(),
+            "debug: stdin:2 Debug: 2".to_string(),
+        ],
+        *messages.lock().unwrap()
+    );
+}
+
+#[test]
+fn logger_is_silenced_by_quiet() {
+    let logger = CollectingLogger::default();
+    let messages = Arc::clone(&logger.messages);
+    let options = grass::Options::default()
+        .logger(Box::new(logger))
+        .quiet(true);
+    grass::from_string("@warn foo;\n@debug 1 + 1;".to_string(), &options).unwrap();
+    assert!(messages.lock().unwrap().is_empty());
+}
+
+#[test]
+fn logger_does_not_receive_collected_warnings() {
+    let logger = CollectingLogger::default();
+    let messages = Arc::clone(&logger.messages);
+    let options = grass::Options::default().logger(Box::new(logger));
+    let result = grass::compile_string("@warn foo;".to_string(), &options).unwrap();
+    assert_eq!(1, result.warnings().len());
+    assert!(messages.lock().unwrap().is_empty());
+}
+
+#[derive(Debug)]
+struct ThemeImporter;
+
+impl grass::Importer for ThemeImporter {
+    fn import(&self, url: &str, _from: &Path) -> io::Result<Option<grass::ImporterResult>> {
+        Ok(match url {
+            "theme" => Some(grass::ImporterResult::new(
+                "theme.scss",
+                "@use \"colors\";\n$primary: colors.$red;",
+            )),
+            "colors" => Some(grass::ImporterResult::new("colors.scss", "$red: #f00;")),
+            "plain" => Some(grass::ImporterResult::new("plain.css", "a { b: c; }")),
+            "broken" => return Err(io::Error::new(io::ErrorKind::Other, "theme server is down")),
+            _ => None,
+        })
+    }
+}
+
+test!(
+    importer_use,
+    "@use \"theme\";\na {\n  color: theme.$primary;\n}\n",
+    "a {\n  color: #f00;\n}\n",
+    grass::Options::default().importer(Box::new(ThemeImporter))
+);
+test!(
+    importer_import,
+    "@import \"theme\";\na {\n  color: $primary;\n}\n",
+    "a {\n  color: #f00;\n}\n",
+    grass::Options::default().importer(Box::new(ThemeImporter))
+);
+test!(
+    importer_plain_css,
+    "@use \"plain\";\n",
+    "a {\n  b: c;\n}\n",
+    grass::Options::default().importer(Box::new(ThemeImporter))
+);
+
+#[test]
+fn importer_prefers_relative_file() {
+    tempfile!("importer_prefers_relative_file.scss", "$primary: blue;");
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        grass::from_string(
+            "@use \"importer_prefers_relative_file\" as theme;\na { color: theme.$primary; }"
+                .to_string(),
+            &grass::Options::default().importer(Box::new(ThemeImporter))
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn importer_unrecognized_url() {
+    let err = grass::from_string(
+        "@use \"unknown\";".to_string(),
+        &grass::Options::default().importer(Box::new(ThemeImporter)),
+    )
+    .unwrap_err();
+    assert_eq!("Can't find stylesheet to import.", err.message());
+}
+
+#[test]
+fn importer_error() {
+    let err = grass::from_string(
+        "@use \"broken\";".to_string(),
+        &grass::Options::default().importer(Box::new(ThemeImporter)),
+    )
+    .unwrap_err();
+    assert_eq!("theme server is down", err.message());
+    assert_eq!(Some(1), err.line());
+}
+
+#[derive(Debug)]
+struct Double;
+
+impl grass::CustomFunction for Double {
+    fn call(&self, args: &[String]) -> Result<String, String> {
+        Ok(format!("{} * 2", args[0]))
+    }
+}
+
+#[derive(Debug)]
+struct Join;
+
+impl grass::CustomFunction for Join {
+    fn call(&self, args: &[String]) -> Result<String, String> {
+        Ok(format!("\"{}\"", args.join("|")))
+    }
+}
+
+#[derive(Debug)]
+struct SourceLength;
+
+impl grass::CustomFunction for SourceLength {
+    fn call(&self, args: &[String]) -> Result<String, String> {
+        Ok(args[0].len().to_string())
+    }
+}
+
+#[derive(Debug)]
+struct Fail;
+
+impl grass::CustomFunction for Fail {
+    fn call(&self, _: &[String]) -> Result<String, String> {
+        Err("custom failure".to_string())
+    }
+}
+
+test!(
+    custom_function,
+    "a {\n  width: double(2px);\n}\n",
+    "a {\n  width: 4px;\n}\n",
+    grass::Options::default()
+        .function("double($number)", Box::new(Double))
+        .unwrap()
+);
+test!(
+    custom_function_named_argument,
+    "a {\n  width: double($number: 3);\n}\n",
+    "a {\n  width: 6;\n}\n",
+    grass::Options::default()
+        .function("double($number)", Box::new(Double))
+        .unwrap()
+);
+test!(
+    custom_function_default_and_rest_arguments,
+    "a {\n  b: join(a);\n  c: join(a, b, c, d);\n}\n",
+    "a {\n  b: \"a|2|()\";\n  c: \"a|b|c, d\";\n}\n",
+    grass::Options::default()
+        .function("join($a, $b: 1 + 1, $rest...)", Box::new(Join))
+        .unwrap()
+);
+test!(
+    custom_function_receives_quoted_strings,
+    "a {\n  b: source-length(\"a\");\n  c: source-length(a);\n}\n",
+    "a {\n  b: 3;\n  c: 1;\n}\n",
+    grass::Options::default()
+        .function("source-length($string)", Box::new(SourceLength))
+        .unwrap()
+);
+test!(
+    custom_function_without_parameters,
+    "a {\n  b: join();\n}\n",
+    "a {\n  b: \"\";\n}\n",
+    grass::Options::default()
+        .function("join", Box::new(Join))
+        .unwrap()
+);
+test!(
+    custom_function_underscore_and_hyphen_are_equivalent,
+    "a {\n  width: times_two(2px);\n}\n",
+    "a {\n  width: 4px;\n}\n",
+    grass::Options::default()
+        .function("times-two($number)", Box::new(Double))
+        .unwrap()
+);
+test!(
+    custom_function_overrides_builtin,
+    "a {\n  width: abs(2px);\n}\n",
+    "a {\n  width: 4px;\n}\n",
+    grass::Options::default()
+        .function("abs($number)", Box::new(Double))
+        .unwrap()
+);
+test!(
+    custom_function_is_overridden_by_user_defined_function,
+    "@function double($n) { @return $n; }\na {\n  width: double(2px);\n}\n",
+    "a {\n  width: 2px;\n}\n",
+    grass::Options::default()
+        .function("double($number)", Box::new(Double))
+        .unwrap()
+);
+test!(
+    custom_function_exists,
+    "a {\n  b: function-exists(double);\n  c: call(get-function(double), 1px);\n}\n",
+    "a {\n  b: true;\n  c: 2px;\n}\n",
+    grass::Options::default()
+        .function("double($number)", Box::new(Double))
+        .unwrap()
+);
+
+#[test]
+fn custom_function_error() {
+    let err = grass::from_string(
+        "a {\n  b: fail();\n}\n".to_string(),
+        &grass::Options::default()
+            .function("fail()", Box::new(Fail))
+            .unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!("custom failure", err.message());
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some("stdin"), err.file_name());
+}
+
+#[test]
+fn custom_function_missing_argument() {
+    let err = grass::from_string(
+        "a {\n  b: double();\n}\n".to_string(),
+        &grass::Options::default()
+            .function("double($number)", Box::new(Double))
+            .unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!("Missing argument $number.", err.message());
+}
+
+#[test]
+fn custom_function_invalid_signature() {
+    let err = grass::Options::default()
+        .function("double($number", Box::new(Double))
+        .unwrap_err();
+    assert_eq!(Some("double($number"), err.file_name());
+}
+
+#[test]
+fn custom_function_error_in_default_argument() {
+    let options = grass::Options::default()
+        .function("double($number: 1px + 1em)", Box::new(Double))
+        .unwrap();
+    for _ in 0..2 {
+        let err = grass::from_string("a {\n  b: double();\n}\n".to_string(), &options).unwrap_err();
+        assert_eq!("Incompatible units em and px.", err.message());
+        assert_eq!(Some("double($number: 1px + 1em)"), err.file_name());
+        assert_eq!(Some(10), err.column());
+    }
+}
+
+#[test]
+fn error_location() {
+    let err = grass::from_string(
+        "a {\n  b: 1px + 1em;\n}\n".to_string(),
+        &grass::Options::default(),
+    )
+    .unwrap_err();
+    assert_eq!("Incompatible units em and px.", err.message());
+    assert_eq!(Some("stdin"), err.file_name());
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(6), err.column());
+}
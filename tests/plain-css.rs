@@ -0,0 +1,114 @@
+use std::io::Write;
+
+#[macro_use]
+mod macros;
+
+#[test]
+fn compiles_plain_css_entrypoint() {
+    tempfile!(
+        "compiles_plain_css_entrypoint.css",
+        "@import \"foo\";\na { width: foo(1px, bar); color: rgb(1, 2, 3); }\n@supports (a: b) { b { c: d; } }"
+    );
+    assert_eq!(
+        "@import \"foo\";\na {\n  width: foo(1px, bar);\n  color: rgb(1, 2, 3);\n}\n\n@supports (a: b) {\n  b {\n    c: d;\n  }\n}\n",
+        &grass::from_path(
+            "compiles_plain_css_entrypoint.css",
+            &grass::Options::default()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn plain_css_entrypoint_preserves_calc() {
+    tempfile!(
+        "plain_css_entrypoint_preserves_calc.css",
+        "a { width: calc(1px + 2px); }"
+    );
+    assert_eq!(
+        "a {\n  width: calc(1px + 2px);\n}\n",
+        &grass::from_path(
+            "plain_css_entrypoint_preserves_calc.css",
+            &grass::Options::default()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn plain_css_entrypoint_sass_function() {
+    tempfile!(
+        "plain_css_entrypoint_sass_function.css",
+        "a { color: darken(red, 10%); }"
+    );
+    match grass::from_path(
+        "plain_css_entrypoint_sass_function.css",
+        &grass::Options::default(),
+    ) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e
+            .to_string()
+            .starts_with("Error: This function isn't allowed in plain CSS.")),
+    }
+}
+
+#[test]
+fn plain_css_entrypoint_variable() {
+    tempfile!("plain_css_entrypoint_variable.css", "$a: red;");
+    match grass::from_path(
+        "plain_css_entrypoint_variable.css",
+        &grass::Options::default(),
+    ) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e
+            .to_string()
+            .starts_with("Error: Sass variables aren't allowed in plain CSS.")),
+    }
+}
+
+#[test]
+fn plain_css_entrypoint_operator() {
+    tempfile!("plain_css_entrypoint_operator.css", "a { b: 1 + 2; }");
+    match grass::from_path(
+        "plain_css_entrypoint_operator.css",
+        &grass::Options::default(),
+    ) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e
+            .to_string()
+            .starts_with("Error: Operators aren't allowed in plain CSS.")),
+    }
+}
+
+#[test]
+fn plain_css_entrypoint_operator_in_function_call() {
+    tempfile!(
+        "plain_css_entrypoint_operator_in_function_call.css",
+        "a { b: foo(1 == 2); }"
+    );
+    match grass::from_path(
+        "plain_css_entrypoint_operator_in_function_call.css",
+        &grass::Options::default(),
+    ) {
+        Ok(..) => panic!("did not fail"),
+        Err(e) => assert!(e
+            .to_string()
+            .starts_with("Error: Operators aren't allowed in plain CSS.")),
+    }
+}
+
+#[test]
+fn plain_css_entrypoint_allows_signed_numbers_and_slash() {
+    tempfile!(
+        "plain_css_entrypoint_allows_signed_numbers_and_slash.css",
+        "a { margin: -1px 2px; font: 12px/1.5 a and b; }"
+    );
+    assert_eq!(
+        "a {\n  margin: -1px 2px;\n  font: 12px/1.5 a and b;\n}\n",
+        &grass::from_path(
+            "plain_css_entrypoint_allows_signed_numbers_and_slash.css",
+            &grass::Options::default()
+        )
+        .unwrap()
+    );
+}
@@ -4,22 +4,21 @@ mod macros;
 test!(
     calc_whitespace,
     "a {\n  color: calc(       1      );\n}\n",
-    "a {\n  color: calc( 1 );\n}\n"
+    "a {\n  color: 1;\n}\n"
 );
-test!(
+error!(
     calc_newline,
-    "a {\n  color: calc(\n);\n}\n",
-    "a {\n  color: calc( );\n}\n"
+    "a {\n  color: calc(\n);\n}\n", "Error: Expected number, variable, function, or calculation."
 );
-test!(
+error!(
     calc_multiple_args,
     "a {\n  color: calc(1, 2, a, b, c);\n}\n",
-    "a {\n  color: calc(1, 2, a, b, c);\n}\n"
+    "Error: expected \"+\", \"-\", \"*\", \"/\", or \")\"."
 );
 test!(
-    calc_does_not_evaluate_arithmetic,
+    calc_evaluates_arithmetic,
     "a {\n  color: calc(1 + 2);\n}\n",
-    "a {\n  color: calc(1 + 2);\n}\n"
+    "a {\n  color: 3;\n}\n"
 );
 test!(
     calc_evaluates_interpolated_arithmetic,
@@ -27,59 +26,136 @@ test!(
     "a {\n  color: calc(3);\n}\n"
 );
 test!(
-    calc_retains_silent_comment,
-    "a {\n  color: calc(//);\n}\n",
-    "a {\n  color: calc(//);\n}\n"
+    calc_interpolation_is_unquoted_string,
+    "a {\n  color: calc(#{\"1px + 2%\"});\n}\n",
+    "a {\n  color: calc(1px + 2%);\n}\n"
 );
-test!(
-    calc_retains_multiline_comment,
-    "a {\n  color: calc(/**/);\n}\n",
-    "a {\n  color: calc(/**/);\n}\n"
+error!(
+    calc_multiline_comment_is_not_argument,
+    "a {\n  color: calc(/**/);\n}\n", "Error: Expected number, variable, function, or calculation."
 );
-test!(
-    calc_nested_parens,
+error!(
+    calc_nested_empty_parens,
     "a {\n  color: calc((((()))));\n}\n",
-    "a {\n  color: calc((((()))));\n}\n"
+    "Error: Expected number, variable, function, or calculation."
 );
 test!(
-    calc_invalid_arithmetic,
+    calc_simplifies_compatible_units,
     "a {\n  color: calc(2px + 2px + 5%);\n}\n",
-    "a {\n  color: calc(2px + 2px + 5%);\n}\n"
+    "a {\n  color: calc(4px + 5%);\n}\n"
 );
 test!(
     calc_uppercase,
     "a {\n  color: CALC(1 + 1);\n}\n",
-    "a {\n  color: calc(1 + 1);\n}\n"
+    "a {\n  color: 2;\n}\n"
 );
 test!(
     calc_mixed_casing,
     "a {\n  color: cAlC(1 + 1);\n}\n",
-    "a {\n  color: calc(1 + 1);\n}\n"
+    "a {\n  color: 2;\n}\n"
 );
 test!(
     calc_browser_prefixed,
     "a {\n  color: -webkit-calc(1 + 2);\n}\n",
     "a {\n  color: -webkit-calc(1 + 2);\n}\n"
 );
-test!(
+error!(
     calc_quoted_string,
-    r#"a { color: calc("\ "); }"#,
-    "a {\n  color: calc(\" \");\n}\n"
+    "a {\n  color: calc('a');\n}\n", "Error: Expected number, variable, function, or calculation."
+);
+error!(
+    calc_quoted_string_variable,
+    "$a: \"a\";\na {\n  color: calc($a);\n}\n",
+    "Error: Quoted string \"a\" can't be used in a calculation."
+);
+error!(
+    calc_hash_no_interpolation,
+    "a {\n  color: calc(#);\n}\n", "Error: Expected number, variable, function, or calculation."
+);
+error!(
+    calc_plus_without_whitespace,
+    "a {\n  color: calc(1px+2px);\n}\n",
+    "Error: \"+\" and \"-\" must be surrounded by whitespace in calculations."
+);
+error!(
+    calc_unknown_identifier,
+    "a {\n  color: calc(foo);\n}\n", "Error: Expected \"(\" or \".\"."
 );
 test!(
-    calc_quoted_string_single_quoted_paren,
-    "a {\n  color: calc(\")\");\n}\n",
-    "a {\n  color: calc(\")\");\n}\n"
+    calc_variable,
+    "$a: 1px;\na {\n  color: calc($a + 2%);\n}\n",
+    "a {\n  color: calc(1px + 2%);\n}\n"
 );
 test!(
-    calc_quoted_string_single_quotes,
-    "a {\n  color: calc('a');\n}\n",
-    "a {\n  color: calc(\"a\");\n}\n"
+    calc_function_call,
+    "a {\n  color: calc(1px + abs(-2px));\n}\n",
+    "a {\n  color: 3px;\n}\n"
 );
 test!(
-    calc_hash_no_interpolation,
-    "a {\n  color: calc(#);\n}\n",
-    "a {\n  color: calc(#);\n}\n"
+    calc_css_function,
+    "a {\n  color: calc(var(--a) + 1px);\n}\n",
+    "a {\n  color: calc(var(--a) + 1px);\n}\n"
+);
+test!(
+    calc_nested_calc_is_unwrapped,
+    "a {\n  color: calc(1px + calc(2% + 3px));\n}\n",
+    "a {\n  color: calc(1px + 2% + 3px);\n}\n"
+);
+test!(
+    calc_negative_rhs_flips_operator,
+    "a {\n  color: calc(1px - -2%);\n}\n",
+    "a {\n  color: calc(1px + 2%);\n}\n"
+);
+test!(
+    calc_preserves_necessary_parens,
+    "a {\n  color: calc((1px + 2%) * 2 - (3px + 4%) / 2);\n}\n",
+    "a {\n  color: calc((1px + 2%) * 2 - (3px + 4%) / 2);\n}\n"
+);
+test!(
+    calc_multiplication_and_division,
+    "a {\n  color: calc(1px * 3 / 2);\n}\n",
+    "a {\n  color: 1.5px;\n}\n"
+);
+test!(
+    calc_type_of,
+    "a {\n  color: type-of(calc(1px + 2%));\n}\n",
+    "a {\n  color: calculation;\n}\n"
+);
+test!(
+    calc_equality,
+    "a {\n  color: calc(1px + 2%) == calc(1px + 2%);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+error!(
+    calc_sass_arithmetic,
+    "a {\n  color: calc(1px + 2%) + 1;\n}\n", "Error: Undefined operation \"calc(1px + 2%) + 1\"."
+);
+error!(
+    calc_known_incompatible_units,
+    "a {\n  color: calc(1px + 2s);\n}\n", "Error: 1px and 2s are incompatible."
+);
+error!(
+    calc_unitless_and_unit_incompatible,
+    "a {\n  color: calc(1 + 2px);\n}\n", "Error: 1 and 2px are incompatible."
+);
+test!(
+    clamp_evaluated,
+    "a {\n  color: clamp(1px, 2px, 3px);\n}\n",
+    "a {\n  color: 2px;\n}\n"
+);
+test!(
+    clamp_evaluated_below_min,
+    "a {\n  color: clamp(1cm, 1mm, 3in);\n}\n",
+    "a {\n  color: 1cm;\n}\n"
+);
+test!(
+    clamp_not_evaluated,
+    "a {\n  color: clamp(1px, 4%, 3px);\n}\n",
+    "a {\n  color: clamp(1px, 4%, 3px);\n}\n"
+);
+error!(
+    clamp_too_few_args,
+    "a {\n  color: clamp(1px, 2px);\n}\n", "Error: 3 arguments required, but only 2 were passed."
 );
 test!(
     element_whitespace,
@@ -0,0 +1,91 @@
+use std::{io::Write, thread};
+
+#[macro_use]
+mod macros;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn public_types_are_send_and_sync() {
+    assert_send_sync::<grass::Options>();
+    assert_send_sync::<grass::Compiler>();
+    assert_send_sync::<grass::CompileResult>();
+    assert_send_sync::<grass::Error>();
+}
+
+#[test]
+fn compile_on_many_threads() {
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            thread::spawn(move || {
+                let input = format!(
+                    "@use \"sass:map\";\n$map-{i}: (key-{i}: value-{i});\na-{i} {{ color-{i}: map.get($map-{i}, key-{i}); }}",
+                    i = i
+                );
+                grass::from_string(input, &grass::Options::default()).unwrap()
+            })
+        })
+        .collect();
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(
+            format!("a-{i} {{\n  color-{i}: value-{i};\n}}\n", i = i),
+            handle.join().unwrap()
+        );
+    }
+}
+
+#[test]
+fn compiler_sent_between_threads() {
+    let mut compiler = grass::Compiler::new(grass::Options::default());
+    compiler
+        .compile_string("$compiler-sent: red;".to_string())
+        .unwrap();
+
+    let css = thread::spawn(move || {
+        compiler
+            .compile_string("$compiler-sent: blue; a { color: $compiler-sent; }".to_string())
+            .unwrap()
+            .into_css()
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!("a {\n  color: blue;\n}\n", css);
+}
+
+#[test]
+fn compile_many_preserves_order() {
+    tempfile!("compile_many_preserves_order__a.scss", "a { color: red; }");
+    tempfile!("compile_many_preserves_order__b.scss", "b { color: $b; }");
+    tempfile!("compile_many_preserves_order__c.scss", "c { color: blue; }");
+
+    let results = grass::compile_many(
+        &[
+            "compile_many_preserves_order__a.scss",
+            "compile_many_preserves_order__b.scss",
+            "compile_many_preserves_order__c.scss",
+        ],
+        &grass::Options::default(),
+    );
+
+    assert_eq!(3, results.len());
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        results[0].as_ref().unwrap().css()
+    );
+    assert!(results[1]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .starts_with("Error: Undefined variable."));
+    assert_eq!(
+        "c {\n  color: blue;\n}\n",
+        results[2].as_ref().unwrap().css()
+    );
+}
+
+#[test]
+fn compile_many_empty() {
+    assert!(grass::compile_many(&[], &grass::Options::default()).is_empty());
+}
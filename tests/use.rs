@@ -337,3 +337,254 @@ fn use_variable_declaration_between_use() {
         &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
     );
 }
+
+#[test]
+fn use_same_module_twice_emits_css_once() {
+    let input = "@use \"use_same_module_twice_emits_css_once\" as a;\n@use \"use_same_module_twice_emits_css_once\" as b;\nc {\n  color: a.$a b.$a;\n}";
+    tempfile!(
+        "use_same_module_twice_emits_css_once.scss",
+        "$a: red; a { color: $a; }"
+    );
+    assert_eq!(
+        "a {\n  color: red;\n}\n\nc {\n  color: red red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn configure_already_loaded_module() {
+    let input = "@use \"configure_already_loaded_module\" as a;\n@use \"configure_already_loaded_module\" as b with ($a: green);";
+    tempfile!("configure_already_loaded_module.scss", "$a: red !default;");
+
+    assert_err!(
+        "Error: This module was already loaded, so it can't be configured using \"with\".",
+        input
+    );
+}
+
+#[test]
+fn use_module_and_module_dependency_use_same_builtin() {
+    let input = "@use \"sass:math\";\n@use \"use_module_and_module_dependency_use_same_builtin\" as lib;\na {\n color: math.abs(-1) lib.$a;\n}";
+    tempfile!(
+        "use_module_and_module_dependency_use_same_builtin.scss",
+        "@use \"sass:math\";\n$a: math.abs(-2);"
+    );
+    assert_eq!(
+        "a {\n  color: 1 2;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn module_namespaces_are_not_visible_to_user() {
+    let input =
+        "@use \"module_namespaces_are_not_visible_to_user\";\na {\n color: math.abs(-1);\n}";
+    tempfile!(
+        "module_namespaces_are_not_visible_to_user.scss",
+        "@use \"sass:math\";"
+    );
+
+    assert_err!(
+        "Error: There is no module with the namespace \"math\".",
+        input
+    );
+}
+
+#[test]
+fn module_function_uses_module_namespaces() {
+    let input = "@use \"module_function_uses_module_namespaces\" as lib;\na {\n color: lib.absolute(-3);\n}";
+    tempfile!(
+        "module_function_uses_module_namespaces.scss",
+        "@use \"sass:math\";\n$_b: 2;\n@function absolute($a) { @return math.abs($a) + $_b; }"
+    );
+    assert_eq!(
+        "a {\n  color: 5;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn module_mixin_content_block_uses_user_scope() {
+    let input = "@use \"module_mixin_content_block_uses_user_scope\" as lib;\n$a: red;\na {\n @include lib.foo {\n  color: $a;\n }\n}";
+    tempfile!(
+        "module_mixin_content_block_uses_user_scope.scss",
+        "$a: green;\n@mixin foo { display: $a; @content; }"
+    );
+    assert_eq!(
+        "a {\n  display: green;\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn use_as_star_does_not_expose_private_members() {
+    let input = "@use \"use_as_star_does_not_expose_private_members\" as *;\na {\n color: $-a;\n}";
+    tempfile!(
+        "use_as_star_does_not_expose_private_members.scss",
+        "$-a: red;"
+    );
+
+    assert_err!("Error: Undefined variable.", input);
+}
+
+#[test]
+fn extend_applies_to_upstream_module() {
+    let input = "@use \"extend_applies_to_upstream_module\";\na {\n @extend %foo;\n}";
+    tempfile!(
+        "extend_applies_to_upstream_module.scss",
+        "%foo { color: red; }"
+    );
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn extend_target_only_in_downstream_module_is_not_found() {
+    let input =
+        "@use \"extend_target_only_in_downstream_module_is_not_found\";\n.b {\n color: red;\n}";
+    tempfile!(
+        "extend_target_only_in_downstream_module_is_not_found.scss",
+        "a { @extend .b; }"
+    );
+    assert_err!("Error: The target selector was not found.", input);
+}
+
+#[test]
+fn extend_does_not_apply_to_downstream_module() {
+    let input = "@use \"extend_does_not_apply_to_downstream_module\";\n.b {\n color: red;\n}";
+    tempfile!(
+        "extend_does_not_apply_to_downstream_module.scss",
+        "a { @extend .b !optional; }"
+    );
+    assert_eq!(
+        ".b {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn extend_does_not_apply_to_unrelated_module() {
+    let input = "@use \"extend_does_not_apply_to_unrelated_module__a\";\n@use \"extend_does_not_apply_to_unrelated_module__b\";";
+    tempfile!(
+        "extend_does_not_apply_to_unrelated_module__a.scss",
+        ".a { @extend .c !optional; }"
+    );
+    tempfile!(
+        "extend_does_not_apply_to_unrelated_module__b.scss",
+        ".c { color: red; }"
+    );
+    assert_eq!(
+        ".c {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn use_plain_css_file() {
+    let input = "@use \"use_plain_css_file\";\na {\n color: red;\n}";
+    tempfile!("use_plain_css_file.css", "b { color: blue; }");
+    assert_eq!(
+        "b {\n  color: blue;\n}\n\na {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn use_plain_css_file_with_variable() {
+    let input = "@use \"use_plain_css_file_with_variable\";";
+    tempfile!("use_plain_css_file_with_variable.css", "$a: red;");
+    assert_err!("Error: Sass variables aren't allowed in plain CSS.", input);
+}
+
+#[test]
+fn use_plain_css_file_with_nesting() {
+    let input = "@use \"use_plain_css_file_with_nesting\";";
+    tempfile!(
+        "use_plain_css_file_with_nesting.css",
+        "a { b { color: red; } }"
+    );
+    assert_err!("Error: Nested rules aren't allowed in plain CSS.", input);
+}
+
+#[test]
+fn use_plain_css_file_with_mixin() {
+    let input = "@use \"use_plain_css_file_with_mixin\";";
+    tempfile!("use_plain_css_file_with_mixin.css", "@mixin foo {}");
+    assert_err!("Error: This at-rule isn't allowed in plain CSS.", input);
+}
+
+#[test]
+fn use_ignores_import_only_file() {
+    let input = "@use \"use_ignores_import_only_file\";";
+    tempfile!("_use_ignores_import_only_file.scss", "a { color: red; }");
+    tempfile!(
+        "_use_ignores_import_only_file.import.scss",
+        "a { color: blue; }"
+    );
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn module_variable_assignment_is_seen_by_other_modules() {
+    let input = "@use \"module_variable_assignment_setter\";\n@use \"module_variable_assignment_reader\" as reader;\na {\n  color: reader.$x;\n}";
+    tempfile!(
+        "module_variable_assignment_tokens.scss",
+        "$x: 1;\n@function get-x() { @return $x; }"
+    );
+    tempfile!(
+        "module_variable_assignment_setter.scss",
+        "@use \"module_variable_assignment_tokens\" as tokens;\ntokens.$x: 5;"
+    );
+    tempfile!(
+        "module_variable_assignment_reader.scss",
+        "@use \"module_variable_assignment_tokens\" as tokens;\n$x: tokens.$x tokens.get-x();"
+    );
+    assert_eq!(
+        "a {\n  color: 5 5;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn module_global_assignment_in_mixin_is_seen_by_namespace() {
+    let input = "@use \"module_global_assignment_in_mixin\" as tokens;\n@include tokens.set-x;\na {\n  color: tokens.$x tokens.get-x();\n}";
+    tempfile!(
+        "module_global_assignment_in_mixin.scss",
+        "$x: 1;\n@mixin set-x { $x: 2 !global; }\n@function get-x() { @return $x; }"
+    );
+    assert_eq!(
+        "a {\n  color: 2 2;\n}\n",
+        &grass::from_string(input.to_string(), &grass::Options::default()).expect(input)
+    );
+}
+
+#[test]
+fn use_module_loop() {
+    let input = "@use \"use_module_loop_a\";";
+    tempfile!("use_module_loop_a.scss", "@use \"use_module_loop_b\";");
+    tempfile!("use_module_loop_b.scss", "@use \"use_module_loop_a\";");
+
+    assert_err!(
+        "Error: Module loop: this module is already being loaded.",
+        input
+    );
+}
+
+#[test]
+fn use_module_loop_on_itself() {
+    let input = "@use \"use_module_loop_on_itself\";";
+    tempfile!(
+        "use_module_loop_on_itself.scss",
+        "@use \"use_module_loop_on_itself\";"
+    );
+
+    assert_err!(
+        "Error: Module loop: this module is already being loaded.",
+        input
+    );
+}
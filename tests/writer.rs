@@ -0,0 +1,188 @@
+use std::io::{self, Write};
+
+#[macro_use]
+mod macros;
+
+fn compile_to_vec(input: &str, options: &grass::Options) -> grass::Result<String> {
+    let mut output = Vec::new();
+    grass::from_reader_to_writer(input.as_bytes(), &mut output, options)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn reader_to_writer() {
+    let input =
+        "a {\n  color: red;\n  b {\n    c: d;\n  }\n}\n@media screen {\n  e {\n    f: g;\n  }\n}\n";
+    assert_eq!(
+        grass::from_string(input.to_string(), &grass::Options::default()).unwrap(),
+        compile_to_vec(input, &grass::Options::default()).unwrap()
+    );
+}
+
+#[test]
+fn writer_emits_charset_first() {
+    assert_eq!(
+        "@charset \"UTF-8\";\na {\n  b: c;\n}\n\nd {\n  e: \"☃\";\n}\n",
+        compile_to_vec("a { b: c; }\nd { e: \"☃\"; }", &grass::Options::default()).unwrap()
+    );
+}
+
+#[test]
+fn writer_emits_charset_for_non_ascii_in_nested_rules() {
+    assert_eq!(
+        "@charset \"UTF-8\";\n@media screen {\n  a {\n    b: \"☃\";\n  }\n}\n",
+        compile_to_vec(
+            "@media screen { a { b: \"☃\"; } }",
+            &grass::Options::default()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn writer_without_charset() {
+    assert_eq!(
+        "a {\n  b: \"☃\";\n}\n",
+        compile_to_vec(
+            "a { b: \"☃\"; }",
+            &grass::Options::default().allows_charset(false)
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn writer_uses_precision() {
+    assert_eq!(
+        "a {\n  b: 0.333;\n}\n",
+        compile_to_vec("a { b: (1/3); }", &grass::Options::default().precision(3)).unwrap()
+    );
+}
+
+#[test]
+fn path_to_writer() {
+    tempfile!("writer_path_to_writer.scss", "a { b { c: d; } }");
+    let mut output = Vec::new();
+    grass::from_path_to_writer(
+        "writer_path_to_writer.scss",
+        &mut output,
+        &grass::Options::default(),
+    )
+    .unwrap();
+    assert_eq!(b"a b {\n  c: d;\n}\n".to_vec(), output);
+}
+
+#[test]
+fn writer_max_output_size() {
+    let err = compile_to_vec(
+        "a { b: c; }\nd { e: f; }",
+        &grass::Options::default().max_output_size(20),
+    )
+    .unwrap_err();
+    assert_eq!(
+        "Error: Maximum output size (20 bytes) exceeded.",
+        err.to_string().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn writer_evaluation_error() {
+    let err = compile_to_vec("a { b: 1px + 1em; }", &grass::Options::default()).unwrap_err();
+    assert_eq!(
+        "Error: Incompatible units em and px.",
+        err.to_string().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn reader_invalid_utf8() {
+    let mut output = Vec::new();
+    assert!(grass::from_reader_to_writer(
+        &[b'a', b' ', 0xff, b'{', b'}'][..],
+        &mut output,
+        &grass::Options::default()
+    )
+    .is_err());
+    assert!(output.is_empty());
+}
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writer_io_error() {
+    let err = grass::from_reader_to_writer(
+        "a { b: c; }".as_bytes(),
+        FailingWriter,
+        &grass::Options::default(),
+    )
+    .unwrap_err();
+    assert_eq!("Error: disk full", err.to_string().trim_end());
+}
+
+/// Accepts a single write, then fails, recording what it was given
+#[derive(Default)]
+struct FailsAfterFirstWrite {
+    written: Vec<u8>,
+}
+
+impl Write for &mut FailsAfterFirstWrite {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.written.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+        }
+
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writer_streams_ascii_output() {
+    let mut output = FailsAfterFirstWrite::default();
+    let err = grass::from_reader_to_writer(
+        "a { b: c; }\nd { e: f; }".as_bytes(),
+        &mut output,
+        &grass::Options::default(),
+    )
+    .unwrap_err();
+    assert_eq!("Error: disk full", err.to_string().trim_end());
+    assert!(b"a {\n  b: c;\n}\n".starts_with(&output.written));
+}
+
+#[test]
+fn writer_streams_non_ascii_output() {
+    let mut output = FailsAfterFirstWrite::default();
+    grass::from_reader_to_writer(
+        "a { b: \"☃\"; }".as_bytes(),
+        &mut output,
+        &grass::Options::default(),
+    )
+    .unwrap_err();
+    assert_eq!(b"@charset \"UTF-8\";\n".to_vec(), output.written);
+}
+
+#[test]
+fn writer_charset_ignores_omitted_rules() {
+    assert_eq!(
+        "a {\n  b: c;\n}\n",
+        compile_to_vec(
+            "%☃ { b: c; }\na { b: c; }\n@media ☃ { d {} }",
+            &grass::Options::default()
+        )
+        .unwrap()
+    );
+}